@@ -0,0 +1,76 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use mfro_snake::env::SnakeEnv;
+
+const WIDTH: usize = 50;
+const HEIGHT: usize = 40;
+const LENGTHS: [usize; 3] = [10, 500, 1800];
+
+fn setup(length: usize) -> SnakeEnv {
+    let mut env = SnakeEnv::with_size(WIDTH, HEIGHT);
+    env.reset(0);
+    env.set_length(length);
+    env
+}
+
+fn movement(c: &mut Criterion) {
+    let mut group = c.benchmark_group("movement");
+
+    for length in LENGTHS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(length),
+            &length,
+            |b, &length| {
+                b.iter_batched(
+                    || setup(length),
+                    |mut env| env.advance(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision");
+
+    for length in LENGTHS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(length),
+            &length,
+            |b, &length| {
+                b.iter_batched(
+                    || setup(length),
+                    |mut env| env.collide(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn food_placement(c: &mut Criterion) {
+    let mut group = c.benchmark_group("food_placement");
+
+    for length in LENGTHS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(length),
+            &length,
+            |b, &length| {
+                b.iter_batched(
+                    || setup(length),
+                    |mut env| env.place_food(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, movement, collision, food_placement);
+criterion_main!(benches);
@@ -0,0 +1,110 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub const DEFAULT_PORT: u16 = 7878;
+// the server holds the first frame back while the clients count down, so
+// this has to match the game's start countdown
+pub const START_DELAY_MS: u64 = 3000;
+pub const ROOM_CODE_LENGTH: usize = 4;
+pub const MAX_NAME_LENGTH: usize = 12;
+// the tick rates a match can ask for. faster would flood everyone, and slower
+// would leave a match that barely moves
+pub const MIN_TICK_MS: u64 = 20;
+pub const MAX_TICK_MS: u64 = 1000;
+// the longest setup a match takes, which is plenty for a level on the largest
+// board the share codes allow
+pub const MAX_SETUP_LENGTH: usize = 256 * 1024;
+
+// a client joins a room by its code, or makes a new one, and the match
+// starts once all the players in it are ready. every client then runs the whole game
+// itself from the same seed and setup, so only turns go over the wire. the
+// server decides which tick each one lands on and sends every tick's turns
+// out as a frame, and the clients only treat a tick as settled once its
+// frame is in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClientMessage {
+    // `setup` describes the game to play and is only read by the game, the
+    // first player in the room picks it for the match
+    Join {
+        name: String,
+        room: Option<String>,
+        role: Role,
+        setup: String,
+        tick_ms: u64,
+    },
+    Ready(bool),
+    // a turn the client would like on `tick`, or on the next frame if that
+    // one has gone out already
+    Input {
+        tick: u32,
+        direction: u8,
+    },
+}
+
+// spectators get every frame of the match but have no snake in it, and
+// don't hold the start back
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Role {
+    #[default]
+    Player,
+    Spectator,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Member {
+    pub name: String,
+    pub role: Role,
+    pub ready: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerMessage {
+    // who's in the room, the players in player order and then the
+    // spectators, whenever that changes
+    Room {
+        code: String,
+        members: Vec<Member>,
+    },
+    // the join was turned down
+    Refused(String),
+    // spectators have no player, and those who come in late get every
+    // frame so far straight after
+    Start {
+        seed: u64,
+        players: usize,
+        player: Option<usize>,
+        setup: String,
+    },
+    // the turns on a tick as (player, direction), sent for every tick in order
+    Frame {
+        tick: u32,
+        turns: Vec<(u8, u8)>,
+    },
+}
+
+// between peers playing without a server, each standing in for the server
+// for its own turns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PeerMessage {
+    // sent to every peer that connects, and again whenever it changes
+    Member(Member),
+    // from the first peer in the room, picking the game for everyone
+    Hello {
+        seed: u64,
+        setup: String,
+        tick_ms: u64,
+    },
+    // the sender's turn on a tick, sent for every tick even without one
+    Turn {
+        tick: u32,
+        direction: Option<u8>,
+    },
+}
+
+// messages go as ron text frames
+pub fn encode(message: &impl Serialize) -> String {
+    ron::to_string(message).expect("protocol messages always serialize")
+}
+
+pub fn decode<T: DeserializeOwned>(text: &str) -> Option<T> {
+    ron::from_str(text).ok()
+}
@@ -0,0 +1,188 @@
+use snake_protocol::{decode, encode, ClientMessage, Member, PeerMessage, Role, ServerMessage};
+
+#[test]
+fn round_trips_a_join() {
+    let message = ClientMessage::Join {
+        name: "ana".to_owned(),
+        room: Some("ABCD".to_owned()),
+        role: Role::Player,
+        setup: "(seed:1)".to_owned(),
+        tick_ms: 120,
+    };
+
+    let Some(ClientMessage::Join {
+        name,
+        room,
+        role,
+        setup,
+        tick_ms,
+    }) = decode(&encode(&message))
+    else {
+        panic!("not a join");
+    };
+
+    assert_eq!(name, "ana");
+    assert_eq!(room.as_deref(), Some("ABCD"));
+    assert_eq!(role, Role::Player);
+    assert_eq!(setup, "(seed:1)");
+    assert_eq!(tick_ms, 120);
+}
+
+#[test]
+fn round_trips_an_input() {
+    let message = ClientMessage::Input {
+        tick: 42,
+        direction: 3,
+    };
+
+    assert!(matches!(
+        decode(&encode(&message)),
+        Some(ClientMessage::Input {
+            tick: 42,
+            direction: 3
+        })
+    ));
+}
+
+#[test]
+fn round_trips_a_frame() {
+    let message = ServerMessage::Frame {
+        tick: 7,
+        turns: vec![(0, 1), (1, 2)],
+    };
+
+    let Some(ServerMessage::Frame { tick, turns }) = decode(&encode(&message)) else {
+        panic!("not a frame");
+    };
+
+    assert_eq!(tick, 7);
+    assert_eq!(turns, [(0, 1), (1, 2)]);
+}
+
+#[test]
+fn round_trips_peer_turns() {
+    for direction in [Some(2), None] {
+        let message = PeerMessage::Turn { tick: 9, direction };
+
+        let Some(PeerMessage::Turn {
+            tick,
+            direction: decoded,
+        }) = decode(&encode(&message))
+        else {
+            panic!("not a turn");
+        };
+
+        assert_eq!(tick, 9);
+        assert_eq!(decoded, direction);
+    }
+}
+
+#[test]
+fn round_trips_a_hello() {
+    let message = PeerMessage::Hello {
+        seed: u64::MAX,
+        setup: "(seed:1)".to_owned(),
+        tick_ms: 80,
+    };
+
+    let Some(PeerMessage::Hello {
+        seed,
+        setup,
+        tick_ms,
+    }) = decode(&encode(&message))
+    else {
+        panic!("not a hello");
+    };
+
+    assert_eq!(seed, u64::MAX);
+    assert_eq!(setup, "(seed:1)");
+    assert_eq!(tick_ms, 80);
+}
+
+#[test]
+fn round_trips_a_room() {
+    let members = vec![
+        Member {
+            name: "ana".to_owned(),
+            role: Role::Player,
+            ready: true,
+        },
+        Member {
+            name: "bo".to_owned(),
+            role: Role::Player,
+            ready: false,
+        },
+    ];
+    let message = ServerMessage::Room {
+        code: "ABCD".to_owned(),
+        members: members.clone(),
+    };
+
+    let Some(ServerMessage::Room {
+        code,
+        members: decoded,
+    }) = decode(&encode(&message))
+    else {
+        panic!("not a room");
+    };
+
+    assert_eq!(code, "ABCD");
+    assert_eq!(decoded, members);
+
+    // peers without a server send their own member instead
+    let message = PeerMessage::Member(members[1].clone());
+
+    let Some(PeerMessage::Member(member)) = decode(&encode(&message)) else {
+        panic!("not a member");
+    };
+
+    assert_eq!(member, members[1]);
+}
+
+#[test]
+fn spectators_join_and_start_without_a_player() {
+    let message = ClientMessage::Join {
+        name: "cy".to_owned(),
+        room: Some("ABCD".to_owned()),
+        role: Role::Spectator,
+        setup: String::new(),
+        tick_ms: 120,
+    };
+
+    assert!(matches!(
+        decode(&encode(&message)),
+        Some(ClientMessage::Join {
+            role: Role::Spectator,
+            ..
+        })
+    ));
+
+    let message = ServerMessage::Start {
+        seed: 1,
+        players: 2,
+        player: None,
+        setup: String::new(),
+    };
+
+    assert!(matches!(
+        decode(&encode(&message)),
+        Some(ServerMessage::Start {
+            players: 2,
+            player: None,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn lists_players_before_spectators() {
+    assert_eq!(Role::default(), Role::Player);
+    assert!(Role::Player < Role::Spectator);
+}
+
+#[test]
+fn refuses_garbage() {
+    assert!(decode::<ClientMessage>("Join(").is_none());
+    assert!(decode::<ServerMessage>("").is_none());
+    assert!(decode::<ServerMessage>(&encode(&ClientMessage::Ready(true))).is_none());
+}
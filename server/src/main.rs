@@ -0,0 +1,517 @@
+use std::{collections::HashMap, time::Duration};
+
+use clap::Parser;
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use snake_protocol::{
+    decode, encode, ClientMessage, Member, Role, ServerMessage, DEFAULT_PORT, MAX_NAME_LENGTH,
+    MAX_SETUP_LENGTH, MAX_TICK_MS, MIN_TICK_MS, ROOM_CODE_LENGTH, START_DELAY_MS,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, Sender},
+};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+// how far ahead of the frames a client may ask for a turn. clients only
+// guess a few ticks ahead, so anything further is held back for nothing
+const MAX_INPUT_AHEAD: u32 = 32;
+// frames kept for spectators who come in late. a match that has gone on
+// longer can't be watched from the start any more
+const MAX_FRAMES: usize = 30_000;
+// messages waiting to go out to a client. a client that falls this far
+// behind is dropped rather than buffered for without end, and a late
+// spectator's catch-up has to fit
+const OUTBOX_SIZE: usize = MAX_FRAMES + 256;
+// messages from all clients waiting on the lobby, and inputs waiting on a
+// match, past which clients are held back or inputs dropped
+const EVENT_QUEUE: usize = 1024;
+const INPUT_QUEUE: usize = 256;
+
+/// Relay server for online snake matches
+#[derive(Parser)]
+struct Cli {
+    /// Address to listen on
+    #[arg(long, default_value_t = format!("0.0.0.0:{DEFAULT_PORT}"))]
+    bind: String,
+
+    /// Most players a room takes, not counting spectators
+    #[arg(long, default_value_t = 4)]
+    max_players: usize,
+}
+
+type Outbox = Sender<ServerMessage>;
+// turns for a match as (player, tick, direction)
+type Inputs = Sender<(usize, u32, u8)>;
+
+enum Event {
+    Connected(usize, Outbox),
+    Message(usize, ClientMessage),
+    Left(usize),
+    // the match in the room with this code is over
+    Ended(String),
+}
+
+struct Seat {
+    client: usize,
+    member: Member,
+    setup: String,
+    tick_ms: u64,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    let listener = match TcpListener::bind(&cli.bind).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("error: failed to listen on {}: {e}", cli.bind);
+            std::process::exit(1);
+        }
+    };
+
+    println!("listening on {}", cli.bind);
+
+    let (events, receiver) = mpsc::channel(EVENT_QUEUE);
+    tokio::spawn(lobby(receiver, events.clone(), cli.max_players));
+
+    for client in 0.. {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+
+        tokio::spawn(connection(stream, client, events.clone()));
+    }
+}
+
+// passes a client's messages on to the lobby, and the lobby's and its
+// match's messages back
+async fn connection(stream: TcpStream, client: usize, events: Sender<Event>) {
+    let Ok(socket) = accept_async(stream).await else {
+        return;
+    };
+
+    let (mut sink, mut stream) = socket.split();
+    let (outbox, mut outgoing) = mpsc::channel(OUTBOX_SIZE);
+
+    if events.send(Event::Connected(client, outbox)).await.is_err() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        while let Some(message) = outgoing.recv().await {
+            if sink.send(Message::Text(encode(&message))).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(Ok(message)) = stream.next().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+
+        // a busy lobby holds the client back here rather than queueing up
+        // whatever it sends
+        if let Some(message) = decode(&text) {
+            if events.send(Event::Message(client, message)).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    let _ = events.send(Event::Left(client)).await;
+}
+
+// where a playing client's inputs go
+struct Playing {
+    inputs: Inputs,
+    player: usize,
+    // the code of the room the match was started from
+    code: String,
+}
+
+// the rooms waiting to start, and where the inputs of clients in a match go
+struct Lobby {
+    // matches report back here when they end
+    events: Sender<Event>,
+    outboxes: HashMap<usize, Outbox>,
+    rooms: HashMap<String, Vec<Seat>>,
+    playing: HashMap<usize, Playing>,
+    // matches under way by their room's code, taking late spectators
+    matches: HashMap<String, Sender<Outbox>>,
+}
+
+async fn lobby(mut events: Receiver<Event>, ended: Sender<Event>, max_players: usize) {
+    let mut lobby = Lobby {
+        events: ended,
+        outboxes: HashMap::new(),
+        rooms: HashMap::new(),
+        playing: HashMap::new(),
+        matches: HashMap::new(),
+    };
+
+    while let Some(event) = events.recv().await {
+        match event {
+            Event::Connected(client, outbox) => {
+                lobby.outboxes.insert(client, outbox);
+            }
+            Event::Message(
+                client,
+                ClientMessage::Join {
+                    name,
+                    room,
+                    role,
+                    setup,
+                    tick_ms,
+                },
+            ) => {
+                let seat = Seat {
+                    client,
+                    member: Member {
+                        name,
+                        role,
+                        ready: false,
+                    },
+                    setup,
+                    tick_ms,
+                };
+
+                lobby.join(seat, room, max_players);
+            }
+            Event::Message(client, ClientMessage::Ready(ready)) => lobby.ready(client, ready),
+            Event::Message(client, ClientMessage::Input { tick, direction }) => {
+                // a match that's behind on inputs drops the newest
+                if let Some(playing) = lobby.playing.get(&client) {
+                    let _ = playing.inputs.try_send((playing.player, tick, direction));
+                }
+            }
+            Event::Left(client) => lobby.leave(client),
+            Event::Ended(code) => lobby.end(&code),
+        }
+    }
+}
+
+impl Lobby {
+    fn room_of(&self, client: usize) -> Option<String> {
+        self.rooms
+            .iter()
+            .find(|(_, seats)| seats.iter().any(|s| s.client == client))
+            .map(|(code, _)| code.clone())
+    }
+
+    fn send(&self, client: usize, message: ServerMessage) {
+        if let Some(outbox) = self.outboxes.get(&client) {
+            let _ = outbox.try_send(message);
+        }
+    }
+
+    // a code no room or match has yet
+    fn new_code(&self) -> String {
+        let mut rng = rand::thread_rng();
+
+        loop {
+            let code: String = (0..ROOM_CODE_LENGTH)
+                .map(|_| rng.gen_range(b'A'..=b'Z') as char)
+                .collect();
+
+            if !self.rooms.contains_key(&code) && !self.matches.contains_key(&code) {
+                return code;
+            }
+        }
+    }
+
+    fn join(&mut self, mut seat: Seat, room: Option<String>, max_players: usize) {
+        let client = seat.client;
+
+        if self.room_of(client).is_some() || self.playing.contains_key(&client) {
+            self.send(
+                client,
+                ServerMessage::Refused("already in a room or a match".to_owned()),
+            );
+            return;
+        }
+
+        self.matches.retain(|_, spectators| !spectators.is_closed());
+
+        let code = room.map(|code| code.trim().to_uppercase());
+
+        // spectators can still come in once the match has started
+        if seat.member.role == Role::Spectator {
+            if let (Some(spectators), Some(outbox)) = (
+                code.as_ref().and_then(|code| self.matches.get(code)),
+                self.outboxes.get(&client),
+            ) {
+                let _ = spectators.try_send(outbox.clone());
+                return;
+            }
+        }
+
+        // nothing is made for a join that's turned down
+        let refused = match code.as_ref().map(|code| (code, self.rooms.get(code))) {
+            _ if seat.setup.len() > MAX_SETUP_LENGTH => Some("that game is too big to play online"),
+            None => None,
+            Some((code, None)) if self.matches.contains_key(code) => {
+                Some("that match has already started")
+            }
+            Some((_, None)) => Some("no room with that code"),
+            Some((_, Some(seats)))
+                if seat.member.role == Role::Player && players(seats).count() >= max_players =>
+            {
+                Some("that room is full")
+            }
+            Some(_) => None,
+        };
+
+        if let Some(reason) = refused {
+            self.send(client, ServerMessage::Refused(reason.to_owned()));
+            return;
+        }
+
+        let code = code.unwrap_or_else(|| self.new_code());
+        let seats = self.rooms.entry(code.clone()).or_default();
+
+        seat.member.name = seat
+            .member
+            .name
+            .trim()
+            .chars()
+            .take(MAX_NAME_LENGTH)
+            .collect();
+        if seat.member.name.is_empty() {
+            seat.member.name = format!("player {}", seats.len() + 1);
+        }
+
+        // spectators have nothing to get ready for
+        seat.member.ready = seat.member.role == Role::Spectator;
+
+        seats.push(seat);
+        self.update(&code);
+    }
+
+    fn ready(&mut self, client: usize, ready: bool) {
+        let Some(code) = self.room_of(client) else {
+            return;
+        };
+
+        if let Some(seat) = self
+            .rooms
+            .get_mut(&code)
+            .and_then(|seats| seats.iter_mut().find(|s| s.client == client))
+            .filter(|seat| seat.member.role == Role::Player)
+        {
+            seat.member.ready = ready;
+        }
+
+        self.update(&code);
+    }
+
+    fn leave(&mut self, client: usize) {
+        self.outboxes.remove(&client);
+        self.playing.remove(&client);
+
+        let Some(code) = self.room_of(client) else {
+            return;
+        };
+
+        if let Some(seats) = self.rooms.get_mut(&code) {
+            seats.retain(|s| s.client != client);
+        }
+
+        self.update(&code);
+    }
+
+    // everyone in the room hears who's in it, and once the players are all
+    // ready the room becomes a match
+    fn update(&mut self, code: &str) {
+        let Some(seats) = self.rooms.get(code) else {
+            return;
+        };
+
+        if seats.is_empty() {
+            self.rooms.remove(code);
+            return;
+        }
+
+        let mut members: Vec<_> = seats.iter().map(|s| s.member.clone()).collect();
+        members.sort_by_key(|m| m.role);
+
+        for seat in seats.iter() {
+            self.send(
+                seat.client,
+                ServerMessage::Room {
+                    code: code.to_owned(),
+                    members: members.clone(),
+                },
+            );
+        }
+
+        if players(seats).count() < 2 || !seats.iter().all(|s| s.member.ready) {
+            return;
+        }
+
+        let Some(seats) = self.rooms.remove(code) else {
+            return;
+        };
+
+        let (inputs, receiver) = mpsc::channel(INPUT_QUEUE);
+        let (spectators, joining) = mpsc::channel(EVENT_QUEUE);
+        let mut outboxes = vec![];
+
+        for (player, seat) in players(&seats).enumerate() {
+            self.playing.insert(
+                seat.client,
+                Playing {
+                    inputs: inputs.clone(),
+                    player,
+                    code: code.to_owned(),
+                },
+            );
+            outboxes.extend(self.outboxes.get(&seat.client).cloned());
+        }
+
+        for seat in seats.iter().filter(|s| s.member.role == Role::Spectator) {
+            if let Some(outbox) = self.outboxes.get(&seat.client) {
+                let _ = spectators.try_send(outbox.clone());
+            }
+        }
+
+        self.matches.insert(code.to_owned(), spectators);
+
+        // the match plays what the first player in the room asked for
+        let Some(first) = players(&seats).next() else {
+            return;
+        };
+        let interval = Duration::from_millis(first.tick_ms.clamp(MIN_TICK_MS, MAX_TICK_MS));
+
+        let events = self.events.clone();
+        let code = code.to_owned();
+        let setup = first.setup.clone();
+
+        tokio::spawn(async move {
+            play(outboxes, setup, interval, receiver, joining).await;
+            let _ = events.send(Event::Ended(code)).await;
+        });
+    }
+
+    // the players are free to join another room, and late spectators are
+    // turned away
+    fn end(&mut self, code: &str) {
+        self.playing.retain(|_, playing| playing.code != code);
+        self.matches.remove(code);
+    }
+}
+
+fn players(seats: &[Seat]) -> impl Iterator<Item = &Seat> {
+    seats.iter().filter(|s| s.member.role == Role::Player)
+}
+
+async fn play(
+    outboxes: Vec<Outbox>,
+    setup: String,
+    interval: Duration,
+    mut inputs: Receiver<(usize, u32, u8)>,
+    mut joining: Receiver<Outbox>,
+) {
+    let seed = rand::random();
+    let players = outboxes.len();
+
+    for (player, outbox) in outboxes.iter().enumerate() {
+        let _ = outbox.try_send(ServerMessage::Start {
+            seed,
+            players,
+            player: Some(player),
+            setup: setup.clone(),
+        });
+    }
+
+    println!("match of {players} started on seed {seed}");
+
+    tokio::time::sleep(Duration::from_millis(START_DELAY_MS)).await;
+
+    let mut clock = tokio::time::interval(interval);
+    let mut outboxes: Vec<_> = outboxes.into_iter().map(Some).collect();
+    let mut pending: Vec<(usize, u32, u8)> = vec![];
+    // every frame so far, for spectators who come in late, until there are
+    // too many to keep
+    let mut frames: Vec<ServerMessage> = vec![];
+    let mut spectators: Vec<Outbox> = vec![];
+
+    for tick in 0u32.. {
+        clock.tick().await;
+
+        // a turn asked for too far ahead would sit here for the whole match,
+        // and a player gets no more turns held than it could ever use
+        while let Ok(input) = inputs.try_recv() {
+            let (player, wanted, _) = input;
+            let held = pending.iter().filter(|&&(p, _, _)| p == player).count();
+
+            if wanted <= tick.saturating_add(MAX_INPUT_AHEAD) && held < MAX_INPUT_AHEAD as usize {
+                pending.push(input);
+            }
+        }
+
+        while let Ok(outbox) = joining.try_recv() {
+            if frames.len() >= MAX_FRAMES {
+                let _ = outbox.try_send(ServerMessage::Refused(
+                    "that match has gone on too long to watch".to_owned(),
+                ));
+                continue;
+            }
+
+            let _ = outbox.try_send(ServerMessage::Start {
+                seed,
+                players,
+                player: None,
+                setup: setup.clone(),
+            });
+
+            for frame in frames.iter() {
+                let _ = outbox.try_send(frame.clone());
+            }
+
+            spectators.push(outbox);
+        }
+
+        // one turn per player a frame, in the order they came in. turns asked
+        // for on a tick that has gone out already land on this one
+        let mut turns: Vec<(u8, u8)> = vec![];
+        pending.retain(|&(player, wanted, direction)| {
+            if wanted > tick || turns.iter().any(|&(p, _)| p as usize == player) {
+                return true;
+            }
+
+            turns.push((player as u8, direction));
+            false
+        });
+
+        let frame = ServerMessage::Frame { tick, turns };
+
+        // a player whose frames have backed up that far is as good as gone,
+        // a missed frame would leave its game out of step for good
+        for outbox in outboxes.iter_mut() {
+            if outbox
+                .as_ref()
+                .is_some_and(|o| o.try_send(frame.clone()).is_err())
+            {
+                *outbox = None;
+            }
+        }
+
+        let sent = outboxes.iter().any(Option::is_some);
+
+        spectators.retain(|outbox| outbox.try_send(frame.clone()).is_ok());
+
+        if frames.len() < MAX_FRAMES {
+            frames.push(frame);
+        }
+
+        // the match is over once the players have all gone
+        if !sent {
+            break;
+        }
+    }
+
+    println!("match on seed {seed} ended");
+}
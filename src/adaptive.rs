@@ -0,0 +1,151 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{Game, GameConfig, Position, GRID_SCALE};
+
+// how far back deaths and meals count, in seconds of game time
+const WINDOW: f32 = 60.0;
+const ADJUST_INTERVAL: f32 = 5.0;
+
+// the challenge band: more deaths a minute than this, or longer between
+// meals, and the game eases off; no deaths and quicker meals and it pushes
+const MAX_DEATHS_PER_MINUTE: usize = 1;
+const MAX_FOOD_INTERVAL: f32 = 12.0;
+const MIN_FOOD_INTERVAL: f32 = 5.0;
+
+const EASE: f32 = 1.05;
+const PUSH: f32 = 0.97;
+// ticks never get slower than the mode's starting speed by more than this
+const MAX_SLOWDOWN: f32 = 1.5;
+
+// how many cells from the head new food lands within
+const START_REACH: f32 = 15.0;
+const MIN_REACH: f32 = 5.0;
+const MAX_REACH: f32 = 40.0;
+const REACH_STEP: f32 = 1.1;
+const FOOD_TRIES: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tracker {
+    // game time summed from tick lengths rather than measured, so a replay
+    // makes the same adjustments
+    elapsed: f32,
+    last_adjusted: f32,
+    deaths: VecDeque<f32>,
+    meals: VecDeque<f32>,
+    ticks: u32,
+    lives: u32,
+    food_eaten: u32,
+    pub reach: f32,
+}
+
+impl Tracker {
+    pub fn new(lives: u32) -> Self {
+        Self {
+            elapsed: 0.0,
+            last_adjusted: 0.0,
+            deaths: VecDeque::new(),
+            meals: VecDeque::new(),
+            ticks: 0,
+            lives,
+            food_eaten: 0,
+            reach: START_REACH,
+        }
+    }
+
+    fn food_interval(&self) -> f32 {
+        match (self.meals.front(), self.meals.back()) {
+            (Some(first), Some(last)) if self.meals.len() > 1 => {
+                (last - first) / (self.meals.len() - 1) as f32
+            }
+            _ => self.elapsed.min(WINDOW),
+        }
+    }
+}
+
+// follows the player's deaths and meals once per tick, and every few
+// seconds nudges the tick rate and food reach towards the challenge band
+pub fn adjust(config: Res<GameConfig>, mut game: ResMut<Game>) {
+    if !config.adaptive || game.is_over() || game.adaptive.ticks == game.replay.ticks {
+        return;
+    }
+
+    let game = &mut *game;
+    let tracker = &mut game.adaptive;
+
+    tracker.ticks = game.replay.ticks;
+    tracker.elapsed += game.tick_timer.duration().as_secs_f32();
+
+    let now = tracker.elapsed;
+
+    if game.lives < tracker.lives {
+        tracker.deaths.push_back(now);
+    }
+    for _ in tracker.food_eaten..game.food_eaten {
+        tracker.meals.push_back(now);
+    }
+
+    tracker.lives = game.lives;
+    tracker.food_eaten = game.food_eaten;
+
+    while tracker.deaths.front().is_some_and(|&t| t < now - WINDOW) {
+        tracker.deaths.pop_front();
+    }
+    while tracker.meals.front().is_some_and(|&t| t < now - WINDOW) {
+        tracker.meals.pop_front();
+    }
+
+    if now - tracker.last_adjusted < ADJUST_INTERVAL {
+        return;
+    }
+
+    tracker.last_adjusted = now;
+
+    let food_interval = tracker.food_interval();
+
+    let (speed, reach) =
+        if tracker.deaths.len() > MAX_DEATHS_PER_MINUTE || food_interval > MAX_FOOD_INTERVAL {
+            (EASE, 1.0 / REACH_STEP)
+        } else if tracker.deaths.is_empty() && food_interval < MIN_FOOD_INTERVAL {
+            (PUSH, REACH_STEP)
+        } else {
+            return;
+        };
+
+    tracker.reach = (tracker.reach * reach).clamp(MIN_REACH, MAX_REACH);
+
+    let interval = game.tick_timer.duration().as_secs_f32() * speed;
+    let interval = interval.clamp(
+        config.min_tick_interval,
+        config.tick_interval * MAX_SLOWDOWN,
+    );
+    game.tick_timer
+        .set_duration(Duration::from_secs_f32(interval));
+}
+
+// a free cell within reach of the player's head, or the closest of a few
+// picks when little of the board within reach is free
+pub fn food_cell(config: &GameConfig, game: &mut Game) -> Position {
+    let topology = config.topology.get();
+    let head = topology.to_world(game.player().head());
+    let reach = game.adaptive.reach;
+    let distance = |p: Position| topology.to_world(p).distance(head) / GRID_SCALE;
+
+    let mut best = game.random_free_cell(config);
+
+    for _ in 1..FOOD_TRIES {
+        if distance(best) <= reach {
+            break;
+        }
+
+        let position = game.random_free_cell(config);
+
+        if distance(position) < distance(best) {
+            best = position;
+        }
+    }
+
+    best
+}
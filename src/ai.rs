@@ -0,0 +1,536 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    advance, difficulty::Difficulty, is_out_of_bounds, reachable_cells, storage, Game, GameConfig,
+    Offset, Position,
+};
+
+// chance per tick that the easy policy forgets about the food and takes a
+// random safe turn
+const EASY_WANDER: f64 = 0.25;
+
+pub const POLICY_KEY: &str = "policy";
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AiLevel {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+    // plays with the weights exported from the training mode
+    Evolved,
+    // how every opponent steered before there were levels to pick from. only
+    // replays recorded back then play with it, so it isn't in `ALL`
+    Legacy,
+}
+
+impl AiLevel {
+    pub const ALL: [AiLevel; 4] = [
+        AiLevel::Easy,
+        AiLevel::Medium,
+        AiLevel::Hard,
+        AiLevel::Evolved,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AiLevel::Easy => "easy",
+            AiLevel::Medium => "medium",
+            AiLevel::Hard => "hard",
+            AiLevel::Evolved => "evolved",
+            AiLevel::Legacy => "legacy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+
+        Self::ALL.into_iter().find(|l| l.name() == name)
+    }
+
+    // the level opponents play at unless one is picked for them
+    pub fn for_difficulty(difficulty: Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Easy => AiLevel::Easy,
+            Difficulty::Normal => AiLevel::Medium,
+            Difficulty::Hard | Difficulty::Insane => AiLevel::Hard,
+        }
+    }
+
+    pub(crate) fn get(self) -> &'static dyn SnakePolicy {
+        match self {
+            AiLevel::Easy => &Greedy,
+            AiLevel::Medium => &Bfs,
+            AiLevel::Hard => &AStar,
+            AiLevel::Evolved => &Evolved,
+            AiLevel::Legacy => &Legacy,
+        }
+    }
+}
+
+// how the evolved policy values each feature of a candidate move. the
+// defaults are a hand-tuned starting point for training to improve on
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Weights {
+    pub food: f32,
+    pub space: f32,
+    pub straight: f32,
+    pub crowding: f32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            food: -1.0,
+            space: 2.0,
+            straight: 0.1,
+            crowding: -0.3,
+        }
+    }
+}
+
+impl Weights {
+    pub fn exported() -> Option<Self> {
+        storage::load(POLICY_KEY)
+    }
+
+    fn score(&self, food: f32, space: f32, straight: f32, crowding: f32) -> f32 {
+        self.food * food + self.space * space + self.straight * straight + self.crowding * crowding
+    }
+}
+
+pub trait SnakePolicy: Send + Sync {
+    fn steer(&self, game: &Game, config: &GameConfig, index: usize) -> Offset;
+
+    // the same, along with the cells the snake is headed along from the one
+    // after its head, for drawing. only the policies that find a whole path
+    // have any
+    fn steer_along(
+        &self,
+        game: &Game,
+        config: &GameConfig,
+        index: usize,
+    ) -> (Offset, Vec<Position>) {
+        (self.steer(game, config, index), vec![])
+    }
+}
+
+fn level(config: &GameConfig, index: usize) -> AiLevel {
+    // opponents are spawned after all the players
+    let opponent = index.saturating_sub(config.players);
+
+    config
+        .ai_levels
+        .get(opponent)
+        .copied()
+        .unwrap_or_else(|| AiLevel::for_difficulty(config.difficulty))
+}
+
+// the level of every opponent, as recorded in replays
+pub fn levels(config: &GameConfig) -> Vec<AiLevel> {
+    (0..config.opponents)
+        .map(|opponent| level(config, config.players + opponent))
+        .collect()
+}
+
+pub fn steer(game: &Game, config: &GameConfig, index: usize) -> (Offset, Vec<Position>) {
+    level(config, index).get().steer_along(game, config, index)
+}
+
+// heads for the closest food as the crow flies, now and then wandering off
+pub struct Greedy;
+
+impl SnakePolicy for Greedy {
+    fn steer(&self, game: &Game, config: &GameConfig, index: usize) -> Offset {
+        let snake = &game.snakes[index];
+        let head = snake.head();
+        let blocked = blocked(game);
+
+        let Some(target) = targets(game).into_iter().min_by_key(|&p| distance(head, p)) else {
+            return open_space(config, &blocked, head, snake.facing);
+        };
+
+        let safe: Vec<_> = choices(config, snake.facing)
+            .into_iter()
+            .filter(|&d| is_safe(config, &blocked, advance(config, head, d)))
+            .collect();
+
+        // seeded per snake and tick so replays make the same choices
+        let mut rng = ChaCha8Rng::seed_from_u64(
+            game.replay.seed ^ (u64::from(game.replay.ticks) << 8) ^ index as u64,
+        );
+
+        if !safe.is_empty() && rng.gen_bool(EASY_WANDER) {
+            return safe[rng.gen_range(0..safe.len())];
+        }
+
+        safe.into_iter()
+            .min_by_key(|&d| distance(advance(config, head, d), target))
+            .unwrap_or(snake.facing)
+    }
+}
+
+// follows the shortest open path to the closest food
+pub struct Bfs;
+
+impl Bfs {
+    fn path(game: &Game, config: &GameConfig, index: usize) -> Option<Vec<Position>> {
+        let snake = &game.snakes[index];
+        let head = snake.head();
+        let blocked = blocked(game);
+        let targets = targets(game);
+
+        let mut came_from = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        for direction in choices(config, snake.facing) {
+            let next = advance(config, head, direction);
+
+            if is_safe(config, &blocked, next) && !came_from.contains_key(&next) {
+                came_from.insert(next, head);
+                queue.push_back(next);
+            }
+        }
+
+        while let Some(position) = queue.pop_front() {
+            if targets.contains(&position) {
+                return Some(walk_back(&came_from, head, position));
+            }
+
+            for &direction in config.topology.get().directions() {
+                let next = advance(config, position, direction);
+
+                if is_safe(config, &blocked, next) && next != head && !came_from.contains_key(&next)
+                {
+                    came_from.insert(next, position);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl SnakePolicy for Bfs {
+    fn steer(&self, game: &Game, config: &GameConfig, index: usize) -> Offset {
+        self.steer_along(game, config, index).0
+    }
+
+    fn steer_along(
+        &self,
+        game: &Game,
+        config: &GameConfig,
+        index: usize,
+    ) -> (Offset, Vec<Position>) {
+        follow(game, config, index, Self::path(game, config, index))
+    }
+}
+
+// a* to each food in turn, nearest first, only taking a path if the snake
+// still has room to move once it has eaten, and otherwise filling space
+// until a safe path opens up
+pub struct AStar;
+
+impl AStar {
+    fn path(game: &Game, config: &GameConfig, index: usize) -> Option<Vec<Position>> {
+        let snake = &game.snakes[index];
+        let head = snake.head();
+        let blocked = blocked(game);
+
+        let mut targets = targets(game);
+        targets.sort_by_key(|&t| distance(head, t));
+
+        targets.into_iter().find_map(|target| {
+            let path = astar(config, &blocked, head, target)?;
+
+            // the body after following the path and growing by one
+            let body: Vec<_> = snake
+                .nodes
+                .iter()
+                .map(|n| n.position)
+                .chain(path.iter().copied())
+                .collect();
+            let body = &body[body.len().saturating_sub(snake.nodes.len() + 1)..];
+
+            let mut after = blocked.clone();
+            for node in snake.nodes.iter() {
+                after.remove(&node.position);
+            }
+            after.extend(body.iter().copied());
+
+            (flood(config, &after, target) >= snake.nodes.len()).then_some(path)
+        })
+    }
+}
+
+impl SnakePolicy for AStar {
+    fn steer(&self, game: &Game, config: &GameConfig, index: usize) -> Offset {
+        self.steer_along(game, config, index).0
+    }
+
+    fn steer_along(
+        &self,
+        game: &Game,
+        config: &GameConfig,
+        index: usize,
+    ) -> (Offset, Vec<Position>) {
+        follow(game, config, index, Self::path(game, config, index))
+    }
+}
+
+// takes the first step of a path, or heads for open space without one
+fn follow(
+    game: &Game,
+    config: &GameConfig,
+    index: usize,
+    path: Option<Vec<Position>>,
+) -> (Offset, Vec<Position>) {
+    let snake = &game.snakes[index];
+
+    match path.and_then(|p| Some((direction_to(config, snake.head(), p[0])?, p))) {
+        Some((direction, path)) => (direction, path),
+        None => (
+            open_space(config, &blocked(game), snake.head(), snake.facing),
+            vec![],
+        ),
+    }
+}
+
+// scores every safe move by a weighted sum of its distance to food, the room
+// behind it, whether it keeps going straight and how boxed in it is
+pub struct Evolved;
+
+impl SnakePolicy for Evolved {
+    fn steer(&self, game: &Game, config: &GameConfig, index: usize) -> Offset {
+        let snake = &game.snakes[index];
+        let head = snake.head();
+        let blocked = blocked(game);
+        let directions = config.topology.get().directions();
+
+        let target = targets(game).into_iter().min_by_key(|&p| distance(head, p));
+        let span = (config.width + config.height) as f32;
+        let cells = (config.width * config.height) as f32;
+
+        choices(config, snake.facing)
+            .into_iter()
+            .filter(|&d| is_safe(config, &blocked, advance(config, head, d)))
+            .map(|d| {
+                let next = advance(config, head, d);
+
+                let food = target.map_or(0.0, |t| distance(next, t) as f32 / span);
+                let space = flood(config, &blocked, next) as f32 / cells;
+                let straight = if d == snake.facing { 1.0 } else { 0.0 };
+                let crowding = directions
+                    .iter()
+                    .filter(|&&n| !is_safe(config, &blocked, advance(config, next, n)))
+                    .count() as f32
+                    / directions.len() as f32;
+
+                (d, config.evolved.score(food, space, straight, crowding))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(snake.facing, |(d, _)| d)
+    }
+}
+
+// heads for the closest food, or the most open space on a light-cycle board,
+// minding only walls and snakes. kept exactly as it was so old replays with
+// opponents still play back the same
+pub struct Legacy;
+
+impl SnakePolicy for Legacy {
+    fn steer(&self, game: &Game, config: &GameConfig, index: usize) -> Offset {
+        let snake = &game.snakes[index];
+        let head = snake.head();
+
+        let blocked: HashSet<_> = game
+            .walls
+            .iter()
+            .map(|w| w.position)
+            .chain(
+                game.snakes
+                    .iter()
+                    .filter(|s| s.alive)
+                    .flat_map(|s| s.nodes.iter().map(|n| n.position)),
+            )
+            .collect();
+
+        let safe = choices(config, snake.facing)
+            .into_iter()
+            .filter(|&d| is_safe(config, &blocked, advance(config, head, d)));
+
+        if config.trail {
+            return safe
+                .rev()
+                .max_by_key(|&d| reachable_cells(config, game, advance(config, head, d), None))
+                .unwrap_or(snake.facing);
+        }
+
+        let target = targets(game).into_iter().min_by_key(|&p| distance(head, p));
+
+        safe.min_by_key(|&d| target.map_or(0, |t| distance(advance(config, head, d), t)))
+            .unwrap_or(snake.facing)
+    }
+}
+
+// the cells from the head (exclusive) to the target (inclusive)
+fn astar(
+    config: &GameConfig,
+    blocked: &HashSet<Position>,
+    head: Position,
+    target: Position,
+) -> Option<Vec<Position>> {
+    let mut open = BinaryHeap::new();
+    let mut cost = HashMap::from([(head, 0)]);
+    let mut came_from = HashMap::new();
+
+    open.push(Reverse((distance(head, target), 0, head.x, head.y)));
+
+    while let Some(Reverse((_, steps, x, y))) = open.pop() {
+        let position = Position::new(x, y);
+
+        if position == target {
+            return Some(walk_back(&came_from, head, position));
+        }
+
+        if cost.get(&position).is_some_and(|&c| steps > c) {
+            continue;
+        }
+
+        for &direction in config.topology.get().directions() {
+            let next = advance(config, position, direction);
+            let steps = steps + 1;
+
+            if is_safe(config, blocked, next) && cost.get(&next).is_none_or(|&c| steps < c) {
+                cost.insert(next, steps);
+                came_from.insert(next, position);
+                open.push(Reverse((
+                    steps + distance(next, target),
+                    steps,
+                    next.x,
+                    next.y,
+                )));
+            }
+        }
+    }
+
+    None
+}
+
+// the path from the cell after `start` to `end` through the cells each one
+// was reached from
+fn walk_back(
+    came_from: &HashMap<Position, Position>,
+    start: Position,
+    end: Position,
+) -> Vec<Position> {
+    let mut path = vec![end];
+
+    while let Some(&previous) = came_from.get(path.last().unwrap()) {
+        if previous == start {
+            break;
+        }
+        path.push(previous);
+    }
+
+    path.reverse();
+    path
+}
+
+// the move from one cell onto a neighbouring one
+fn direction_to(config: &GameConfig, from: Position, to: Position) -> Option<Offset> {
+    config
+        .topology
+        .get()
+        .directions()
+        .iter()
+        .copied()
+        .find(|&d| advance(config, from, d) == to)
+}
+
+// the safe move with the most room behind it, used when there's no safe way
+// to any food and by light-cycles, which have nothing to chase at all
+fn open_space(
+    config: &GameConfig,
+    blocked: &HashSet<Position>,
+    head: Position,
+    facing: Offset,
+) -> Offset {
+    choices(config, facing)
+        .into_iter()
+        .filter(|&d| is_safe(config, blocked, advance(config, head, d)))
+        .rev()
+        .max_by_key(|&d| flood(config, blocked, advance(config, head, d)))
+        .unwrap_or(facing)
+}
+
+// cells reachable from `start`, not counting `start` itself
+fn flood(config: &GameConfig, blocked: &HashSet<Position>, start: Position) -> usize {
+    let mut seen = HashSet::from([start]);
+    let mut stack = vec![start];
+
+    while let Some(position) = stack.pop() {
+        for &direction in config.topology.get().directions() {
+            let next = advance(config, position, direction);
+
+            if is_safe(config, blocked, next) && seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+
+    seen.len() - 1
+}
+
+// directions other than reversing, straight ahead first
+fn choices(config: &GameConfig, facing: Offset) -> Vec<Offset> {
+    let mut choices: Vec<_> = config
+        .topology
+        .get()
+        .directions()
+        .iter()
+        .copied()
+        .filter(|&d| d != -facing)
+        .collect();
+
+    choices.sort_by_key(|&d| d != facing);
+    choices
+}
+
+fn blocked(game: &Game) -> HashSet<Position> {
+    game.walls
+        .iter()
+        .map(|w| w.position)
+        .chain(game.enemies.iter().map(|e| e.route.position))
+        .chain(
+            game.snakes
+                .iter()
+                .filter(|s| s.alive)
+                .flat_map(|s| s.nodes.iter().map(|n| n.position)),
+        )
+        .collect()
+}
+
+fn targets(game: &Game) -> Vec<Position> {
+    game.food
+        .iter()
+        .chain(game.pellets.iter())
+        .map(|f| f.position)
+        .collect()
+}
+
+fn is_safe(config: &GameConfig, blocked: &HashSet<Position>, position: Position) -> bool {
+    !is_out_of_bounds(config, position) && !blocked.contains(&position)
+}
+
+fn distance(a: Position, b: Position) -> usize {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+}
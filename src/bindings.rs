@@ -0,0 +1,239 @@
+use bevy::prelude::*;
+
+use crate::{
+    hud::Toasts,
+    locale::Localized,
+    settings::{Settings, DIRECTIONS},
+    AppState, PauseState,
+};
+
+const SELECTED: Color = Color::BLACK;
+const UNSELECTED: Color = Color::GRAY;
+// one entry per player and direction
+const ENTRIES: usize = 2 * DIRECTIONS.len();
+
+// keys a round already answers to, which steering can't take over
+const RESERVED: [KeyCode; 20] = [
+    KeyCode::Escape,
+    KeyCode::KeyP,
+    KeyCode::KeyR,
+    KeyCode::KeyM,
+    KeyCode::KeyQ,
+    KeyCode::KeyZ,
+    KeyCode::KeyC,
+    KeyCode::KeyG,
+    KeyCode::KeyL,
+    KeyCode::KeyX,
+    KeyCode::KeyU,
+    KeyCode::Equal,
+    KeyCode::Minus,
+    KeyCode::Digit0,
+    KeyCode::Tab,
+    KeyCode::Backquote,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F12,
+    KeyCode::PrintScreen,
+];
+
+#[derive(Resource, Default)]
+pub struct BindingsMenu {
+    selected: usize,
+    // the next key pressed is bound to the selected entry
+    listening: bool,
+}
+
+#[derive(Component)]
+pub struct BindingsRoot;
+
+#[derive(Component)]
+pub struct BindingsEntry(usize);
+
+pub fn setup(mut cmd: Commands) {
+    cmd.init_resource::<BindingsMenu>();
+
+    cmd.spawn((
+        BindingsRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            Localized("keys"),
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 40.0,
+                    color: SELECTED,
+                    ..default()
+                },
+            ),
+        ));
+
+        for index in 0..ENTRIES {
+            parent.spawn((
+                BindingsEntry(index),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: UNSELECTED,
+                        ..default()
+                    },
+                ),
+            ));
+        }
+
+        parent.spawn((
+            Localized("up/down to choose, enter to rebind, escape to go back"),
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: UNSELECTED,
+                    ..default()
+                },
+            ),
+        ));
+    });
+}
+
+pub fn cleanup(mut cmd: Commands, roots: Query<Entity, With<BindingsRoot>>) {
+    for entity in roots.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    cmd.remove_resource::<BindingsMenu>();
+}
+
+pub fn navigate(
+    input: Res<ButtonInput<KeyCode>>,
+    mut menu: ResMut<BindingsMenu>,
+    mut settings: ResMut<Settings>,
+    mut toast: Toasts,
+    pause: Res<State<PauseState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if menu.listening {
+        listen(&input, &mut menu, &mut settings, &mut toast);
+        return;
+    }
+
+    if input.just_pressed(KeyCode::ArrowUp) {
+        menu.selected = menu.selected.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        menu.selected = (menu.selected + 1).min(ENTRIES - 1);
+    }
+
+    if input.just_pressed(KeyCode::Enter) {
+        menu.listening = true;
+    }
+
+    // back to whichever settings screen this was opened from
+    if input.just_pressed(KeyCode::Escape) && *pause.get() == PauseState::Bindings {
+        next_pause.set(PauseState::Settings);
+    } else if input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Settings);
+    }
+}
+
+// escape gives up on rebinding, any other key is taken unless something else
+// already uses it
+fn listen(
+    input: &ButtonInput<KeyCode>,
+    menu: &mut BindingsMenu,
+    settings: &mut Settings,
+    toast: &mut Toasts,
+) {
+    let Some(&key) = input.get_just_pressed().next() else {
+        return;
+    };
+
+    if key == KeyCode::Escape {
+        menu.listening = false;
+        return;
+    }
+
+    let player = menu.selected / DIRECTIONS.len();
+    let direction = menu.selected % DIRECTIONS.len();
+
+    if RESERVED.contains(&key) {
+        toast.show(format!("{} is already used by the game", key_name(key)));
+        return;
+    }
+
+    if let Some((other, towards)) = settings.bindings.find(key) {
+        if (other, towards) != (player, direction) {
+            toast.show(format!(
+                "{} already steers player {} {}",
+                key_name(key),
+                other + 1,
+                DIRECTIONS[towards].1
+            ));
+            return;
+        }
+    }
+
+    settings.bindings.0[player][direction] = key;
+    settings.save();
+    menu.listening = false;
+}
+
+pub fn refresh(
+    menu: Res<BindingsMenu>,
+    settings: Res<Settings>,
+    mut entries: Query<(&BindingsEntry, &mut Text)>,
+) {
+    let language = settings.language;
+
+    for (entry, mut text) in entries.iter_mut() {
+        let player = entry.0 / DIRECTIONS.len();
+        let direction = entry.0 % DIRECTIONS.len();
+
+        let key = if menu.listening && entry.0 == menu.selected {
+            "...".to_owned()
+        } else {
+            key_name(settings.bindings.0[player][direction])
+        };
+
+        let value = language.format(
+            "player {} {}: {}",
+            &[&(player + 1), &language.text(DIRECTIONS[direction].1), &key],
+        );
+
+        let color = if entry.0 == menu.selected {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
+
+// ArrowUp reads as up and KeyW as w
+pub fn key_name(key: KeyCode) -> String {
+    format!("{key:?}")
+        .trim_start_matches("Arrow")
+        .trim_start_matches("Key")
+        .to_lowercase()
+}
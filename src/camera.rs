@@ -0,0 +1,215 @@
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::{online::Session, settings::Settings, Game, GameConfig};
+
+const FOLLOW_SMOOTHING: f32 = 8.0;
+const LARGE_CELL_SCALE: f32 = 2.0;
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+const ZOOM_STEP: f32 = 1.1;
+// screen pixels a second the free camera moves at with the arrow keys
+const PAN_SPEED: f32 = 400.0;
+
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    #[default]
+    Board,
+    Follow,
+    // stays wherever it's panned to
+    Free,
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct CameraZoom(pub f32);
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+pub fn toggle_mode(input: Res<ButtonInput<KeyCode>>, mut mode: ResMut<CameraMode>) {
+    if input.just_pressed(KeyCode::KeyC) {
+        *mode = match *mode {
+            CameraMode::Board => CameraMode::Follow,
+            CameraMode::Follow => CameraMode::Free,
+            CameraMode::Free => CameraMode::Board,
+        };
+    }
+}
+
+// the free camera is dragged around with the mouse, or moved with the arrow
+// keys when they aren't steering a snake
+pub fn pan(
+    mode: Res<CameraMode>,
+    config: Res<GameConfig>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    input: Res<ButtonInput<KeyCode>>,
+    session: Option<Res<Session>>,
+    time: Res<Time>,
+    mut motion: EventReader<MouseMotion>,
+    mut cameras: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+) {
+    let mut delta: Vec2 = motion
+        .read()
+        .filter(|_| buttons.pressed(MouseButton::Left))
+        .map(|event| Vec2::new(-event.delta.x, event.delta.y))
+        .sum();
+
+    if *mode != CameraMode::Free {
+        return;
+    }
+
+    if session.is_some_and(|s| s.player.is_none()) {
+        let keys = [
+            (KeyCode::ArrowLeft, Vec2::NEG_X),
+            (KeyCode::ArrowRight, Vec2::X),
+            (KeyCode::ArrowUp, Vec2::Y),
+            (KeyCode::ArrowDown, Vec2::NEG_Y),
+        ];
+
+        for (key, direction) in keys {
+            if input.pressed(key) {
+                delta += direction * PAN_SPEED * time.delta_seconds();
+            }
+        }
+    }
+
+    let board = config
+        .topology
+        .get()
+        .board_size(config.width, config.height);
+
+    // the middle of the view never leaves the board
+    for (mut transform, projection) in cameras.iter_mut() {
+        let position = transform.translation.truncate() + delta * projection.scale;
+
+        transform.translation.x = position.x.clamp(0.0, board.x);
+        transform.translation.y = position.y.clamp(-board.y, 0.0);
+    }
+}
+
+pub fn zoom(
+    input: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut zoom: ResMut<CameraZoom>,
+) {
+    let mut steps = 0.0;
+
+    for event in wheel.read() {
+        steps += match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        };
+    }
+
+    if input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        steps += 1.0;
+    }
+    if input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        steps -= 1.0;
+    }
+
+    if input.just_pressed(KeyCode::Digit0) {
+        zoom.0 = 1.0;
+    } else if steps != 0.0 {
+        zoom.0 = (zoom.0 * ZOOM_STEP.powf(-steps)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+pub fn follow(
+    mode: Res<CameraMode>,
+    zoom: Res<CameraZoom>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    game: Option<Res<Game>>,
+    time: Res<Time>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let topology = config.topology.get();
+    let board = topology.board_size(config.width, config.height);
+    let (board_width, board_height) = (board.x, board.y);
+    let center = Vec2::new(board_width / 2.0, -board_height / 2.0);
+
+    let magnification = if settings.large_cells {
+        LARGE_CELL_SCALE
+    } else {
+        1.0
+    };
+
+    // an endless board is far too big to show whole
+    let mode = if config.endless && *mode == CameraMode::Board {
+        CameraMode::Follow
+    } else {
+        *mode
+    };
+
+    let head = game
+        .as_ref()
+        .and_then(|g| g.player().nodes.last())
+        .map(|node| topology.to_world(node.position));
+
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        projection.scale = match mode {
+            CameraMode::Board | CameraMode::Free => {
+                f32::max(board_width / window.width(), board_height / window.height()) * zoom.0
+            }
+            CameraMode::Follow => zoom.0,
+        } / magnification;
+
+        // pixelated boards only zoom in whole steps so every cell covers the
+        // same number of screen pixels
+        if config.pixelated && projection.scale < 1.0 {
+            projection.scale = 1.0 / (1.0 / projection.scale).floor();
+        }
+
+        // the free camera only moves when it's panned
+        if mode == CameraMode::Free {
+            continue;
+        }
+
+        // with large cells the whole board no longer fits, so board mode
+        // falls back to tracking the player within the board edges
+        let tracking = mode == CameraMode::Follow || settings.large_cells;
+
+        let target = match head {
+            Some(head) if tracking => {
+                let half_view = Vec2::new(window.width(), window.height()) * projection.scale / 2.0;
+
+                Vec2::new(
+                    clamp_axis(head.x, half_view.x, board_width),
+                    -clamp_axis(-head.y, half_view.y, board_height),
+                )
+            }
+            None if mode == CameraMode::Follow => continue,
+            _ => center,
+        };
+
+        let t = if settings.reduced_motion || !tracking {
+            1.0
+        } else {
+            1.0 - (-FOLLOW_SMOOTHING * time.delta_seconds()).exp()
+        };
+        let current = transform.translation.truncate();
+
+        transform.translation = current.lerp(target, t).extend(transform.translation.z);
+    }
+}
+
+fn clamp_axis(value: f32, half_view: f32, board_size: f32) -> f32 {
+    if board_size <= half_view * 2.0 {
+        board_size / 2.0
+    } else {
+        value.clamp(half_view, board_size - half_view)
+    }
+}
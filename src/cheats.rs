@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{hud::Toasts, Controller, Game, Spawner, PLAYER};
+
+// how many colors the rainbow snake cycles through, and how many segments a
+// second the colors run along it
+const RAINBOW_COLORS: usize = 12;
+const RAINBOW_SPEED: f32 = 8.0;
+pub const GIANT_FOOD_SCALE: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cheat {
+    Rainbow,
+    GiantFood,
+    SlowMotion,
+}
+
+impl Cheat {
+    const ALL: [Cheat; 3] = [Cheat::Rainbow, Cheat::GiantFood, Cheat::SlowMotion];
+
+    fn name(self) -> &'static str {
+        match self {
+            Cheat::Rainbow => "rainbow snake",
+            Cheat::GiantFood => "giant food",
+            Cheat::SlowMotion => "slow motion",
+        }
+    }
+
+    fn code(self) -> &'static [KeyCode] {
+        match self {
+            Cheat::Rainbow => &[
+                KeyCode::ArrowUp,
+                KeyCode::ArrowUp,
+                KeyCode::ArrowDown,
+                KeyCode::ArrowDown,
+                KeyCode::ArrowLeft,
+                KeyCode::ArrowRight,
+                KeyCode::ArrowLeft,
+                KeyCode::ArrowRight,
+                KeyCode::KeyB,
+                KeyCode::KeyA,
+            ],
+            Cheat::GiantFood => &[
+                KeyCode::KeyF,
+                KeyCode::KeyE,
+                KeyCode::KeyA,
+                KeyCode::KeyS,
+                KeyCode::KeyT,
+            ],
+            Cheat::SlowMotion => &[
+                KeyCode::KeyS,
+                KeyCode::KeyN,
+                KeyCode::KeyA,
+                KeyCode::KeyI,
+                KeyCode::KeyL,
+            ],
+        }
+    }
+}
+
+// the codes typed in so far and what they've turned on, which stays on
+// between rounds until the code is typed again. any of them keeps rounds off
+// the high score table
+#[derive(Resource, Default)]
+pub struct Cheats {
+    recent: VecDeque<KeyCode>,
+    active: Vec<Cheat>,
+}
+
+impl Cheats {
+    fn is_on(&self, cheat: Cheat) -> bool {
+        self.active.contains(&cheat)
+    }
+
+    pub fn giant_food(&self) -> bool {
+        self.is_on(Cheat::GiantFood)
+    }
+}
+
+pub fn listen(
+    input: Res<ButtonInput<KeyCode>>,
+    mut cheats: ResMut<Cheats>,
+    mut game: ResMut<Game>,
+    mut toast: Toasts,
+) {
+    let longest = Cheat::ALL.iter().map(|c| c.code().len()).max().unwrap_or(0);
+
+    for &key in input.get_just_pressed() {
+        cheats.recent.push_back(key);
+
+        if cheats.recent.len() > longest {
+            cheats.recent.pop_front();
+        }
+
+        let recent = cheats.recent.make_contiguous();
+        let Some(cheat) = Cheat::ALL.into_iter().find(|c| recent.ends_with(c.code())) else {
+            continue;
+        };
+
+        cheats.recent.clear();
+
+        if cheats.is_on(cheat) {
+            cheats.active.retain(|&c| c != cheat);
+            toast.show(format!("cheat off: {}", cheat.name()));
+        } else {
+            cheats.active.push(cheat);
+            toast.show(format!("cheat on: {}", cheat.name()));
+        }
+    }
+
+    if !cheats.active.is_empty() && game.ranked {
+        game.ranked = false;
+    }
+
+    // keeps the near-miss slow motion going for as long as it's on
+    if cheats.is_on(Cheat::SlowMotion) && !game.is_over() && game.slow_motion == 0 {
+        game.slow_motion = 1;
+    }
+}
+
+// colors run down the player's snake from head to tail, and it goes back to
+// its own colors when the cheat is turned off
+pub fn rainbow(
+    cheats: Res<Cheats>,
+    spawner: Res<Spawner>,
+    time: Res<Time>,
+    game: Res<Game>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut nodes: Query<&mut Handle<ColorMaterial>>,
+    mut rainbow: Local<Vec<Handle<ColorMaterial>>>,
+) {
+    let on = cheats.is_on(Cheat::Rainbow);
+
+    if !on && !cheats.is_changed() {
+        return;
+    }
+
+    if rainbow.is_empty() {
+        *rainbow = (0..RAINBOW_COLORS)
+            .map(|i| {
+                let hue = i as f32 * 360.0 / RAINBOW_COLORS as f32;
+                materials.add(Color::hsl(hue, 0.8, 0.5))
+            })
+            .collect();
+    }
+
+    let Some(snake) = game.snakes.get(PLAYER) else {
+        return;
+    };
+
+    let offset = (time.elapsed_seconds() * RAINBOW_SPEED) as usize;
+    let length = snake.nodes.len();
+
+    for (index, node) in snake.nodes.iter().enumerate() {
+        let Ok(mut material) = nodes.get_mut(node.entity) else {
+            continue;
+        };
+
+        let next = if on {
+            &rainbow[(length - index + offset) % RAINBOW_COLORS]
+        } else {
+            spawner.node_material(Controller::Player(0), index)
+        };
+
+        if *material != *next {
+            *material = next.clone();
+        }
+    }
+}
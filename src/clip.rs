@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    hud::Toasts, powerup::PowerUp, settings::Settings, theme, Controller, Game, GameConfig,
+    Position, GRID_SCALE,
+};
+
+const CLIP_SECONDS: f32 = 10.0;
+const CELL_PIXELS: f32 = 6.0;
+const FINAL_FRAME_SECONDS: f32 = 1.5;
+
+const BACKGROUND: u8 = 0;
+const SNAKE: u8 = 1;
+const PARTNER: u8 = 2;
+const OPPONENT: u8 = 3;
+const FOOD: u8 = 4;
+const WALL: u8 = 5;
+const ENEMY: u8 = 6;
+const SHRINK: u8 = 7;
+const MAGNET: u8 = 8;
+const SHIELD: u8 = 9;
+const REWIND: u8 = 10;
+
+struct Frame {
+    tick: u32,
+    time: f32,
+    cells: Vec<u8>,
+}
+
+// the board as it looked after each of the last few seconds of ticks, kept
+// as one palette index per cell and only rasterized when exported
+#[derive(Resource, Default)]
+pub struct Clip {
+    frames: VecDeque<Frame>,
+}
+
+pub fn record(config: Res<GameConfig>, game: Res<Game>, time: Res<Time>, mut clip: ResMut<Clip>) {
+    // a frame of an endless board would be millions of cells
+    if config.endless {
+        return;
+    }
+
+    let tick = game.replay.ticks;
+
+    match clip.frames.back() {
+        Some(last) if last.tick == tick => return,
+        Some(last) if last.tick > tick => clip.frames.clear(),
+        _ => {}
+    }
+
+    let mut cells = vec![BACKGROUND; config.width * config.height];
+    let mut plot = |position: Position, color: u8| {
+        if position.x < config.width && position.y < config.height {
+            cells[position.y * config.width + position.x] = color;
+        }
+    };
+
+    for wall in game.walls.iter() {
+        plot(wall.position, WALL);
+    }
+
+    for snake in game.snakes.iter() {
+        let color = match snake.controller {
+            Controller::Player(0) => SNAKE,
+            Controller::Player(_) => PARTNER,
+            Controller::Ai => OPPONENT,
+        };
+
+        for node in snake.nodes.iter() {
+            plot(node.position, color);
+        }
+    }
+
+    for food in game.food.iter().chain(game.pellets.iter()) {
+        plot(food.position, FOOD);
+    }
+
+    for hazard in game.enemies.iter() {
+        plot(hazard.route.position, ENEMY);
+    }
+
+    for pickup in game.pickups.iter() {
+        let color = match pickup.kind {
+            PowerUp::Shrink => SHRINK,
+            PowerUp::Magnet => MAGNET,
+            PowerUp::Shield => SHIELD,
+            PowerUp::Rewind => REWIND,
+        };
+
+        plot(pickup.position, color);
+    }
+
+    let now = time.elapsed_seconds();
+
+    clip.frames.push_back(Frame {
+        tick,
+        time: now,
+        cells,
+    });
+
+    while clip
+        .frames
+        .front()
+        .is_some_and(|f| now - f.time > CLIP_SECONDS)
+    {
+        clip.frames.pop_front();
+    }
+}
+
+pub fn export(
+    input: Res<ButtonInput<KeyCode>>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    game: Res<Game>,
+    clip: Res<Clip>,
+    mut toast: Toasts,
+) {
+    if !game.is_over() || !input.just_pressed(KeyCode::KeyG) {
+        return;
+    }
+
+    if config.endless {
+        toast.show("clips aren't recorded on endless boards");
+        return;
+    }
+
+    match encode(&config, &settings, &clip) {
+        Ok(bytes) => match save(
+            &format!("snake-{}.gif", crate::daily::now_millis()),
+            &bytes,
+            "image/gif",
+        ) {
+            Some(name) => toast.show(format!("saved {name}")),
+            None => toast.show("failed to save the clip"),
+        },
+        Err(e) => warn!("failed to encode clip: {e}"),
+    }
+}
+
+fn encode(
+    config: &GameConfig,
+    settings: &Settings,
+    clip: &Clip,
+) -> Result<Vec<u8>, gif::EncodingError> {
+    let topology = config.topology.get();
+    let scale = CELL_PIXELS / GRID_SCALE;
+    let size = topology.board_size(config.width, config.height) * scale;
+    let (width, height) = (size.x.ceil() as usize, size.y.ceil() as usize);
+
+    let theme = theme::current(config, settings);
+    let palette: Vec<u8> = [
+        theme.background,
+        theme.snake,
+        theme.partner,
+        theme.opponent,
+        theme.food,
+        theme.wall,
+        theme.enemy,
+        theme.shrink,
+        theme.magnet,
+        theme.shield,
+        theme.rewind,
+    ]
+    .iter()
+    .flat_map(|c| {
+        let [r, g, b, _] = c.as_rgba_u8();
+        [r, g, b]
+    })
+    .collect();
+
+    let mut bytes = vec![];
+    let mut encoder = gif::Encoder::new(&mut bytes, width as u16, height as u16, &palette)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    let half = (CELL_PIXELS / 2.0) as isize;
+
+    for (index, frame) in clip.frames.iter().enumerate() {
+        let mut pixels = vec![BACKGROUND; width * height];
+
+        for (cell, &color) in frame
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c != BACKGROUND)
+        {
+            let position = Position::new(cell % config.width, cell / config.width);
+            let center = topology.to_world(position) * scale;
+            let (cx, cy) = (center.x as isize, -center.y as isize);
+
+            for y in (cy - half).max(0)..(cy + half).min(height as isize) {
+                for x in (cx - half).max(0)..(cx + half).min(width as isize) {
+                    pixels[y as usize * width + x as usize] = color;
+                }
+            }
+        }
+
+        let seconds = clip
+            .frames
+            .get(index + 1)
+            .map_or(FINAL_FRAME_SECONDS, |next| next.time - frame.time);
+
+        let mut output = gif::Frame::from_indexed_pixels(width as u16, height as u16, pixels, None);
+        output.delay = (seconds * 100.0).round().max(1.0) as u16;
+
+        encoder.write_frame(&output)?;
+    }
+
+    drop(encoder);
+
+    Ok(bytes)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(name: &str, bytes: &[u8], _mime: &str) -> Option<String> {
+    let path = crate::screenshot::path(name)?;
+
+    match std::fs::write(&path, bytes) {
+        Ok(()) => Some(path.display().to_string()),
+        Err(e) => {
+            warn!("failed to save {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+// hand the file to the browser as a download through a temporary object url
+#[cfg(target_arch = "wasm32")]
+pub fn save(name: &str, bytes: &[u8], mime: &str) -> Option<String> {
+    use wasm_bindgen::JsCast;
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(
+        &parts,
+        web_sys::BlobPropertyBag::new().type_(mime),
+    )
+    .ok()?;
+
+    let url = web_sys::Url::create_object_url_with_blob(&blob).ok()?;
+    let anchor: web_sys::HtmlAnchorElement = web_sys::window()?
+        .document()?
+        .create_element("a")
+        .ok()?
+        .dyn_into()
+        .ok()?;
+
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+
+    let _ = web_sys::Url::revoke_object_url(&url);
+
+    Some(name.to_owned())
+}
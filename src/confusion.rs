@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{hud::Toasts, Game, GameConfig, Offset};
+
+const ROTATE_INTERVAL: f32 = 30.0;
+const ROTATE_WARNING: f32 = 3.0;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Confusion {
+    #[default]
+    Off,
+    Mirror,
+    Rotate,
+}
+
+impl Confusion {
+    pub const ALL: [Confusion; 3] = [Confusion::Off, Confusion::Mirror, Confusion::Rotate];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Confusion::Off => "normal",
+            Confusion::Mirror => "mirrored",
+            Confusion::Rotate => "rotating",
+        }
+    }
+}
+
+// the stage between the pressed keys and the input queue: every direction a
+// player presses goes through `transform` before it steers the snake, so turns
+// are recorded (and replayed) after confusion has been applied
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Controls {
+    quarter_turns: usize,
+    timer: Timer,
+    warned: bool,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self {
+            quarter_turns: 0,
+            timer: Timer::from_seconds(ROTATE_INTERVAL, TimerMode::Repeating),
+            warned: false,
+        }
+    }
+}
+
+impl Controls {
+    pub fn transform(&self, confusion: Confusion, offset: Offset) -> Offset {
+        match confusion {
+            Confusion::Off => offset,
+            Confusion::Mirror => Offset::new(-offset.x, offset.y),
+            // clockwise on screen, where y points down
+            Confusion::Rotate => {
+                (0..self.quarter_turns).fold(offset, |o, _| Offset::new(-o.y, o.x))
+            }
+        }
+    }
+}
+
+pub fn rotate(time: Res<Time>, config: Res<GameConfig>, mut game: ResMut<Game>, mut toast: Toasts) {
+    if config.confusion != Confusion::Rotate
+        || game.paused
+        || game.countdown.is_some()
+        || game.is_over()
+    {
+        return;
+    }
+
+    let controls = &mut game.controls;
+
+    if controls.timer.tick(time.delta()).just_finished() {
+        controls.quarter_turns = (controls.quarter_turns + 1) % 4;
+        controls.warned = false;
+        toast.show("controls rotated");
+    } else if !controls.warned && controls.timer.remaining_secs() <= ROTATE_WARNING {
+        controls.warned = true;
+        toast.show("controls are about to rotate!");
+    }
+}
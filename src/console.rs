@@ -0,0 +1,264 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+
+use crate::{
+    cleanup_game, hud, kill, script::free_cell, setup_game, FoodKind, Game, GameConfig, Spawner,
+};
+
+// lines of output kept above the prompt
+const SCROLLBACK: usize = 8;
+
+const HELP: &str = "spawn_food x y, set_speed seconds, kill, grow n, seed n";
+
+// a drop-down command line for setting up situations by hand, opened with `.
+// the round holds still while it's open, and keys typed into it don't reach
+// the game
+#[derive(Resource, Default)]
+pub struct Console {
+    open: bool,
+    line: String,
+    output: Vec<String>,
+    // whether the round was already paused when the console opened
+    was_paused: bool,
+}
+
+impl Console {
+    fn print(&mut self, text: impl Into<String>) {
+        self.output.push(text.into());
+
+        if self.output.len() > SCROLLBACK {
+            self.output.remove(0);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ConsoleRoot;
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+pub fn setup(mut cmd: Commands) {
+    cmd.spawn((
+        ConsoleRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                top: Val::Px(0.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            ConsoleText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+        ));
+    });
+}
+
+// runs before everything else reads the keyboard, so it can swallow what was
+// typed into it
+pub fn input(
+    mut cmd: Commands,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut console: ResMut<Console>,
+    spawner: Res<Spawner>,
+    mut config: ResMut<GameConfig>,
+    game: Option<ResMut<Game>>,
+) {
+    let Some(mut game) = game else {
+        return;
+    };
+
+    // a round started from the console holds still too
+    if console.open && game.is_added() {
+        console.was_paused = false;
+        game.paused = true;
+    }
+
+    if keys.just_pressed(KeyCode::Backquote) {
+        console.open = !console.open;
+
+        if console.open {
+            console.was_paused = game.paused;
+            game.paused = true;
+        } else {
+            game.paused = console.was_paused;
+        }
+
+        keys.reset_all();
+        return;
+    }
+
+    if !console.open {
+        return;
+    }
+
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let mut typed: String = hud::typed(&keys).collect();
+
+    if keys.just_pressed(KeyCode::Space) {
+        typed.push(' ');
+    }
+    if keys.just_pressed(KeyCode::Period) {
+        typed.push('.');
+    }
+    if keys.just_pressed(KeyCode::Minus) {
+        typed.push(if shift { '_' } else { '-' });
+    }
+
+    console.line.push_str(&typed);
+
+    if keys.just_pressed(KeyCode::Backspace) {
+        console.line.pop();
+    }
+
+    if keys.just_pressed(KeyCode::Enter) {
+        let line = std::mem::take(&mut console.line);
+        console.print(format!("> {line}"));
+
+        let result = run(&mut cmd, &spawner, &mut config, &mut game, &line);
+
+        match result {
+            Ok(Some(text)) => console.print(text),
+            Ok(None) => {}
+            Err(e) => console.print(e),
+        }
+    }
+
+    keys.reset_all();
+}
+
+fn run(
+    cmd: &mut Commands,
+    spawner: &Spawner,
+    config: &mut GameConfig,
+    game: &mut Game,
+    line: &str,
+) -> Result<Option<String>, String> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    let args: Vec<_> = words.collect();
+
+    let number = |index: usize| -> Result<i64, String> {
+        let arg = args
+            .get(index)
+            .ok_or_else(|| format!("{command} is missing an argument, try help"))?;
+
+        arg.parse().map_err(|_| format!("{arg} isn't a number"))
+    };
+
+    // a round set up by hand can't be trusted on the leaderboard
+    if !matches!(command, "" | "help") {
+        game.ranked = false;
+    }
+
+    match command {
+        "" => Ok(None),
+        "help" => Ok(Some(HELP.to_owned())),
+        "spawn_food" => {
+            let (x, y) = (number(0)?, number(1)?);
+            let position =
+                free_cell(config, game, x, y).ok_or_else(|| format!("{x}, {y} isn't free"))?;
+
+            game.pellets
+                .push(spawner.new_food(cmd, position, FoodKind::Pellet));
+
+            Ok(None)
+        }
+        "set_speed" => {
+            let seconds = args
+                .first()
+                .and_then(|a| a.parse::<f32>().ok())
+                .filter(|&s| s > 0.0)
+                .ok_or("set_speed takes the seconds per tick")?;
+
+            game.tick_timer
+                .set_duration(Duration::from_secs_f32(seconds));
+
+            Ok(None)
+        }
+        "kill" => {
+            let active = game.active;
+            kill(cmd, spawner, config, game, active);
+
+            Ok(None)
+        }
+        "grow" => {
+            let count = usize::try_from(number(0)?).map_err(|_| "grow takes a count")?;
+            let controller = game.player().controller;
+
+            // stacked on the tail, they unfold one by one as the snake moves
+            for _ in 0..count {
+                let snake = &game.snakes[game.active];
+                let tail = snake
+                    .nodes
+                    .first()
+                    .ok_or("there's no snake to grow")?
+                    .position;
+                let node = spawner.new_node(cmd, tail, controller, snake.nodes.len());
+
+                game.snakes[game.active].nodes.insert(0, node);
+            }
+
+            Ok(None)
+        }
+        "seed" => {
+            let seed = u64::try_from(number(0)?).map_err(|_| "seeds can't be negative")?;
+            config.seed = Some(seed);
+
+            cleanup_game(cmd, game);
+            cmd.add(|world: &mut World| world.run_system_once(setup_game));
+
+            Ok(Some(format!("restarted with seed {seed}")))
+        }
+        _ => Err(format!("unknown command {command}, try help")),
+    }
+}
+
+pub fn refresh(
+    console: Res<Console>,
+    mut roots: Query<&mut Visibility, With<ConsoleRoot>>,
+    mut texts: Query<&mut Text, With<ConsoleText>>,
+) {
+    let visibility = if console.open {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for mut root in roots.iter_mut() {
+        if *root != visibility {
+            *root = visibility;
+        }
+    }
+
+    let value = console
+        .output
+        .iter()
+        .cloned()
+        .chain(std::iter::once(format!("> {}_", console.line)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
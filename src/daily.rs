@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const STORAGE_KEY: &str = "daily";
+pub const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct DailyRecord {
+    day: u64,
+}
+
+impl DailyRecord {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn played(&self, day: u64) -> bool {
+        self.day == day
+    }
+
+    pub fn claim(&mut self, day: u64) -> bool {
+        if self.played(day) {
+            return false;
+        }
+
+        self.day = day;
+        storage::save(STORAGE_KEY, self);
+
+        true
+    }
+}
+
+pub fn today() -> u64 {
+    now_millis() / DAY_MILLIS
+}
+
+pub fn seed(day: u64) -> u64 {
+    day.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ 0x736e_616b_6500_0000
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn now_millis() -> u64 {
+    js_sys::Date::now() as u64
+}
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::entity::Entities,
+    prelude::*,
+    utils::Instant,
+};
+
+use crate::{
+    hud::{self, Toasts},
+    Game,
+};
+
+// stops the automatic tick so the game only moves on when . is pressed, one
+// tick at a time, to look at input queue and collision edge cases frame by
+// frame. f3 turns it on and off
+#[derive(Resource, Default)]
+pub struct Stepping {
+    pub enabled: bool,
+    // whether a step was asked for this frame
+    pub step: bool,
+}
+
+pub fn input(input: Res<ButtonInput<KeyCode>>, mut stepping: ResMut<Stepping>, mut toast: Toasts) {
+    if input.just_pressed(KeyCode::F3) {
+        stepping.enabled = !stepping.enabled;
+
+        toast.show(if stepping.enabled {
+            "single-step on, . to advance"
+        } else {
+            "single-step off"
+        });
+    }
+
+    let step = stepping.enabled && input.just_pressed(KeyCode::Period);
+    if stepping.step != step {
+        stepping.step = step;
+    }
+}
+
+// frame rate, how long the last tick took, how many entities there are and
+// the player's queued turns and length, in a corner. f2 shows and hides it
+#[derive(Resource, Default)]
+pub struct Overlay {
+    visible: bool,
+    // when `update` started this frame, and how long it took on the last
+    // frame it ticked
+    started: Option<Instant>,
+    tick: Duration,
+}
+
+#[derive(Component)]
+pub struct OverlayText;
+
+pub fn setup_overlay(mut cmd: Commands) {
+    cmd.spawn((
+        OverlayText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_text_justify(JustifyText::Right)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            top: Val::Px(40.0),
+            ..default()
+        }),
+    ));
+}
+
+pub fn toggle_overlay(input: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<Overlay>) {
+    if input.just_pressed(KeyCode::F2) {
+        overlay.visible = !overlay.visible;
+    }
+}
+
+pub fn start_tick(mut overlay: ResMut<Overlay>) {
+    overlay.started = Some(Instant::now());
+}
+
+// frames that don't tick only check the timer, so they'd drown out the ones
+// that do
+pub fn end_tick(game: Option<Res<Game>>, mut overlay: ResMut<Overlay>, mut ticks: Local<u32>) {
+    let Some(started) = overlay.started.take() else {
+        return;
+    };
+
+    let Some(game) = game else {
+        return;
+    };
+
+    if game.replay.ticks != *ticks {
+        *ticks = game.replay.ticks;
+        overlay.tick = started.elapsed();
+    }
+}
+
+pub fn update_overlay(
+    overlay: Res<Overlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    entities: &Entities,
+    game: Option<Res<Game>>,
+    mut texts: Query<&mut Text, With<OverlayText>>,
+) {
+    let value = if overlay.visible {
+        let fps = diagnostics
+            .get(&FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|d| d.smoothed())
+            .unwrap_or_default();
+
+        let mut lines = vec![
+            format!("fps {fps:.0}"),
+            format!("tick {:.2}ms", overlay.tick.as_secs_f64() * 1000.0),
+            format!("entities {}", entities.len()),
+        ];
+
+        if let Some(game) = game {
+            let snake = game.player();
+            let queue: Vec<_> = snake.input_queue.iter().map(|&o| hud::arrow(o)).collect();
+
+            lines.push(format!("queue [{}]", queue.join(" ")));
+            lines.push(format!("length {}", snake.nodes.len()));
+        }
+
+        lines.join("\n")
+    } else {
+        String::new()
+    };
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
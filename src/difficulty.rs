@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::Weights, confusion::Confusion, mode::GameMode, theme::Theme, topology::Topology,
+    FriendlyCollision, GameConfig, InputBuffering,
+};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Insane => "insane",
+        }
+    }
+
+    pub(crate) fn config(self) -> GameConfig {
+        match self {
+            Difficulty::Easy => GameConfig {
+                difficulty: self,
+                mode: GameMode::default(),
+                width: 40,
+                height: 32,
+                tick_interval: 1.0 / 15.0,
+                speed_ramp: 0.99,
+                min_tick_interval: 1.0 / 25.0,
+                wall_density: 0.0,
+                score_multiplier: 1,
+                time_limit: None,
+                seed: None,
+                shrink_interval: None,
+                escalation_interval: None,
+                vision: None,
+                wrap: false,
+                trail: false,
+                swap: false,
+                endless: false,
+                adaptive: false,
+                practice: false,
+                forgiving: false,
+                input_buffering: InputBuffering::Queue,
+                players: 1,
+                opponents: 0,
+                ai_levels: vec![],
+                evolved: Weights::default(),
+                friendly_collision: FriendlyCollision::Deadly,
+                confusion: Confusion::Off,
+                topology: Topology::Square,
+                theme: Theme::CLASSIC,
+                pixelated: false,
+                level: None,
+                mask: None,
+                lives: 3,
+            },
+            Difficulty::Normal => GameConfig {
+                difficulty: self,
+                mode: GameMode::default(),
+                width: 50,
+                height: 40,
+                tick_interval: 1.0 / 30.0,
+                speed_ramp: 0.995,
+                min_tick_interval: 1.0 / 45.0,
+                wall_density: 0.0,
+                score_multiplier: 2,
+                time_limit: None,
+                seed: None,
+                shrink_interval: None,
+                escalation_interval: None,
+                vision: None,
+                wrap: false,
+                trail: false,
+                swap: false,
+                endless: false,
+                adaptive: false,
+                practice: false,
+                forgiving: false,
+                input_buffering: InputBuffering::Queue,
+                players: 1,
+                opponents: 0,
+                ai_levels: vec![],
+                evolved: Weights::default(),
+                friendly_collision: FriendlyCollision::Deadly,
+                confusion: Confusion::Off,
+                topology: Topology::Square,
+                theme: Theme::CLASSIC,
+                pixelated: false,
+                level: None,
+                mask: None,
+                lives: 1,
+            },
+            Difficulty::Hard => GameConfig {
+                difficulty: self,
+                mode: GameMode::default(),
+                width: 60,
+                height: 48,
+                tick_interval: 1.0 / 40.0,
+                speed_ramp: 0.99,
+                min_tick_interval: 1.0 / 60.0,
+                wall_density: 0.01,
+                score_multiplier: 3,
+                time_limit: None,
+                seed: None,
+                shrink_interval: None,
+                escalation_interval: None,
+                vision: None,
+                wrap: false,
+                trail: false,
+                swap: false,
+                endless: false,
+                adaptive: false,
+                practice: false,
+                forgiving: false,
+                input_buffering: InputBuffering::Queue,
+                players: 1,
+                opponents: 0,
+                ai_levels: vec![],
+                evolved: Weights::default(),
+                friendly_collision: FriendlyCollision::Deadly,
+                confusion: Confusion::Off,
+                topology: Topology::Square,
+                theme: Theme::CLASSIC,
+                pixelated: false,
+                level: None,
+                mask: None,
+                lives: 1,
+            },
+            Difficulty::Insane => GameConfig {
+                difficulty: self,
+                mode: GameMode::default(),
+                width: 100,
+                height: 80,
+                tick_interval: 1.0 / 50.0,
+                speed_ramp: 0.985,
+                min_tick_interval: 1.0 / 90.0,
+                wall_density: 0.02,
+                score_multiplier: 5,
+                time_limit: None,
+                seed: None,
+                shrink_interval: None,
+                escalation_interval: None,
+                vision: None,
+                wrap: false,
+                trail: false,
+                swap: false,
+                endless: false,
+                adaptive: false,
+                practice: false,
+                forgiving: false,
+                input_buffering: InputBuffering::Queue,
+                players: 1,
+                opponents: 0,
+                ai_levels: vec![],
+                evolved: Weights::default(),
+                friendly_collision: FriendlyCollision::Deadly,
+                confusion: Confusion::Off,
+                topology: Topology::Square,
+                theme: Theme::CLASSIC,
+                pixelated: false,
+                level: None,
+                mask: None,
+                lives: 1,
+            },
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    enemy::Enemy,
+    hud::Toasts,
+    keys::{self, KeyColor},
+    level::{self, Level},
+    spawn_nodes,
+    terrain::{self, Terrain},
+    AppState, Controller, FoodKind, GameConfig, Offset, Position, Spawner, GRID_SCALE,
+};
+
+// enemies placed in the editor bounce, moving every other tick; patrol
+// paths can only be written in level files
+const ENEMY_INTERVAL: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Wall,
+    Erase,
+    Spawn,
+    Food,
+    Enemy,
+    Ice,
+    Mud,
+    Conveyor,
+    Key,
+    Door,
+}
+
+impl Tool {
+    const ALL: [Tool; 10] = [
+        Tool::Wall,
+        Tool::Erase,
+        Tool::Spawn,
+        Tool::Food,
+        Tool::Enemy,
+        Tool::Ice,
+        Tool::Mud,
+        Tool::Conveyor,
+        Tool::Key,
+        Tool::Door,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Tool::Wall => "walls",
+            Tool::Erase => "erase",
+            Tool::Spawn => "spawn",
+            Tool::Food => "food",
+            Tool::Enemy => "enemies",
+            Tool::Ice => "ice",
+            Tool::Mud => "mud",
+            Tool::Conveyor => "conveyors",
+            Tool::Key => "keys",
+            Tool::Door => "doors",
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Editor {
+    level: Level,
+    tool: Tool,
+    // the direction new enemies and conveyors are placed with
+    facing: Offset,
+    // the color new keys and doors are placed with
+    color: KeyColor,
+}
+
+impl Editor {
+    fn apply(&mut self, tool: Tool, position: Position) -> bool {
+        let level = &mut self.level;
+        let has_wall = level.walls.contains(&position);
+        let has_food = level.food == Some(position);
+        let has_enemy = level.enemies.iter().any(|e| e.start() == Some(position));
+        let has_key = level.keys.iter().any(|&(p, _)| p == position);
+        let has_door = level.doors.iter().any(|&(p, _)| p == position);
+        let blocked = has_wall || has_door;
+        let terrain = level
+            .terrain
+            .iter()
+            .find(|&&(p, _)| p == position)
+            .map(|&(_, t)| t);
+
+        match tool {
+            Tool::Wall | Tool::Door
+                if !blocked && !has_food && !has_enemy && !has_key && level.spawn != position =>
+            {
+                match tool {
+                    Tool::Wall => level.walls.push(position),
+                    _ => level.doors.push((position, self.color)),
+                }
+            }
+            Tool::Erase if blocked || has_food || has_enemy || has_key || terrain.is_some() => {
+                level.walls.retain(|&w| w != position);
+                level.food = level.food.filter(|&f| f != position);
+                level.enemies.retain(|e| e.start() != Some(position));
+                level.terrain.retain(|&(p, _)| p != position);
+                level.keys.retain(|&(p, _)| p != position);
+                level.doors.retain(|&(p, _)| p != position);
+            }
+            Tool::Spawn if level.spawn != position => {
+                level.spawn = position;
+                level.walls.retain(|&w| w != position);
+                level.doors.retain(|&(p, _)| p != position);
+            }
+            Tool::Food if !has_food && !blocked && !has_key => {
+                level.food = Some(position);
+            }
+            Tool::Key if !has_key && !blocked && !has_food => {
+                level.keys.push((position, self.color));
+            }
+            Tool::Enemy if !has_enemy && !blocked && level.spawn != position => {
+                level.enemies.push(Enemy::Bounce {
+                    start: position,
+                    facing: self.facing,
+                    interval: ENEMY_INTERVAL,
+                });
+            }
+            Tool::Ice | Tool::Mud | Tool::Conveyor if !blocked => {
+                let kind = match tool {
+                    Tool::Ice => Terrain::Ice,
+                    Tool::Mud => Terrain::Mud,
+                    _ => Terrain::Conveyor(self.facing),
+                };
+
+                if terrain == Some(kind) {
+                    return false;
+                }
+
+                level.terrain.retain(|&(p, _)| p != position);
+                level.terrain.push((position, kind));
+            }
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+#[derive(Component)]
+pub struct EditorCell;
+
+#[derive(Component)]
+pub struct EditorText;
+
+pub fn setup(mut cmd: Commands, mut config: ResMut<GameConfig>) {
+    let level = config
+        .level
+        .clone()
+        .or_else(Level::load)
+        .unwrap_or_else(|| Level::new(config.width, config.height));
+
+    config.width = level.width;
+    config.height = level.height;
+
+    cmd.insert_resource(Editor {
+        level,
+        tool: Tool::Wall,
+        facing: Offset::new(1, 0),
+        color: KeyColor::Red,
+    });
+
+    cmd.spawn((
+        EditorText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+pub fn cleanup(mut cmd: Commands, cells: Query<Entity, Or<(With<EditorCell>, With<EditorText>)>>) {
+    for entity in cells.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    cmd.remove_resource::<Editor>();
+}
+
+pub fn input(
+    input: Res<ButtonInput<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    mut config: ResMut<GameConfig>,
+    mut toast: Toasts,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(KeyCode::Tab) {
+        let index = Tool::ALL
+            .iter()
+            .position(|&t| t == editor.tool)
+            .unwrap_or(0);
+        editor.tool = Tool::ALL[(index + 1) % Tool::ALL.len()];
+    }
+
+    // f turns whatever the current tool places: new enemies and conveyors,
+    // or the spawn. for keys and doors it cycles their color instead
+    if input.just_pressed(KeyCode::KeyF) && matches!(editor.tool, Tool::Key | Tool::Door) {
+        let index = KeyColor::ALL
+            .iter()
+            .position(|&c| c == editor.color)
+            .unwrap_or(0);
+        editor.color = KeyColor::ALL[(index + 1) % KeyColor::ALL.len()];
+    } else if input.just_pressed(KeyCode::KeyF) {
+        let directions = config.topology.get().directions();
+        let editor = &mut *editor;
+        let facing = match editor.tool {
+            Tool::Enemy | Tool::Conveyor => &mut editor.facing,
+            _ => &mut editor.level.facing,
+        };
+        let index = directions.iter().position(|d| d == facing).unwrap_or(0);
+
+        *facing = directions[(index + 1) % directions.len()];
+    }
+
+    if input.just_pressed(KeyCode::KeyN) {
+        editor.level = Level::new(config.width, config.height);
+    }
+
+    if input.just_pressed(KeyCode::KeyS) {
+        editor.level.save();
+        toast.show("level saved");
+    }
+
+    if input.just_pressed(KeyCode::KeyL) {
+        match Level::load() {
+            Some(level) => {
+                config.width = level.width;
+                config.height = level.height;
+                editor.level = level;
+                toast.show("level loaded");
+            }
+            None => toast.show("no saved level"),
+        }
+    }
+
+    if input.just_pressed(KeyCode::KeyX) && level::export(&editor.level) {
+        toast.show("level code exported");
+    }
+
+    if input.just_pressed(KeyCode::KeyI) {
+        match level::import() {
+            Some(level) => {
+                config.width = level.width;
+                config.height = level.height;
+                editor.level = level;
+                toast.show("level imported");
+            }
+            None => toast.show("no valid level code"),
+        }
+    }
+
+    if input.just_pressed(KeyCode::Enter) {
+        editor.level.clone().apply(&mut config);
+        next_state.set(AppState::Playing);
+    }
+
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+pub fn paint(
+    mouse: Res<ButtonInput<MouseButton>>,
+    config: Res<GameConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut editor: ResMut<Editor>,
+) {
+    let tool = if mouse.pressed(MouseButton::Left) {
+        editor.tool
+    } else if mouse.pressed(MouseButton::Right) {
+        Tool::Erase
+    } else {
+        return;
+    };
+
+    let Some(cursor) = windows.get_single().ok().and_then(|w| w.cursor_position()) else {
+        return;
+    };
+
+    let Some(world) = cameras
+        .iter()
+        .find_map(|(camera, transform)| camera.viewport_to_world_2d(transform, cursor))
+    else {
+        return;
+    };
+
+    let Some(position) = cell_at(&config, world) else {
+        return;
+    };
+
+    // only flag a change when a cell actually changed so holding the button
+    // doesn't redraw the level every frame
+    if editor.bypass_change_detection().apply(tool, position) {
+        editor.set_changed();
+    }
+}
+
+// topologies only map cells to world space, so find the cell whose center
+// is closest to the cursor
+fn cell_at(config: &GameConfig, world: Vec2) -> Option<Position> {
+    let topology = config.topology.get();
+
+    (0..config.height)
+        .flat_map(|y| (0..config.width).map(move |x| Position::new(x, y)))
+        .map(|p| (p, topology.to_world(p).distance_squared(world)))
+        .filter(|&(_, d)| d <= GRID_SCALE * GRID_SCALE * 0.5)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(p, _)| p)
+}
+
+pub fn redraw(
+    mut cmd: Commands,
+    editor: Res<Editor>,
+    config: Res<GameConfig>,
+    spawner: Res<Spawner>,
+    cells: Query<Entity, With<EditorCell>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+
+    for entity in cells.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    let level = &editor.level;
+    let mut entities = vec![];
+
+    for &(position, kind) in level.terrain.iter() {
+        entities.push(terrain::spawn(&mut cmd, &spawner, &config, position, kind));
+    }
+
+    for &position in level.walls.iter() {
+        entities.push(spawner.new_wall(&mut cmd, position).entity);
+    }
+
+    for &(position, color) in level.doors.iter() {
+        entities.push(keys::new_door(&mut cmd, &spawner, position, color));
+    }
+
+    for &(position, color) in level.keys.iter() {
+        entities.push(keys::new_key(&mut cmd, &spawner, position, color).entity);
+    }
+
+    for (i, position) in spawn_nodes(&config, level.spawn, level.facing)
+        .into_iter()
+        .enumerate()
+    {
+        entities.push(
+            spawner
+                .new_node(&mut cmd, position, Controller::Player(0), i)
+                .entity,
+        );
+    }
+
+    for position in level.enemies.iter().filter_map(|e| e.start()) {
+        entities.push(spawner.new_enemy(&mut cmd, position));
+    }
+
+    if let Some(position) = level.food {
+        entities.push(
+            spawner
+                .new_food(&mut cmd, position, FoodKind::Regular)
+                .entity,
+        );
+    }
+
+    for entity in entities {
+        cmd.entity(entity).insert(EditorCell);
+    }
+}
+
+pub fn refresh(editor: Res<Editor>, mut texts: Query<&mut Text, With<EditorText>>) {
+    let action = match editor.tool {
+        Tool::Enemy => "rotate enemy direction".to_string(),
+        Tool::Conveyor => "rotate conveyor direction".to_string(),
+        Tool::Key | Tool::Door => format!("cycle color ({})", editor.color.name()),
+        _ => "rotate spawn".to_string(),
+    };
+
+    let value = format!(
+        "tool: {} (tab to switch, right click erases)\nf: {action}, n: clear, s: save, l: load, x: share, i: import, enter: playtest, escape: menu",
+        editor.tool.name()
+    );
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use bevy::{
+    asset::{
+        io::{
+            AssetReader, AssetReaderError, AssetSource, AssetSourceId, PathStream, Reader,
+            VecReader,
+        },
+        AssetApp,
+    },
+    prelude::*,
+    utils::BoxedFuture,
+};
+
+// every asset the game loads, compiled into the binary so the web build is a
+// single .wasm that never fetches anything. entries are paths under assets/,
+// added as
+//
+//     ("fonts/example.ttf", include_bytes!("../assets/fonts/example.ttf")),
+//
+// and loaded with the same path through the asset server
+const ASSETS: &[(&str, &[u8])] = &[];
+
+// serves `ASSETS` in place of the file system or http, on every build so the
+// native and web games can't end up with different assets
+struct EmbeddedReader;
+
+impl EmbeddedReader {
+    fn get(path: &Path) -> Option<&'static [u8]> {
+        ASSETS
+            .iter()
+            .find(|&&(name, _)| Path::new(name) == path)
+            .map(|&(_, bytes)| bytes)
+    }
+}
+
+impl AssetReader for EmbeddedReader {
+    fn read<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<Box<Reader<'a>>, AssetReaderError>> {
+        Box::pin(async move {
+            match Self::get(path) {
+                Some(bytes) => Ok(Box::new(VecReader::new(bytes.to_vec())) as Box<Reader>),
+                None => Err(AssetReaderError::NotFound(path.to_owned())),
+            }
+        })
+    }
+
+    // nothing is embedded with a .meta file, so every asset loads with its
+    // loader's default settings
+    fn read_meta<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<Box<Reader<'a>>, AssetReaderError>> {
+        Box::pin(async move { Err(AssetReaderError::NotFound(path.to_owned())) })
+    }
+
+    fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<Box<PathStream>, AssetReaderError>> {
+        Box::pin(async move { Err(AssetReaderError::NotFound(path.to_owned())) })
+    }
+
+    fn is_directory<'a>(
+        &'a self,
+        _path: &'a Path,
+    ) -> BoxedFuture<'a, Result<bool, AssetReaderError>> {
+        Box::pin(async move { Ok(false) })
+    }
+}
+
+// has to be called before the asset plugin is added
+pub fn register(app: &mut App) {
+    app.register_asset_source(
+        AssetSourceId::Default,
+        AssetSource::build().with_reader(|| Box::new(EmbeddedReader)),
+    );
+}
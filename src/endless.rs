@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{Game, GameConfig, Position, Spawner};
+
+// an endless board is a wrapping board big enough that nobody travels round
+// it, filled in a chunk at a time as the player gets close
+pub const ENDLESS_SIZE: usize = 4096;
+
+const CHUNK_SIZE: usize = 16;
+// chunks around the one holding the head that are kept generated
+const CHUNK_RADIUS: isize = 2;
+// chunks further than this from every head are cleared away again, so the
+// walls a long run leaves behind don't pile up
+const DROP_RADIUS: usize = CHUNK_RADIUS as usize + 2;
+const WALL_DENSITY: f64 = 0.03;
+// walls never appear this close to a head, so a chunk can't pop up a wall
+// right in front of the player
+const CLEARANCE: usize = 4;
+// food, pellets and power-ups land within this many cells of the player
+const PLACE_RADIUS: isize = 12;
+
+// generate the chunks around every living player that haven't been yet, and
+// clear the ones left far behind. each chunk rolls its walls from the seed
+// and its own coordinates, so a chunk comes out the same however the player
+// got there, and again if they come back to it
+pub fn generate(cmd: &mut Commands, spawner: &Spawner, config: &GameConfig, game: &mut Game) {
+    let chunks_x = (config.width / CHUNK_SIZE) as isize;
+    let chunks_y = (config.height / CHUNK_SIZE) as isize;
+
+    let heads: Vec<_> = game
+        .snakes
+        .iter()
+        .filter(|s| s.controller.is_player() && s.alive)
+        .map(|s| s.head())
+        .collect();
+
+    for head in heads.iter() {
+        let (cx, cy) = (
+            (head.x / CHUNK_SIZE) as isize,
+            (head.y / CHUNK_SIZE) as isize,
+        );
+
+        for dy in -CHUNK_RADIUS..=CHUNK_RADIUS {
+            for dx in -CHUNK_RADIUS..=CHUNK_RADIUS {
+                let chunk = (
+                    (cx + dx).rem_euclid(chunks_x) as usize,
+                    (cy + dy).rem_euclid(chunks_y) as usize,
+                );
+
+                if game.chunks.insert(chunk) {
+                    fill(cmd, spawner, game, &heads, chunk);
+                }
+            }
+        }
+    }
+
+    if heads.is_empty() {
+        return;
+    }
+
+    // chunk distance the short way round the wrapping board
+    let apart = |a: usize, b: usize, count: isize| {
+        let d = a.abs_diff(b);
+        d.min(count as usize - d)
+    };
+
+    let far = |&(cx, cy): &(usize, usize)| {
+        heads.iter().all(|h| {
+            apart(cx, h.x / CHUNK_SIZE, chunks_x) > DROP_RADIUS
+                || apart(cy, h.y / CHUNK_SIZE, chunks_y) > DROP_RADIUS
+        })
+    };
+
+    if !game.chunks.iter().any(far) {
+        return;
+    }
+
+    game.chunks.retain(|c| !far(c));
+
+    let (walls, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut game.walls)
+        .into_iter()
+        .partition(|w| far(&(w.position.x / CHUNK_SIZE, w.position.y / CHUNK_SIZE)));
+    game.walls = kept;
+
+    for wall in walls {
+        cmd.entity(wall.entity).despawn();
+    }
+}
+
+fn fill(
+    cmd: &mut Commands,
+    spawner: &Spawner,
+    game: &mut Game,
+    heads: &[Position],
+    (cx, cy): (usize, usize),
+) {
+    let mut rng =
+        ChaCha8Rng::seed_from_u64(game.replay.seed ^ ((cx as u64) << 40) ^ ((cy as u64) << 20));
+
+    for y in cy * CHUNK_SIZE..(cy + 1) * CHUNK_SIZE {
+        for x in cx * CHUNK_SIZE..(cx + 1) * CHUNK_SIZE {
+            let position = Position::new(x, y);
+
+            // rolled for every cell so a blocked cell doesn't shift the rest
+            if !rng.gen_bool(WALL_DENSITY) {
+                continue;
+            }
+
+            let near_head = heads
+                .iter()
+                .any(|h| h.x.abs_diff(x) <= CLEARANCE && h.y.abs_diff(y) <= CLEARANCE);
+
+            if !near_head && !game.is_occupied(position) {
+                game.walls.push(spawner.new_wall(cmd, position));
+            }
+        }
+    }
+}
+
+// a free cell near the player's head
+pub fn free_cell(config: &GameConfig, game: &mut Game) -> Position {
+    let head = game.player().head();
+
+    loop {
+        let x = head.x as isize + game.rng.gen_range(-PLACE_RADIUS..=PLACE_RADIUS);
+        let y = head.y as isize + game.rng.gen_range(-PLACE_RADIUS..=PLACE_RADIUS);
+        let position = Position::new(
+            x.rem_euclid(config.width as isize) as usize,
+            y.rem_euclid(config.height as isize) as usize,
+        );
+
+        if !game.is_occupied(position) {
+            return position;
+        }
+    }
+}
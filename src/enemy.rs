@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{advance, is_out_of_bounds, Game, GameConfig, Offset, Position, Spawner};
+
+// a hazard placed in a level: it either walks back and forth along a path of
+// waypoints, ignoring walls, or travels in a straight line and turns around
+// at walls and board edges. either way it moves once every `interval` ticks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Enemy {
+    Patrol {
+        path: Vec<Position>,
+        interval: u32,
+    },
+    Bounce {
+        start: Position,
+        facing: Offset,
+        interval: u32,
+    },
+}
+
+impl Enemy {
+    pub fn start(&self) -> Option<Position> {
+        match self {
+            Enemy::Patrol { path, .. } => path.first().copied(),
+            Enemy::Bounce { start, .. } => Some(*start),
+        }
+    }
+
+    fn interval(&self) -> u32 {
+        match self {
+            Enemy::Patrol { interval, .. } | Enemy::Bounce { interval, .. } => (*interval).max(1),
+        }
+    }
+}
+
+// how far an enemy is along its route, kept apart from its entity so saved
+// games can store it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Route {
+    enemy: Enemy,
+    pub position: Position,
+    waypoint: usize,
+    forward: bool,
+    facing: Offset,
+}
+
+impl Route {
+    pub fn new(enemy: Enemy) -> Option<Self> {
+        let position = enemy.start()?;
+        let facing = match &enemy {
+            Enemy::Bounce { facing, .. } => *facing,
+            Enemy::Patrol { .. } => Offset::new(0, 0),
+        };
+
+        Some(Self {
+            enemy,
+            position,
+            waypoint: 0,
+            forward: true,
+            facing,
+        })
+    }
+
+    fn step(&mut self, config: &GameConfig, game: &Game) {
+        match &self.enemy {
+            Enemy::Patrol { path, .. } => {
+                if path.len() < 2 {
+                    return;
+                }
+
+                if self.position == path[self.waypoint] {
+                    let last = path.len() - 1;
+
+                    self.forward = match self.waypoint {
+                        0 => true,
+                        w if w == last => false,
+                        _ => self.forward,
+                    };
+
+                    if self.forward {
+                        self.waypoint += 1;
+                    } else {
+                        self.waypoint -= 1;
+                    }
+                }
+
+                let target = path[self.waypoint];
+                let step = |from: usize, to: usize| match from.cmp(&to) {
+                    std::cmp::Ordering::Less => from + 1,
+                    std::cmp::Ordering::Equal => from,
+                    std::cmp::Ordering::Greater => from - 1,
+                };
+
+                self.position = Position::new(
+                    step(self.position.x, target.x),
+                    step(self.position.y, target.y),
+                );
+            }
+            Enemy::Bounce { .. } => {
+                let blocked = |position: Position| {
+                    is_out_of_bounds(config, position)
+                        || game.walls.iter().any(|w| w.position == position)
+                };
+
+                let mut next = advance(config, self.position, self.facing);
+
+                if blocked(next) {
+                    self.facing = -self.facing;
+                    next = advance(config, self.position, self.facing);
+                }
+
+                if !blocked(next) {
+                    self.position = next;
+                }
+            }
+        }
+    }
+}
+
+pub struct Hazard {
+    pub entity: Entity,
+    pub route: Route,
+    // where it was before this tick's step
+    previous: Position,
+}
+
+impl Hazard {
+    // whether a snake whose head moved from `neck` to `head` ran into it,
+    // including swapping cells with it
+    pub fn hits(&self, head: Position, neck: Option<Position>) -> bool {
+        self.route.position == head || (self.previous == head && Some(self.route.position) == neck)
+    }
+}
+
+pub fn spawn(cmd: &mut Commands, spawner: &Spawner, route: Route) -> Hazard {
+    Hazard {
+        entity: spawner.new_enemy(cmd, route.position),
+        previous: route.position,
+        route,
+    }
+}
+
+// advance every enemy that is due on this tick
+pub fn step(
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+) {
+    let ticks = game.replay.ticks;
+    let mut enemies = std::mem::take(&mut game.enemies);
+
+    for hazard in enemies.iter_mut() {
+        hazard.previous = hazard.route.position;
+
+        if !ticks.is_multiple_of(hazard.route.enemy.interval()) {
+            continue;
+        }
+
+        hazard.route.step(config, game);
+
+        if let Ok(mut transform) = transforms.get_mut(hazard.entity) {
+            *transform = spawner.transform(hazard.route.position);
+        }
+    }
+
+    game.enemies = enemies;
+}
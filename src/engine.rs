@@ -0,0 +1,53 @@
+use bevy::{
+    a11y::AccessibilityPlugin,
+    app::PluginGroupBuilder,
+    core::{FrameCountPlugin, TaskPoolPlugin, TypeRegistrationPlugin},
+    core_pipeline::CorePipelinePlugin,
+    diagnostic::DiagnosticsPlugin,
+    gilrs::GilrsPlugin,
+    input::InputPlugin,
+    log::LogPlugin,
+    prelude::*,
+    render::{texture::ImagePlugin, RenderPlugin},
+    sprite::SpritePlugin,
+    text::TextPlugin,
+    time::TimePlugin,
+    ui::UiPlugin,
+    winit::WinitPlugin,
+};
+
+// only the parts of bevy the game draws and plays with, in place of
+// `DefaultPlugins`, so the web build stays small. audio and gizmos are added
+// on top by `launch_with` when the features of the same names are on
+pub struct EnginePlugins {
+    pub window: Window,
+}
+
+impl PluginGroup for EnginePlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(LogPlugin::default())
+            .add(TaskPoolPlugin::default())
+            .add(TypeRegistrationPlugin)
+            .add(FrameCountPlugin)
+            .add(TimePlugin)
+            .add(TransformPlugin)
+            .add(HierarchyPlugin)
+            .add(DiagnosticsPlugin)
+            .add(InputPlugin)
+            .add(WindowPlugin {
+                primary_window: Some(self.window),
+                ..default()
+            })
+            .add(AccessibilityPlugin)
+            .add(AssetPlugin::default())
+            .add(WinitPlugin::default())
+            .add(RenderPlugin::default())
+            .add(ImagePlugin::default())
+            .add(CorePipelinePlugin)
+            .add(SpritePlugin)
+            .add(TextPlugin)
+            .add(UiPlugin)
+            .add(GilrsPlugin)
+    }
+}
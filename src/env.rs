@@ -0,0 +1,265 @@
+use bevy::{
+    ecs::system::{RunSystemOnce, SystemState},
+    prelude::*,
+};
+
+use crate::{
+    collide, daily::DailyRecord, move_snakes, new_food, rules::Rules, settings::Settings,
+    setup_game, steer_snakes, tick, Controller, Game, GameConfig, Offset, Position, Spawner,
+    PLAYER,
+};
+
+pub const EMPTY: u8 = 0;
+pub const WALL: u8 = 1;
+pub const BODY: u8 = 2;
+pub const HEAD: u8 = 3;
+pub const FOOD: u8 = 4;
+pub const OPPONENT: u8 = 5;
+pub const ENEMY: u8 = 6;
+pub const PICKUP: u8 = 7;
+
+const FOOD_REWARD: f32 = 1.0;
+const DEATH_REWARD: f32 = -1.0;
+
+// the board as one cell code per cell in row-major order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observation {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<u8>,
+}
+
+type TickState = SystemState<(
+    Commands<'static, 'static>,
+    Query<'static, 'static, &'static mut Transform>,
+    Res<'static, Spawner>,
+    Res<'static, GameConfig>,
+    ResMut<'static, Game>,
+)>;
+
+// drives the real game systems in a bare world without rendering or timing,
+// one tick per step, for training agents against the exact game rules
+pub struct SnakeEnv {
+    world: World,
+    tick: TickState,
+}
+
+impl SnakeEnv {
+    pub fn new() -> Self {
+        Self::with_config(GameConfig::default())
+    }
+
+    pub fn with_size(width: usize, height: usize) -> Self {
+        let config = GameConfig {
+            width,
+            height,
+            ..default()
+        };
+
+        Self::with_config(config)
+    }
+
+    pub(crate) fn with_config(config: GameConfig) -> Self {
+        let mut world = World::new();
+
+        let mut meshes = Assets::<Mesh>::default();
+        let mut materials = Assets::<ColorMaterial>::default();
+        let spawner = Spawner::setup(&mut meshes, &mut materials);
+
+        world.insert_resource(meshes);
+        world.insert_resource(materials);
+        world.insert_resource(spawner);
+        world.insert_resource(config);
+        world.insert_resource(DailyRecord::default());
+        world.insert_resource(Settings::default());
+        world.insert_resource(Rules::default());
+
+        let tick = SystemState::new(&mut world);
+
+        Self { world, tick }
+    }
+
+    pub fn action_count(&self) -> usize {
+        let config = self.world.resource::<GameConfig>();
+
+        config.topology.get().directions().len()
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.world.resource_mut::<GameConfig>().seed = Some(seed);
+
+        // the game's entities are the only ones in this world
+        self.world.remove_resource::<Game>();
+        self.world.clear_entities();
+
+        self.world.run_system_once(setup_game);
+        self.world.resource_mut::<Game>().countdown = None;
+
+        self.observe()
+    }
+
+    // `action` indexes the board's directions; reversing is ignored just
+    // like a reversing key press
+    pub fn step(&mut self, action: usize) -> (Observation, f32, bool) {
+        let (score, alive) = self.with_game(|cmd, transforms, spawner, config, game| {
+            if let Some(&offset) = config.topology.get().directions().get(action) {
+                let queue = &mut game.snakes[PLAYER].input_queue;
+                queue.clear();
+                queue.push_back(offset);
+            }
+
+            let score = game.score;
+            tick(cmd, transforms, spawner, config, game);
+
+            (score, game.player().alive)
+        });
+
+        let reward = if !alive {
+            DEATH_REWARD
+        } else if self.world.resource::<Game>().score > score {
+            FOOD_REWARD
+        } else {
+            0.0
+        };
+
+        (self.observe(), reward, !alive)
+    }
+
+    // the action an ai policy would take in the player's place
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn policy_action(&self, policy: &dyn crate::ai::SnakePolicy) -> usize {
+        let config = self.world.resource::<GameConfig>();
+        let game = self.world.resource::<Game>();
+        let facing = policy.steer(game, config, PLAYER);
+
+        config
+            .topology
+            .get()
+            .directions()
+            .iter()
+            .position(|&d| d == facing)
+            .unwrap_or(0)
+    }
+
+    fn with_game<R>(
+        &mut self,
+        f: impl FnOnce(&mut Commands, &mut Query<&mut Transform>, &Spawner, &GameConfig, &mut Game) -> R,
+    ) -> R {
+        let (mut cmd, mut transforms, spawner, config, mut game) =
+            self.tick.get_mut(&mut self.world);
+
+        let result = f(&mut cmd, &mut transforms, &spawner, &config, &mut game);
+
+        self.tick.apply(&mut self.world);
+
+        result
+    }
+
+    // the hooks below split a tick into its phases for the benchmarks
+
+    #[doc(hidden)]
+    pub fn set_length(&mut self, length: usize) {
+        self.with_game(|cmd, _, spawner, config, game| {
+            let snake = &mut game.snakes[PLAYER];
+
+            for node in snake.nodes.drain(..) {
+                cmd.entity(node.entity).despawn();
+            }
+
+            // wind the body back and forth across the rows, ending on the
+            // head which faces along the row it is on
+            for i in 0..length.min(config.width * config.height) {
+                let (row, column) = (i / config.width, i % config.width);
+                let x = match row % 2 {
+                    0 => column,
+                    _ => config.width - 1 - column,
+                };
+
+                let position = Position::new(x, row);
+                snake
+                    .nodes
+                    .push(spawner.new_node(cmd, position, snake.controller, i));
+            }
+
+            let row = (length.max(1) - 1) / config.width;
+            snake.facing = Offset::new(if row.is_multiple_of(2) { 1 } else { -1 }, 0);
+        });
+    }
+
+    #[doc(hidden)]
+    pub fn advance(&mut self) {
+        self.with_game(|cmd, transforms, spawner, config, game| {
+            steer_snakes(config, game);
+            move_snakes(cmd, transforms, spawner, config, game);
+        });
+    }
+
+    #[doc(hidden)]
+    pub fn collide(&mut self) {
+        self.with_game(|cmd, transforms, spawner, config, game| {
+            collide(cmd, transforms, spawner, config, game, vec![PLAYER]);
+        });
+    }
+
+    #[doc(hidden)]
+    pub fn place_food(&mut self) {
+        self.with_game(|cmd, transforms, spawner, config, game| {
+            new_food(cmd, transforms, spawner, config, game);
+        });
+    }
+
+    pub fn observe(&self) -> Observation {
+        let config = self.world.resource::<GameConfig>();
+        let game = self.world.resource::<Game>();
+
+        let mut cells = vec![EMPTY; config.width * config.height];
+        let mut plot = |position: Position, code: u8| {
+            if position.x < config.width && position.y < config.height {
+                cells[position.y * config.width + position.x] = code;
+            }
+        };
+
+        for wall in game.walls.iter() {
+            plot(wall.position, WALL);
+        }
+
+        for food in game.food.iter().chain(game.pellets.iter()) {
+            plot(food.position, FOOD);
+        }
+
+        for pickup in game.pickups.iter() {
+            plot(pickup.position, PICKUP);
+        }
+
+        for hazard in game.enemies.iter() {
+            plot(hazard.route.position, ENEMY);
+        }
+
+        for snake in game.snakes.iter() {
+            for node in snake.nodes.iter() {
+                let code = match snake.controller {
+                    Controller::Player(_) => BODY,
+                    Controller::Ai => OPPONENT,
+                };
+
+                plot(node.position, code);
+            }
+        }
+
+        if let Some(head) = game.player().nodes.last() {
+            plot(head.position, HEAD);
+        }
+
+        Observation {
+            width: config.width,
+            height: config.height,
+            cells,
+        }
+    }
+}
+
+impl Default for SnakeEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
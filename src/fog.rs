@@ -0,0 +1,97 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+
+use crate::{topology::Topology, Game, GameConfig, Position, Spawner, GRID_SCALE};
+
+// fog opacity outside the vision radius, leaving a faint hint of the board
+pub const FOG_ALPHA: f32 = 0.92;
+
+// above everything on the board, including the food value label
+const FOG_Z: f32 = 2.0;
+
+#[derive(Component)]
+pub struct FogCell(Position);
+
+// the vision mask: one dimming cell over every board cell, hidden within the
+// vision radius of a living player's head and recomputed whenever the game
+// ticks. the whole board is revealed once the round is over
+pub fn update(
+    mut cmd: Commands,
+    config: Res<GameConfig>,
+    spawner: Res<Spawner>,
+    game: Option<Res<Game>>,
+    mut cells: Query<(Entity, &FogCell, &mut Visibility)>,
+    mut last_tick: Local<Option<u32>>,
+) {
+    let (Some(game), Some(vision)) = (game.as_ref(), config.vision) else {
+        for (entity, _, _) in cells.iter() {
+            cmd.entity(entity).despawn();
+        }
+
+        *last_tick = None;
+        return;
+    };
+
+    if cells.iter().count() != config.width * config.height {
+        for (entity, _, _) in cells.iter() {
+            cmd.entity(entity).despawn();
+        }
+
+        let mesh = match config.topology {
+            Topology::Square => spawner.pixel_mesh.clone(),
+            Topology::Hex => spawner.hex_mesh.clone(),
+        };
+
+        for y in 0..config.height {
+            for x in 0..config.width {
+                let position = Position::new(x, y);
+
+                let mut transform = spawner.transform(position);
+                transform.translation.z = FOG_Z;
+
+                cmd.spawn((
+                    FogCell(position),
+                    MaterialMesh2dBundle {
+                        mesh: mesh.clone(),
+                        material: spawner.fog_material.clone(),
+                        transform,
+                        ..default()
+                    },
+                ));
+            }
+        }
+
+        *last_tick = None;
+        return;
+    }
+
+    if !game.is_added() && *last_tick == Some(game.replay.ticks) {
+        return;
+    }
+
+    *last_tick = Some(game.replay.ticks);
+
+    let topology = config.topology.get();
+    let radius = vision as f32 * GRID_SCALE;
+
+    let heads: Vec<_> = game
+        .snakes
+        .iter()
+        .filter(|s| s.controller.is_player() && s.alive)
+        .map(|s| topology.to_world(s.head()))
+        .collect();
+
+    for (_, cell, mut visibility) in cells.iter_mut() {
+        let world = topology.to_world(cell.0);
+        let visible = game.is_over() || heads.iter().any(|h| h.distance(world) <= radius);
+
+        let value = if visible {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+
+        if *visibility != value {
+            *visibility = value;
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+
+use crate::{
+    is_out_of_bounds, settings::Settings, Game, GameConfig, Position, Spawner, MAX_GRID_CELLS,
+};
+
+// cells are tinted in this many steps, from visited once up to the most
+// visited cell on the board
+pub const HEAT_LEVELS: usize = 8;
+const MAX_ALPHA: f32 = 0.6;
+const HEAT_Z: f32 = -0.6;
+
+// a cell at each step of heat, as a shade of the snake
+pub fn alpha(level: usize) -> f32 {
+    MAX_ALPHA * (level + 1) as f32 / HEAT_LEVELS as f32
+}
+
+// how many ticks the players' heads have spent on each cell this round, in
+// row-major order
+#[derive(Resource, Default)]
+pub struct Heatmap {
+    width: usize,
+    counts: Vec<u32>,
+    tick: u32,
+    markers: HashMap<Position, (Entity, usize)>,
+}
+
+impl Heatmap {
+    fn clear(&mut self, cmd: &mut Commands) {
+        for (_, (entity, _)) in self.markers.drain() {
+            cmd.entity(entity).despawn();
+        }
+    }
+
+    // a new round starts with a cold board, even on the same size
+    pub fn start_round(&mut self, cmd: &mut Commands) {
+        self.clear(cmd);
+        self.counts.clear();
+    }
+}
+
+pub fn update(
+    mut cmd: Commands,
+    spawner: Res<Spawner>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    game: Option<Res<Game>>,
+    mut heatmap: ResMut<Heatmap>,
+    mut materials: Query<&mut Handle<ColorMaterial>>,
+) {
+    let Some(game) = game else {
+        heatmap.clear(&mut cmd);
+        return;
+    };
+
+    // a buffer for an endless board would be millions of cells
+    let cells = config.width * config.height;
+
+    if cells > MAX_GRID_CELLS {
+        return;
+    }
+
+    if heatmap.counts.len() != cells {
+        heatmap.clear(&mut cmd);
+        heatmap.width = config.width;
+        heatmap.counts = vec![0; cells];
+        heatmap.tick = game.replay.ticks;
+    }
+
+    if heatmap.tick != game.replay.ticks {
+        heatmap.tick = game.replay.ticks;
+
+        let width = heatmap.width;
+        let heads = game
+            .snakes
+            .iter()
+            .filter(|s| s.controller.is_player() && s.alive)
+            .map(|s| s.head())
+            .filter(|&h| !is_out_of_bounds(&config, h));
+
+        for head in heads {
+            heatmap.counts[head.y * width + head.x] += 1;
+        }
+    } else if !settings.is_changed() {
+        return;
+    }
+
+    if !settings.heatmap {
+        heatmap.clear(&mut cmd);
+        return;
+    }
+
+    let max = heatmap.counts.iter().copied().max().unwrap_or(0);
+    let heatmap = &mut *heatmap;
+
+    for (cell, &count) in heatmap.counts.iter().enumerate().filter(|(_, &c)| c > 0) {
+        let position = Position::new(cell % heatmap.width, cell / heatmap.width);
+        let level = (count as usize - 1) * HEAT_LEVELS / max as usize;
+        let material = &spawner.heat_materials[level];
+
+        match heatmap.markers.get_mut(&position) {
+            Some((_, drawn)) if *drawn == level => {}
+            Some((entity, drawn)) => {
+                if let Ok(mut handle) = materials.get_mut(*entity) {
+                    *handle = material.clone();
+                }
+                *drawn = level;
+            }
+            None => {
+                let mut transform = spawner.transform(position);
+                transform.translation.z = HEAT_Z;
+
+                let entity = cmd
+                    .spawn(MaterialMesh2dBundle {
+                        mesh: spawner.mesh.clone(),
+                        material: material.clone(),
+                        transform,
+                        ..default()
+                    })
+                    .id();
+
+                heatmap.markers.insert(position, (entity, level));
+            }
+        }
+    }
+}
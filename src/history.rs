@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    daily, difficulty::Difficulty, hud::Toasts, mode::GameMode, storage, Death, Game, GameConfig,
+    RoundOver,
+};
+
+const STORAGE_KEY: &str = "history";
+
+// one finished round, written out as a line of json for anything that wants
+// to look over how a player is doing
+#[derive(Serialize)]
+struct Run {
+    date: u64,
+    mode: GameMode,
+    difficulty: Difficulty,
+    width: usize,
+    height: usize,
+    wrap: bool,
+    players: usize,
+    opponents: usize,
+    time_limit: Option<f32>,
+    seed: u64,
+    speed: f32,
+    score: u32,
+    length: usize,
+    seconds: f32,
+    death: Option<Death>,
+}
+
+pub fn record(mut events: EventReader<RoundOver>, config: Res<GameConfig>, game: Res<Game>) {
+    for event in events.read() {
+        let run = Run {
+            date: daily::now_millis(),
+            mode: config.mode,
+            difficulty: config.difficulty,
+            width: config.width,
+            height: config.height,
+            wrap: config.wrap,
+            players: config.players,
+            opponents: config.opponents,
+            time_limit: config.time_limit,
+            seed: game.replay.seed,
+            speed: game.speed,
+            score: event.score,
+            length: event.length,
+            seconds: game.played,
+            death: event.death,
+        };
+
+        match serde_json::to_string(&run) {
+            Ok(line) => storage::append_log(STORAGE_KEY, &line),
+            Err(e) => warn!("failed to serialize run: {e}"),
+        }
+    }
+}
+
+// the log is already a file on native, the web build hands it over as a
+// download
+pub fn export(input: Res<ButtonInput<KeyCode>>, game: Res<Game>, mut toast: Toasts) {
+    if !game.is_over() || !input.just_pressed(KeyCode::KeyL) {
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = storage::log_path(STORAGE_KEY) {
+        toast.show(format!("runs are logged to {}", path.display()));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    match storage::read_log(STORAGE_KEY) {
+        Some(log) => {
+            let name = format!("snake-history-{}.jsonl", daily::now_millis());
+
+            match crate::clip::save(&name, log.as_bytes(), "application/x-ndjson") {
+                Some(name) => toast.show(format!("saved {name}")),
+                None => toast.show("failed to save the run history"),
+            }
+        }
+        None => toast.show("no runs have been logged yet"),
+    }
+}
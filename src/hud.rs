@@ -0,0 +1,786 @@
+use std::collections::VecDeque;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    objectives, powerup,
+    scores::NameEntry,
+    settings::{self, Settings},
+    AppState, Game, GameConfig, Offset, GRID_SCALE,
+};
+
+#[derive(Component)]
+pub struct CountdownText;
+
+const TOAST_DURATION: f32 = 2.0;
+const TOAST_SLIDE: f32 = 0.2;
+const TOAST_FADE: f32 = 0.4;
+// older messages are dropped past this so a burst doesn't keep the corner
+// busy for half a minute
+const TOAST_QUEUE: usize = 4;
+const TOAST_MARGIN: f32 = 10.0;
+
+// how many past inputs the input display lists
+pub const INPUT_LOG: usize = 6;
+
+// asks for a message to be shown in the corner. sent through `Toasts` by
+// anything that wants to tell the player something
+#[derive(Event)]
+pub struct ShowToast(pub String);
+
+#[derive(SystemParam)]
+pub struct Toasts<'w> {
+    events: EventWriter<'w, ShowToast>,
+}
+
+impl Toasts<'_> {
+    pub fn show(&mut self, message: impl Into<String>) {
+        self.events.send(ShowToast(message.into()));
+    }
+}
+
+// messages waiting their turn, and the one on screen with how long it has
+// been up
+#[derive(Resource, Default)]
+pub struct ToastQueue {
+    waiting: VecDeque<String>,
+    current: Option<(String, f32)>,
+}
+
+#[derive(Component)]
+pub struct ToastText;
+
+#[derive(Component)]
+pub struct PauseText;
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Component)]
+pub struct TimeLimitText;
+
+#[derive(Component)]
+pub struct LivesText;
+
+#[derive(Component)]
+pub struct MagnetText;
+
+#[derive(Component)]
+pub struct ObjectiveText;
+
+#[derive(Component)]
+pub struct SlowMotionOverlay;
+
+const SLOW_MOTION_TINT: Color = Color::rgba(0.2, 0.4, 1.0, 0.15);
+
+#[derive(Component)]
+pub struct FoodValueText;
+
+#[derive(Component)]
+pub struct InputText;
+
+#[derive(Component)]
+pub struct NameEntryText;
+
+#[derive(Component)]
+pub struct GameOverText;
+
+const KEYS: [(KeyCode, char); 36] = [
+    (KeyCode::KeyA, 'a'),
+    (KeyCode::KeyB, 'b'),
+    (KeyCode::KeyC, 'c'),
+    (KeyCode::KeyD, 'd'),
+    (KeyCode::KeyE, 'e'),
+    (KeyCode::KeyF, 'f'),
+    (KeyCode::KeyG, 'g'),
+    (KeyCode::KeyH, 'h'),
+    (KeyCode::KeyI, 'i'),
+    (KeyCode::KeyJ, 'j'),
+    (KeyCode::KeyK, 'k'),
+    (KeyCode::KeyL, 'l'),
+    (KeyCode::KeyM, 'm'),
+    (KeyCode::KeyN, 'n'),
+    (KeyCode::KeyO, 'o'),
+    (KeyCode::KeyP, 'p'),
+    (KeyCode::KeyQ, 'q'),
+    (KeyCode::KeyR, 'r'),
+    (KeyCode::KeyS, 's'),
+    (KeyCode::KeyT, 't'),
+    (KeyCode::KeyU, 'u'),
+    (KeyCode::KeyV, 'v'),
+    (KeyCode::KeyW, 'w'),
+    (KeyCode::KeyX, 'x'),
+    (KeyCode::KeyY, 'y'),
+    (KeyCode::KeyZ, 'z'),
+    (KeyCode::Digit0, '0'),
+    (KeyCode::Digit1, '1'),
+    (KeyCode::Digit2, '2'),
+    (KeyCode::Digit3, '3'),
+    (KeyCode::Digit4, '4'),
+    (KeyCode::Digit5, '5'),
+    (KeyCode::Digit6, '6'),
+    (KeyCode::Digit7, '7'),
+    (KeyCode::Digit8, '8'),
+    (KeyCode::Digit9, '9'),
+];
+
+// the letters and digits typed this frame, for screens that take text
+pub fn typed(input: &ButtonInput<KeyCode>) -> impl Iterator<Item = char> + '_ {
+    KEYS.iter()
+        .filter(|&&(key, _)| input.just_pressed(key))
+        .map(|&(_, c)| c)
+}
+
+pub fn setup(mut cmd: Commands) {
+    // lives in the world rather than the ui so it sits above the food
+    cmd.spawn((
+        FoodValueText,
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: GRID_SCALE,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+
+    cmd.spawn((
+        ScoreText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+
+    cmd.spawn((
+        SlowMotionOverlay,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        },
+    ));
+
+    cmd.spawn((
+        LivesText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(40.0),
+            ..default()
+        }),
+    ));
+
+    cmd.spawn((
+        MagnetText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(60.0),
+            ..default()
+        }),
+    ));
+
+    cmd.spawn((
+        ObjectiveText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(80.0),
+            ..default()
+        }),
+    ));
+
+    cmd.spawn((
+        TimeLimitText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            top: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+
+    cmd.spawn((
+        ToastText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(TOAST_MARGIN),
+            bottom: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+
+    cmd.spawn((
+        InputText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        )
+        .with_text_justify(JustifyText::Right)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+
+    cmd.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ..default()
+    })
+    .with_children(|parent| {
+        parent.spawn((
+            CountdownText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 80.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+        ));
+
+        parent.spawn((
+            PauseText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            )
+            .with_text_justify(JustifyText::Center),
+        ));
+
+        parent.spawn((
+            GameOverText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            )
+            .with_text_justify(JustifyText::Center),
+        ));
+
+        parent.spawn((
+            NameEntryText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            )
+            .with_text_justify(JustifyText::Center),
+        ));
+    });
+}
+
+pub fn update_countdown(game: Option<Res<Game>>, mut texts: Query<&mut Text, With<CountdownText>>) {
+    let value = game
+        .as_ref()
+        .and_then(|g| g.countdown.as_ref())
+        .map(|t| t.remaining_secs().ceil().to_string())
+        .unwrap_or_default();
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// slides each message in from the left, holds it, then fades it out before
+// bringing in the next
+pub fn update_toast(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut events: EventReader<ShowToast>,
+    mut queue: ResMut<ToastQueue>,
+    mut texts: Query<(&mut Text, &mut Style), With<ToastText>>,
+) {
+    for ShowToast(message) in events.read() {
+        // the same message twice in a row only needs showing once
+        let repeated = queue
+            .waiting
+            .back()
+            .or(queue.current.as_ref().map(|(m, _)| m))
+            == Some(message);
+        if !repeated {
+            queue.waiting.push_back(message.clone());
+        }
+    }
+
+    while queue.waiting.len() > TOAST_QUEUE {
+        queue.waiting.pop_front();
+    }
+
+    if let Some((_, age)) = queue.current.as_mut() {
+        *age += time.delta_seconds();
+
+        if *age >= TOAST_SLIDE + TOAST_DURATION + TOAST_FADE {
+            queue.current = None;
+        }
+    }
+
+    if queue.current.is_none() {
+        queue.current = queue.waiting.pop_front().map(|m| (m, 0.0));
+    }
+
+    let (value, age) = queue.current.clone().unwrap_or_default();
+
+    let slide = if settings.reduced_motion {
+        1.0
+    } else {
+        (age / TOAST_SLIDE).min(1.0)
+    };
+    let alpha = 1.0 - ((age - TOAST_SLIDE - TOAST_DURATION) / TOAST_FADE).clamp(0.0, 1.0);
+
+    for (mut text, mut style) in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+
+        if text.sections[0].style.color.a() != alpha {
+            text.sections[0].style.color.set_a(alpha);
+        }
+
+        // ease out so it settles into place
+        let left = Val::Px(TOAST_MARGIN - 200.0 * (1.0 - slide).powi(2));
+        if style.left != left {
+            style.left = left;
+        }
+    }
+}
+
+// rounds being played get the pause menu instead, this only labels paused
+// replays
+pub fn update_pause(
+    state: Res<State<AppState>>,
+    game: Option<Res<Game>>,
+    settings: Res<Settings>,
+    mut texts: Query<&mut Text, With<PauseText>>,
+) {
+    let value = match game {
+        Some(game) if game.paused && *state.get() != AppState::Playing => settings.language.format(
+            "paused\nspeed {}\nleft/right to change, p to resume",
+            &[&settings::speed_label(settings.speed)],
+        ),
+        _ => String::new(),
+    };
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// what ended the round and where, until the next one starts
+pub fn update_game_over(
+    game: Option<Res<Game>>,
+    settings: Res<Settings>,
+    mut texts: Query<&mut Text, With<GameOverText>>,
+) {
+    let language = settings.language;
+
+    let value = match game {
+        Some(game) if game.is_over() => match game.death {
+            Some(death) => language.format(
+                "game over\n{} at {}, {}",
+                &[
+                    &language.text(death.cause.describe()),
+                    &death.position.x,
+                    &death.position.y,
+                ],
+            ),
+            None => language.text("game over").to_owned(),
+        },
+        _ => String::new(),
+    };
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub fn update_name_entry(
+    entry: Option<Res<NameEntry>>,
+    settings: Res<Settings>,
+    mut texts: Query<&mut Text, With<NameEntryText>>,
+) {
+    let value = match entry {
+        Some(entry) => {
+            let letters: String = entry
+                .name()
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    if i == entry.cursor() {
+                        format!("[{c}]")
+                    } else {
+                        format!(" {c} ")
+                    }
+                })
+                .collect();
+
+            settings.language.format(
+                "new high score {}\n{}\nup/down or type to pick letters, enter to save",
+                &[&entry.score(), &letters],
+            )
+        }
+        None => String::new(),
+    };
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub fn update_score(game: Option<Res<Game>>, mut texts: Query<&mut Text, With<ScoreText>>) {
+    let value = game
+        .as_ref()
+        .map(|g| g.score.to_string())
+        .unwrap_or_default();
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub fn update_lives(
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    game: Option<Res<Game>>,
+    mut texts: Query<&mut Text, With<LivesText>>,
+) {
+    let value = game
+        .as_ref()
+        .filter(|_| config.lives > 1)
+        .map(|g| settings.language.format("lives: {}", &[&g.lives]))
+        .unwrap_or_default();
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub fn update_magnet(game: Option<Res<Game>>, mut texts: Query<&mut Text, With<MagnetText>>) {
+    const WIDTH: u32 = 10;
+
+    let value = game
+        .as_ref()
+        .filter(|g| g.magnet > 0)
+        .map(|g| {
+            let filled = (g.magnet * WIDTH).div_ceil(powerup::MAGNET_TICKS) as usize;
+            format!(
+                "magnet [{}{}]",
+                "=".repeat(filled),
+                "-".repeat(WIDTH as usize - filled)
+            )
+        })
+        .unwrap_or_default();
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// flicker the players while they are invulnerable after a respawn. the
+// nodes are only gone over when the flicker flips or the snakes change
+pub fn update_invulnerable(
+    game: Option<Res<Game>>,
+    mut visibilities: Query<&mut Visibility>,
+    mut shown: Local<Option<(Visibility, Vec<(Entity, usize)>)>>,
+) {
+    let Some(game) = game else {
+        return;
+    };
+
+    let visibility = match game.invulnerable / 4 % 2 {
+        1 => Visibility::Hidden,
+        _ => Visibility::Inherited,
+    };
+
+    let players = || game.snakes.iter().filter(|s| s.controller.is_player());
+
+    // a snake's first node and length stand in for all of its nodes
+    let snakes = players()
+        .filter_map(|s| Some((s.nodes.first()?.entity, s.nodes.len())))
+        .collect();
+    let state = Some((visibility, snakes));
+
+    if *shown == state {
+        return;
+    }
+
+    *shown = state;
+
+    for snake in players() {
+        for node in snake.nodes.iter() {
+            if let Ok(mut current) = visibilities.get_mut(node.entity) {
+                if *current != visibility {
+                    *current = visibility;
+                }
+            }
+        }
+    }
+}
+
+pub fn update_slow_motion(
+    game: Option<Res<Game>>,
+    mut overlays: Query<&mut BackgroundColor, With<SlowMotionOverlay>>,
+) {
+    let color = match game {
+        Some(game) if game.slow_motion > 0 && !game.is_over() => SLOW_MOTION_TINT,
+        _ => Color::NONE,
+    };
+
+    for mut background in overlays.iter_mut() {
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+}
+
+pub fn update_food_value(
+    game: Option<Res<Game>>,
+    foods: Query<&Transform, Without<FoodValueText>>,
+    mut texts: Query<(&mut Text, &mut Transform, &mut Visibility), With<FoodValueText>>,
+) {
+    let food = game.as_ref().and_then(|g| {
+        let food = g.food.as_ref()?;
+        let transform = foods.get(food.entity).ok()?;
+        Some((g.food_value.round() as u32, transform.translation))
+    });
+
+    for (mut text, mut transform, mut visibility) in texts.iter_mut() {
+        let Some((value, translation)) = food else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let value = value.to_string();
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+
+        transform.translation = translation + Vec3::new(0.0, GRID_SCALE, 1.0);
+        *visibility = Visibility::Visible;
+    }
+}
+
+pub fn update_time_limit(
+    game: Option<Res<Game>>,
+    mut texts: Query<&mut Text, With<TimeLimitText>>,
+) {
+    let value = game
+        .as_ref()
+        .and_then(|g| g.time_limit.as_ref())
+        .map(|t| {
+            let seconds = t.remaining_secs().ceil() as u32;
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        })
+        .unwrap_or_default();
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// one line per objective of the level being played, then how the round went
+// once it's over
+pub fn update_objectives(
+    game: Option<Res<Game>>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    mut texts: Query<&mut Text, With<ObjectiveText>>,
+) {
+    let language = settings.language;
+
+    let value = match (game, objectives::of(&config)) {
+        (Some(game), Some(objectives)) => {
+            let mut lines = vec![];
+
+            if let Some(length) = objectives.length {
+                let current = game.player().nodes.len().min(length);
+                lines.push(language.format("length {}/{}", &[&current, &length]));
+            }
+
+            if let Some(food) = objectives.food {
+                let current = game.food_eaten.min(food);
+                lines.push(language.format("food {}/{}", &[&current, &food]));
+            }
+
+            if objectives.no_walls {
+                let line = if game.touched_wall.is_some() {
+                    "touched a wall"
+                } else {
+                    "don't touch the walls"
+                };
+                lines.push(language.text(line).to_owned());
+            }
+
+            match objectives::status(objectives, &game) {
+                objectives::Status::Playing => {}
+                objectives::Status::Complete => {
+                    lines.push(language.text("level complete").to_owned());
+                }
+                objectives::Status::Failed => {
+                    lines.push(language.text("objectives failed").to_owned());
+                }
+            }
+
+            lines.join("\n")
+        }
+        _ => String::new(),
+    };
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// the queued turns of the snake in control on top, then the latest presses
+// with the tick each came on, newest first
+pub fn update_inputs(
+    game: Option<Res<Game>>,
+    settings: Res<Settings>,
+    mut texts: Query<&mut Text, With<InputText>>,
+) {
+    let value = match game {
+        Some(game) if settings.input_display => {
+            let queue: Vec<_> = game
+                .player()
+                .input_queue
+                .iter()
+                .map(|&o| arrow(o))
+                .collect();
+            let presses = game
+                .inputs
+                .iter()
+                .rev()
+                .map(|&(tick, offset)| format!("{tick:>6} {:>2}", arrow(offset)));
+
+            std::iter::once(settings.language.format("queue [{}]", &[&queue.join(" ")]))
+                .chain(presses)
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        _ => String::new(),
+    };
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub fn arrow(offset: Offset) -> &'static str {
+    match (offset.x.signum(), offset.y.signum()) {
+        (0, -1) => "^",
+        (0, 1) => "v",
+        (1, 0) => ">",
+        (-1, 0) => "<",
+        (1, -1) => "^>",
+        (-1, -1) => "<^",
+        (1, 1) => "v>",
+        (-1, 1) => "<v",
+        _ => "",
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use serde::{Deserialize, Serialize};
+
+use crate::{theme::Theme, Game, Position, Spawner};
+
+const OPEN_SECONDS: f32 = 0.3;
+
+// picking up a key opens every door of the same color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyColor {
+    Red,
+    Blue,
+    Green,
+    Yellow,
+}
+
+impl KeyColor {
+    pub const ALL: [KeyColor; 4] = [
+        KeyColor::Red,
+        KeyColor::Blue,
+        KeyColor::Green,
+        KeyColor::Yellow,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyColor::Red => "red",
+            KeyColor::Blue => "blue",
+            KeyColor::Green => "green",
+            KeyColor::Yellow => "yellow",
+        }
+    }
+
+    pub fn color(self, theme: &Theme) -> Color {
+        theme.keys[self.index()]
+    }
+
+    // doors are a darker shade of their key
+    pub fn door_color(self, theme: &Theme) -> Color {
+        let [r, g, b, a] = self.color(theme).as_rgba_f32();
+        Color::rgba(r * 0.6, g * 0.6, b * 0.6, a)
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|&c| c == self).unwrap()
+    }
+
+    pub fn code(self) -> u8 {
+        self.index() as u8
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+}
+
+pub struct Key {
+    pub entity: Entity,
+    pub position: Position,
+    pub color: KeyColor,
+}
+
+// doors are walls, so they block like walls until opened; they only get
+// their own look
+pub fn new_door(
+    cmd: &mut Commands,
+    spawner: &Spawner,
+    position: Position,
+    color: KeyColor,
+) -> Entity {
+    cmd.spawn(MaterialMesh2dBundle {
+        mesh: spawner.mesh.clone(),
+        material: spawner.door_materials[color.index()].clone(),
+        transform: spawner.transform(position),
+        ..default()
+    })
+    .id()
+}
+
+pub fn new_key(cmd: &mut Commands, spawner: &Spawner, position: Position, color: KeyColor) -> Key {
+    let entity = cmd
+        .spawn(MaterialMesh2dBundle {
+            mesh: spawner.diamond_mesh.clone(),
+            material: spawner.key_materials[color.index()].clone(),
+            transform: spawner.transform(position),
+            ..default()
+        })
+        .id();
+
+    Key {
+        entity,
+        position,
+        color,
+    }
+}
+
+// take the key at `index` and start opening its doors
+pub fn collect(cmd: &mut Commands, game: &mut Game, index: usize) {
+    let key = game.keys.swap_remove(index);
+    cmd.entity(key.entity).despawn();
+
+    let (opened, closed): (Vec<_>, Vec<_>) =
+        game.doors.drain(..).partition(|&(_, c)| c == key.color);
+    game.doors = closed;
+
+    for (position, _) in opened {
+        let Some(wall) = game.walls.iter().position(|w| w.position == position) else {
+            continue;
+        };
+
+        let wall = game.walls.swap_remove(wall);
+        cmd.entity(wall.entity)
+            .insert(Opening(Timer::from_seconds(OPEN_SECONDS, TimerMode::Once)));
+    }
+}
+
+#[derive(Component)]
+pub struct Opening(Timer);
+
+// opened doors shrink away before they disappear
+pub fn animate_doors(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut doors: Query<(Entity, &mut Opening, &mut Transform)>,
+) {
+    for (entity, mut opening, mut transform) in doors.iter_mut() {
+        if opening.0.tick(time.delta()).finished() {
+            cmd.entity(entity).despawn();
+            continue;
+        }
+
+        transform.scale = Vec3::splat(1.0 - opening.0.fraction());
+    }
+}
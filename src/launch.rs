@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::{AiLevel, Weights},
+    level::Level,
+    mask::Mask,
+    mode::GameMode,
+    script::Script,
+    settings, GameConfig,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub speed: Option<f32>,
+    pub seed: Option<u64>,
+    pub lives: Option<u32>,
+    pub ai: Option<String>,
+    pub mode: Option<String>,
+    pub level: Option<PathBuf>,
+    pub mask: Option<PathBuf>,
+    pub script: Option<PathBuf>,
+    pub twitch: Option<String>,
+    pub tas: Option<PathBuf>,
+    pub connect: Option<String>,
+    pub fullscreen: bool,
+    pub quit_on_escape: bool,
+}
+
+// the parts of the launch options that have to survive the menu rebuilding
+// the config whenever the difficulty or mode changes
+#[derive(Resource, Default)]
+pub struct Overrides {
+    width: Option<usize>,
+    height: Option<usize>,
+    seed: Option<u64>,
+    lives: Option<u32>,
+    ai_levels: Option<Vec<AiLevel>>,
+    evolved: Option<Weights>,
+    level: Option<Level>,
+    mask: Option<Mask>,
+}
+
+impl Overrides {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_level(&mut self, level: Level) {
+        self.level = Some(level);
+    }
+
+    pub fn apply(&self, config: &mut GameConfig) {
+        if let Some(width) = self.width {
+            config.width = width;
+        }
+
+        if let Some(height) = self.height {
+            config.height = height;
+        }
+
+        if self.seed.is_some() {
+            config.seed = self.seed;
+        }
+
+        if let Some(lives) = self.lives {
+            config.lives = lives;
+        }
+
+        if let Some(ai_levels) = self.ai_levels.clone() {
+            config.ai_levels = ai_levels;
+        }
+
+        if let Some(evolved) = self.evolved {
+            config.evolved = evolved;
+        }
+
+        if let Some(level) = self.level.clone() {
+            level.apply(config);
+        }
+
+        if let Some(mask) = self.mask.clone() {
+            mask.apply(config);
+        }
+    }
+}
+
+impl LaunchOptions {
+    pub(crate) fn overrides(&self) -> Result<Overrides, String> {
+        for size in [self.width, self.height].into_iter().flatten() {
+            if size < crate::MIN_LIVE_SIZE {
+                return Err(format!(
+                    "board size {size} is too small, the minimum is {}",
+                    crate::MIN_LIVE_SIZE
+                ));
+            }
+        }
+
+        if self.lives == Some(0) {
+            return Err("lives must be at least 1".to_owned());
+        }
+
+        let ai_levels = match self.ai.as_deref() {
+            Some(names) => Some(
+                names
+                    .split(',')
+                    .map(|name| {
+                        AiLevel::from_name(name).ok_or_else(|| {
+                            let names: Vec<_> = AiLevel::ALL.iter().map(|l| l.name()).collect();
+                            format!(
+                                "unknown ai level {name:?}, expected one of: {}",
+                                names.join(", ")
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<AiLevel>, _>>()?,
+            ),
+            None => None,
+        };
+
+        let evolved = match ai_levels.as_ref() {
+            Some(levels) if levels.contains(&AiLevel::Evolved) => Some(
+                Weights::exported()
+                    .ok_or("no evolved policy has been exported yet, train one first")?,
+            ),
+            _ => None,
+        };
+
+        let level = match self.level.as_ref() {
+            Some(path) => Some(Level::from_file(path)?),
+            None => None,
+        };
+
+        let mask = match self.mask.as_ref() {
+            Some(path) => Some(Mask::from_file(path)?),
+            None => None,
+        };
+
+        Ok(Overrides {
+            width: self.width,
+            height: self.height,
+            seed: self.seed,
+            lives: self.lives,
+            ai_levels,
+            evolved,
+            level,
+            mask,
+        })
+    }
+
+    pub(crate) fn mode(&self) -> Result<Option<GameMode>, String> {
+        let Some(name) = self.mode.as_deref() else {
+            return Ok(None);
+        };
+
+        GameMode::from_name(name).map(Some).ok_or_else(|| {
+            let names: Vec<_> = GameMode::ALL.iter().map(|m| m.name()).collect();
+            format!(
+                "unknown mode {name:?}, expected one of: {}",
+                names.join(", ")
+            )
+        })
+    }
+
+    pub(crate) fn script(&self) -> Result<Option<Script>, String> {
+        self.script.as_deref().map(Script::load).transpose()
+    }
+
+    pub(crate) fn speed(&self) -> Result<Option<f32>, String> {
+        match self.speed {
+            Some(speed) if !(settings::MIN_SPEED..=settings::MAX_SPEED).contains(&speed) => {
+                Err(format!(
+                    "speed {speed} is out of range, expected {}-{}",
+                    settings::MIN_SPEED,
+                    settings::MAX_SPEED
+                ))
+            }
+            speed => Ok(speed),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use bevy::prelude::*;
+
+use crate::{
+    daily::DAY_MILLIS,
+    difficulty::Difficulty,
+    hud::Toasts,
+    menu,
+    mode::GameMode,
+    replay,
+    scores::{HighScore, HighScores},
+    AppState, GameConfig,
+};
+
+const PAGE_SIZE: usize = 5;
+
+const SELECTED: Color = Color::BLACK;
+const UNSELECTED: Color = Color::GRAY;
+
+// which table is shown and the entry picked in it
+#[derive(Resource)]
+pub struct Leaderboard {
+    mode: GameMode,
+    difficulty: Difficulty,
+    selected: usize,
+}
+
+#[derive(Component)]
+pub struct LeaderboardRoot;
+
+#[derive(Component)]
+pub struct LeaderboardTable;
+
+#[derive(Component)]
+pub struct LeaderboardRow(usize);
+
+#[derive(Component)]
+pub struct LeaderboardPage;
+
+pub fn setup(mut cmd: Commands, config: Res<GameConfig>) {
+    cmd.insert_resource(Leaderboard {
+        mode: config.mode,
+        difficulty: config.difficulty,
+        selected: 0,
+    });
+
+    cmd.spawn((
+        LeaderboardRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "high scores",
+            TextStyle {
+                font_size: 40.0,
+                color: SELECTED,
+                ..default()
+            },
+        ));
+
+        parent.spawn((
+            LeaderboardTable,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.0,
+                    color: SELECTED,
+                    ..default()
+                },
+            ),
+        ));
+
+        for index in 0..PAGE_SIZE {
+            parent.spawn((
+                LeaderboardRow(index),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: UNSELECTED,
+                        ..default()
+                    },
+                ),
+            ));
+        }
+
+        parent.spawn((
+            LeaderboardPage,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: UNSELECTED,
+                    ..default()
+                },
+            ),
+        ));
+
+        parent.spawn(TextBundle::from_section(
+            "left/right to change mode, tab to change difficulty, up/down to choose, page up/down to turn the page, enter to watch the replay, escape to go back",
+            TextStyle {
+                font_size: 16.0,
+                color: UNSELECTED,
+                ..default()
+            },
+        ));
+    });
+}
+
+pub fn cleanup(mut cmd: Commands, roots: Query<Entity, With<LeaderboardRoot>>) {
+    for entity in roots.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    cmd.remove_resource::<Leaderboard>();
+}
+
+pub fn navigate(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    scores: Res<HighScores>,
+    mut leaderboard: ResMut<Leaderboard>,
+    mut toast: Toasts,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mode = menu::step(
+        &GameMode::ALL,
+        leaderboard.mode,
+        &input,
+        KeyCode::ArrowLeft,
+        KeyCode::ArrowRight,
+    );
+
+    let mut difficulty = leaderboard.difficulty;
+    if input.just_pressed(KeyCode::Tab) {
+        let index = Difficulty::ALL
+            .iter()
+            .position(|&d| d == difficulty)
+            .unwrap_or(0);
+        difficulty = Difficulty::ALL[(index + 1) % Difficulty::ALL.len()];
+    }
+
+    if mode != leaderboard.mode || difficulty != leaderboard.difficulty {
+        leaderboard.mode = mode;
+        leaderboard.difficulty = difficulty;
+        leaderboard.selected = 0;
+    }
+
+    let table = scores.table(leaderboard.mode, leaderboard.difficulty);
+    let last = table.len().saturating_sub(1);
+
+    if input.just_pressed(KeyCode::ArrowUp) {
+        leaderboard.selected = leaderboard.selected.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        leaderboard.selected = (leaderboard.selected + 1).min(last);
+    }
+    if input.just_pressed(KeyCode::PageUp) {
+        leaderboard.selected = leaderboard.selected.saturating_sub(PAGE_SIZE);
+    }
+    if input.just_pressed(KeyCode::PageDown) {
+        leaderboard.selected = (leaderboard.selected + PAGE_SIZE).min(last);
+    }
+
+    if input.any_just_pressed([KeyCode::Enter, KeyCode::Space]) {
+        match table.get(leaderboard.selected) {
+            Some(HighScore {
+                replay: Some(run), ..
+            }) => replay::watch(&mut cmd, run.clone(), &mut next_state),
+            Some(_) => toast.show("no replay was kept for that score"),
+            None => {}
+        }
+    }
+
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+pub fn refresh(
+    scores: Res<HighScores>,
+    leaderboard: Res<Leaderboard>,
+    mut tables: Query<&mut Text, With<LeaderboardTable>>,
+    mut rows: Query<(&LeaderboardRow, &mut Text), Without<LeaderboardTable>>,
+    mut pages: Query<
+        &mut Text,
+        (
+            With<LeaderboardPage>,
+            Without<LeaderboardTable>,
+            Without<LeaderboardRow>,
+        ),
+    >,
+) {
+    let table = scores.table(leaderboard.mode, leaderboard.difficulty);
+    let page = leaderboard.selected / PAGE_SIZE;
+    let page_count = table.len().div_ceil(PAGE_SIZE).max(1);
+
+    let value = format!(
+        "< {} >\n{}",
+        leaderboard.mode.name(),
+        leaderboard.difficulty.name()
+    );
+
+    for mut text in tables.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+
+    for (row, mut text) in rows.iter_mut() {
+        let rank = page * PAGE_SIZE + row.0;
+
+        let value = match table.get(rank) {
+            Some(entry) => describe(rank, entry),
+            None if rank == 0 => "no scores yet".to_owned(),
+            None => String::new(),
+        };
+
+        let color = if rank == leaderboard.selected {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+
+    let value = format!("page {} of {page_count}", page + 1);
+
+    for mut text in pages.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// scores from before names, times and dates were kept leave them blank
+fn describe(rank: usize, entry: &HighScore) -> String {
+    let name = match entry.name.as_str() {
+        "" => "---",
+        name => name,
+    };
+
+    let seconds = entry.seconds as u32;
+    let time = match seconds {
+        0 => "-".to_owned(),
+        _ => format!("{}:{:02}", seconds / 60, seconds % 60),
+    };
+
+    let date = match entry.date {
+        0 => "-".to_owned(),
+        millis => date(millis),
+    };
+
+    let replay = if entry.replay.is_some() {
+        "  (replay)"
+    } else {
+        ""
+    };
+
+    format!(
+        "{}. {name}  {}  length {}  {time}  {date}{replay}",
+        rank + 1,
+        entry.score,
+        entry.length
+    )
+}
+
+// the calendar day of a time, from the days since the epoch
+fn date(millis: u64) -> String {
+    let days = millis / DAY_MILLIS + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!("{year}-{month:02}-{day:02}")
+}
@@ -0,0 +1,462 @@
+use std::collections::HashSet;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enemy::Enemy, keys::KeyColor, share, storage, terrain::Terrain, AppState, GameConfig, Offset,
+    Position,
+};
+
+const STORAGE_KEY: &str = "level";
+
+// older codes are still accepted: version 1 predates enemies, version 2
+// predates terrain, version 3 predates keys and doors and version 4 predates
+// objectives
+const CODE_VERSION: u8 = 5;
+const MAX_CODE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) walls: Vec<Position>,
+    pub(crate) spawn: Position,
+    pub(crate) facing: Offset,
+    pub(crate) food: Option<Position>,
+    #[serde(default)]
+    pub(crate) enemies: Vec<Enemy>,
+    #[serde(default)]
+    pub(crate) terrain: Vec<(Position, Terrain)>,
+    #[serde(default)]
+    pub(crate) keys: Vec<(Position, KeyColor)>,
+    #[serde(default)]
+    pub(crate) doors: Vec<(Position, KeyColor)>,
+    #[serde(default)]
+    pub(crate) objectives: Objectives,
+}
+
+// what a round on the level has to achieve to be won, see `objectives`. a
+// level without any is played until the snake dies, like any other round
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Objectives {
+    // the player's snake has to grow to this many cells
+    pub length: Option<usize>,
+    // pieces of food and pellets to eat
+    pub food: Option<u32>,
+    // seconds to meet the others in, or to survive for when there are none
+    pub time_limit: Option<f32>,
+    // touching a wall loses the round, even when it isn't deadly
+    pub no_walls: bool,
+}
+
+impl Objectives {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.time_limit.is_some_and(|t| !t.is_finite() || t <= 0.0) {
+            return Err("has a time limit that isn't a positive number of seconds".to_owned());
+        }
+
+        if self.length == Some(0) || self.food == Some(0) {
+            return Err("has a target of zero, which can never be reached".to_owned());
+        }
+
+        Ok(())
+    }
+}
+
+impl Level {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            walls: vec![],
+            spawn: Position::new(5 + crate::START_LENGTH - 1, 5),
+            facing: Offset::new(1, 0),
+            food: None,
+            enemies: vec![],
+            terrain: vec![],
+            keys: vec![],
+            doors: vec![],
+            objectives: default(),
+        }
+    }
+
+    pub fn load() -> Option<Self> {
+        storage::load(STORAGE_KEY)
+    }
+
+    pub fn save(&self) {
+        storage::save(STORAGE_KEY, self);
+    }
+
+    pub fn from_file(path: &std::path::Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+
+        let level: Self =
+            ron::from_str(&text).map_err(|e| format!("failed to parse {}: {e}", path.display()))?;
+
+        level
+            .validate()
+            .map_err(|e| format!("{} {e}", path.display()))?;
+
+        Ok(level)
+    }
+
+    // what a hand-written or crafted level can get wrong that the game can't
+    // play: the same checks hold for files, codes and replays
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width == 0
+            || self.height == 0
+            || self.width > MAX_CODE_SIZE
+            || self.height > MAX_CODE_SIZE
+        {
+            return Err(format!(
+                "is {}x{}, the maximum is {MAX_CODE_SIZE}x{MAX_CODE_SIZE}",
+                self.width, self.height
+            ));
+        }
+
+        let outside = self
+            .walls
+            .iter()
+            .copied()
+            .chain([self.spawn])
+            .chain(self.food)
+            .chain(self.terrain.iter().map(|&(p, _)| p))
+            .chain(self.keys.iter().chain(self.doors.iter()).map(|&(p, _)| p))
+            .find(|p| p.x >= self.width || p.y >= self.height);
+
+        if let Some(position) = outside {
+            return Err(format!(
+                "has a cell at {},{} outside the board",
+                position.x, position.y
+            ));
+        }
+
+        self.objectives.validate()
+    }
+
+    pub(crate) fn apply(self, config: &mut GameConfig) {
+        config.width = self.width;
+        config.height = self.height;
+        config.level = Some(self);
+    }
+
+    // a version byte, the header as varints, then the walls as alternating
+    // run lengths of open and walled cells in row-major order, then the
+    // enemies, the terrain, the keys, the doors and the objectives
+    pub fn encode(&self) -> String {
+        let mut bytes = vec![CODE_VERSION];
+
+        for value in [self.width, self.height, self.spawn.x, self.spawn.y] {
+            write_varint(&mut bytes, value);
+        }
+
+        bytes.push(self.facing.x as i8 as u8);
+        bytes.push(self.facing.y as i8 as u8);
+
+        match self.food {
+            Some(food) => {
+                bytes.push(1);
+                write_varint(&mut bytes, food.x);
+                write_varint(&mut bytes, food.y);
+            }
+            None => bytes.push(0),
+        }
+
+        let walls: HashSet<_> = self.walls.iter().copied().collect();
+        let mut run = 0;
+        let mut walled = false;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if walls.contains(&Position::new(x, y)) != walled {
+                    write_varint(&mut bytes, run);
+                    run = 0;
+                    walled = !walled;
+                }
+
+                run += 1;
+            }
+        }
+
+        write_varint(&mut bytes, run);
+
+        write_varint(&mut bytes, self.enemies.len());
+
+        for enemy in self.enemies.iter() {
+            match enemy {
+                Enemy::Patrol { path, interval } => {
+                    bytes.push(0);
+                    write_varint(&mut bytes, *interval as usize);
+                    write_varint(&mut bytes, path.len());
+
+                    for position in path.iter() {
+                        write_varint(&mut bytes, position.x);
+                        write_varint(&mut bytes, position.y);
+                    }
+                }
+                Enemy::Bounce {
+                    start,
+                    facing,
+                    interval,
+                } => {
+                    bytes.push(1);
+                    write_varint(&mut bytes, *interval as usize);
+                    write_varint(&mut bytes, start.x);
+                    write_varint(&mut bytes, start.y);
+                    bytes.push(facing.x as i8 as u8);
+                    bytes.push(facing.y as i8 as u8);
+                }
+            }
+        }
+
+        write_varint(&mut bytes, self.terrain.len());
+
+        for &(position, terrain) in self.terrain.iter() {
+            write_varint(&mut bytes, position.x);
+            write_varint(&mut bytes, position.y);
+            terrain.encode(&mut bytes);
+        }
+
+        for cells in [&self.keys, &self.doors] {
+            write_varint(&mut bytes, cells.len());
+
+            for &(position, color) in cells.iter() {
+                write_varint(&mut bytes, position.x);
+                write_varint(&mut bytes, position.y);
+                bytes.push(color.code());
+            }
+        }
+
+        // a target of zero stands for none, and the time limit is in tenths
+        // of a second
+        let objectives = &self.objectives;
+        write_varint(&mut bytes, objectives.length.unwrap_or(0));
+        write_varint(&mut bytes, objectives.food.unwrap_or(0) as usize);
+        write_varint(
+            &mut bytes,
+            objectives
+                .time_limit
+                .map_or(0, |t| (t * 10.0).round().max(1.0) as usize),
+        );
+        bytes.push(objectives.no_walls as u8);
+
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    pub fn decode(code: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(code).ok()?;
+        let mut bytes = bytes.iter().copied();
+
+        let version = bytes.next()?;
+
+        if version == 0 || version > CODE_VERSION {
+            return None;
+        }
+
+        let width = read_varint(&mut bytes)?;
+        let height = read_varint(&mut bytes)?;
+
+        if width == 0 || height == 0 || width > MAX_CODE_SIZE || height > MAX_CODE_SIZE {
+            return None;
+        }
+
+        let spawn = Position::new(read_varint(&mut bytes)?, read_varint(&mut bytes)?);
+        let facing = Offset::new(bytes.next()? as i8 as isize, bytes.next()? as i8 as isize);
+
+        let food = match bytes.next()? {
+            0 => None,
+            _ => Some(Position::new(
+                read_varint(&mut bytes)?,
+                read_varint(&mut bytes)?,
+            )),
+        };
+
+        let mut walls = vec![];
+        let mut cell = 0;
+        let mut walled = false;
+
+        while cell < width * height {
+            let run = read_varint(&mut bytes)?;
+
+            if run > width * height - cell {
+                return None;
+            }
+
+            if walled {
+                walls.extend((cell..cell + run).map(|c| Position::new(c % width, c / width)));
+            }
+
+            cell += run;
+            walled = !walled;
+        }
+
+        let mut enemies = vec![];
+
+        if version >= 2 {
+            let in_bounds = |p: &Position| p.x < width && p.y < height;
+
+            for _ in 0..read_varint(&mut bytes)? {
+                let kind = bytes.next()?;
+                let interval = u32::try_from(read_varint(&mut bytes)?).ok()?;
+
+                let enemy = match kind {
+                    0 => {
+                        let mut path = vec![];
+
+                        for _ in 0..read_varint(&mut bytes)? {
+                            let position =
+                                Position::new(read_varint(&mut bytes)?, read_varint(&mut bytes)?);
+                            path.push(position);
+                        }
+
+                        Enemy::Patrol { path, interval }
+                    }
+                    1 => Enemy::Bounce {
+                        start: Position::new(read_varint(&mut bytes)?, read_varint(&mut bytes)?),
+                        facing: Offset::new(
+                            bytes.next()? as i8 as isize,
+                            bytes.next()? as i8 as isize,
+                        ),
+                        interval,
+                    },
+                    _ => return None,
+                };
+
+                let valid = match &enemy {
+                    Enemy::Patrol { path, .. } => path.iter().all(in_bounds),
+                    Enemy::Bounce { start, .. } => in_bounds(start),
+                };
+
+                if !valid {
+                    return None;
+                }
+
+                enemies.push(enemy);
+            }
+        }
+
+        let mut terrain = vec![];
+
+        if version >= 3 {
+            for _ in 0..read_varint(&mut bytes)? {
+                let position = Position::new(read_varint(&mut bytes)?, read_varint(&mut bytes)?);
+                let kind = Terrain::decode(&mut bytes)?;
+
+                if position.x >= width || position.y >= height {
+                    return None;
+                }
+
+                terrain.push((position, kind));
+            }
+        }
+
+        let mut colored = [vec![], vec![]];
+
+        if version >= 4 {
+            for cells in colored.iter_mut() {
+                for _ in 0..read_varint(&mut bytes)? {
+                    let position =
+                        Position::new(read_varint(&mut bytes)?, read_varint(&mut bytes)?);
+                    let color = KeyColor::from_code(bytes.next()?)?;
+
+                    if position.x >= width || position.y >= height {
+                        return None;
+                    }
+
+                    cells.push((position, color));
+                }
+            }
+        }
+
+        let [keys, doors] = colored;
+
+        let mut objectives = Objectives::default();
+
+        if version >= 5 {
+            let target = |value: usize| (value > 0).then_some(value);
+
+            objectives.length = target(read_varint(&mut bytes)?);
+            objectives.food = target(read_varint(&mut bytes)?)
+                .map(u32::try_from)
+                .transpose()
+                .ok()?;
+            objectives.time_limit = target(read_varint(&mut bytes)?).map(|t| t as f32 / 10.0);
+            objectives.no_walls = match bytes.next()? {
+                0 => false,
+                1 => true,
+                _ => return None,
+            };
+        }
+
+        let level = Self {
+            width,
+            height,
+            walls,
+            spawn,
+            facing,
+            food,
+            enemies,
+            terrain,
+            keys,
+            doors,
+            objectives,
+        };
+
+        level.validate().ok().map(|()| level)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Option<usize> {
+    let mut value = 0;
+
+    for shift in (0..usize::BITS).step_by(7) {
+        let byte = bytes.next()?;
+        value |= ((byte & 0x7f) as usize) << shift;
+
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+pub fn export(level: &Level) -> bool {
+    share::export("level", level.encode())
+}
+
+pub fn import() -> Option<Level> {
+    Level::decode(&share::import("level")?)
+}
+
+pub fn open_link(mut config: ResMut<GameConfig>, mut next_state: ResMut<NextState<AppState>>) {
+    let Some(code) = share::link_param("level") else {
+        return;
+    };
+
+    match Level::decode(&code) {
+        Some(level) => {
+            level.apply(&mut config);
+            next_state.set(AppState::Editor);
+        }
+        None => warn!("failed to read level from link"),
+    }
+}
@@ -1,41 +1,651 @@
-use std::{collections::VecDeque, mem::swap};
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+mod adaptive;
+mod ai;
+mod bindings;
+mod camera;
+mod cheats;
+mod clip;
+mod confusion;
+#[cfg(feature = "debug-tools")]
+mod console;
+mod daily;
+#[cfg(feature = "debug-tools")]
+mod debug;
+mod difficulty;
+mod editor;
+mod embedded;
+mod endless;
+mod enemy;
+mod engine;
+pub mod env;
+mod fog;
+mod heatmap;
+mod history;
+mod hud;
+mod keys;
+mod launch;
+mod leaderboard;
+mod level;
+mod lobby;
+mod locale;
+mod mask;
+mod menu;
+mod minimap;
+mod mode;
+mod mouse;
+mod net;
+mod objectives;
+mod online;
+mod pause;
+#[cfg(target_arch = "wasm32")]
+mod peer;
+mod plan;
+mod popup;
+mod power;
+mod powerup;
+mod practice;
+#[cfg(all(feature = "discord", not(target_arch = "wasm32")))]
+mod presence;
+#[cfg(not(target_arch = "wasm32"))]
+mod reload;
+mod replay;
+mod rewind;
+pub mod rules;
+mod rumble;
+mod savegame;
+mod scores;
+mod screenshot;
+mod script;
+mod segments;
+mod settings;
+mod share;
+mod skins;
+mod storage;
+pub mod submission;
+mod tas;
+#[cfg(feature = "telemetry")]
+mod telemetry;
+mod terrain;
+#[doc(hidden)]
+pub mod testing;
+mod theme;
+#[cfg(target_arch = "wasm32")]
+mod tilt;
+mod topology;
+#[cfg(target_arch = "wasm32")]
+mod touch;
+mod trail;
+#[cfg(not(target_arch = "wasm32"))]
+mod training;
+mod tutorial;
+#[cfg(not(target_arch = "wasm32"))]
+mod twitch;
+mod verify;
+
+#[cfg(target_arch = "wasm32")]
+mod visibility;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    mem::swap,
+    time::Duration,
+};
 
 use bevy::{
     app::AppExit,
+    ecs::system::RunSystemOnce,
     prelude::*,
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
-    window::WindowResolution,
+    window::{WindowFocused, WindowMode, WindowResolution},
 };
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use topology::BoardTopology;
 use wasm_bindgen::prelude::*;
 
+pub use launch::LaunchOptions;
+pub use replay::Replay;
+pub use verify::{verify_replay, FinalState, VerifyError};
+
 const GRID_SCALE: f32 = 10.0;
 const WIDTH: usize = 50;
 const HEIGHT: usize = 40;
 
+const PLAYER: usize = 0;
+const START_LENGTH: usize = 5;
+const FOOD_POINTS: u32 = 10;
+// extra points per cell between the head and freshly placed food, and how
+// much of that bonus is lost each tick until it is eaten
+const FOOD_DISTANCE_POINTS: f32 = 2.0;
+const FOOD_DECAY: f32 = 1.0;
+const MIN_LIVE_SIZE: usize = 8;
+// past this many cells a board is too big for anything that keeps a value per
+// cell, like the minimap and heatmap, or flood fills it whole
+const MAX_GRID_CELLS: usize = 1 << 16;
+// cells between the board edge and the default starting rows
+const START_MARGIN: usize = 5;
+
+const INPUT_QUEUE_CAP: usize = 2;
+
+const START_COUNTDOWN: f32 = 3.0;
+const RESUME_COUNTDOWN: f32 = 1.0;
+// ticks after a respawn during which the player can't die
+const RESPAWN_GRACE_TICKS: u32 = 45;
+// the near-miss assist only kicks in when ticks are shorter than this, and
+// then stretches the next few ticks by the slowdown factor
+const NEAR_MISS_INTERVAL: f32 = 1.0 / 40.0;
+const NEAR_MISS_TICKS: u32 = 3;
+const NEAR_MISS_SLOWDOWN: f32 = 0.35;
+
 #[wasm_bindgen]
 pub fn start() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                resolution: WindowResolution::new(
-                    GRID_SCALE * WIDTH as f32 - 1.0,
-                    GRID_SCALE * HEIGHT as f32 - 1.0,
-                ),
-                resizable: false,
-                title: "snake".to_owned(),
-                ..default()
-            }),
+    if let Err(e) = launch(default()) {
+        error!("{e}");
+    }
+}
+
+pub fn launch(options: LaunchOptions) -> Result<(), String> {
+    launch_with(options, default())
+}
+
+// like `launch`, with a plugin carrying custom rules
+pub fn launch_with(options: LaunchOptions, plugin: SnakePlugin) -> Result<(), String> {
+    let overrides = options.overrides()?;
+
+    let mut settings = settings::Settings::load();
+    if let Some(speed) = options.speed()? {
+        settings.speed = speed;
+    }
+
+    let mode = options.mode()?.unwrap_or(settings.last_mode);
+    let mut config = GameConfig::sized(default(), mode, settings.board_size.cells());
+    overrides.apply(&mut config);
+    check_start_room(&config)?;
+
+    let mode = if options.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+
+    let script = options.script()?;
+    let tas = options
+        .tas
+        .as_deref()
+        .map(|path| tas::Tas::load(path, &config))
+        .transpose()?;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let chat = match options.twitch.as_deref() {
+        Some(channel) => Some(twitch::Chat::connect(channel)?),
+        None => None,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    if options.twitch.is_some() {
+        return Err("twitch chat isn't available in the web build".to_owned());
+    }
+
+    // a ?room= link opens the lobby with its code filled in
+    let room = share::link_param("room");
+    let online = options.connect.is_some() || room.is_some();
+    let lobby = lobby::Lobby::new(options.connect.clone(), room);
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        visibility::listen();
+        tilt::listen();
+
+        if settings.tilt {
+            tilt::request_permission();
+        }
+    }
+
+    let mut app = App::new();
+
+    embedded::register(&mut app);
+
+    app.add_plugins(engine::EnginePlugins {
+        window: Window {
+            resolution: WindowResolution::new(
+                GRID_SCALE * WIDTH as f32 - 1.0,
+                GRID_SCALE * HEIGHT as f32 - 1.0,
+            ),
+            mode,
+            title: "snake".to_owned(),
             ..default()
-        }))
-        .insert_resource(ClearColor(Color::WHITE))
-        .add_systems(PreStartup, setup)
-        .add_systems(Startup, setup_game)
-        .add_systems(Update, (input, update).chain())
-        .run();
+        },
+    })
+    .insert_resource(ClearColor(Color::WHITE))
+    .insert_resource(scores::HighScores::load())
+    .insert_resource(daily::DailyRecord::load())
+    .insert_resource(settings)
+    .insert_resource(savegame::SaveSlot::load())
+    .insert_resource(skins::SkinRecord::load())
+    .insert_resource(config)
+    .insert_resource(overrides)
+    .insert_resource(lobby)
+    .add_plugins(plugin)
+    .init_resource::<camera::CameraMode>()
+    .init_resource::<camera::CameraZoom>()
+    .init_resource::<heatmap::Heatmap>()
+    .add_systems(PreStartup, (hud::setup, minimap::setup))
+    .add_systems(Startup, (replay::open_link, level::open_link))
+    .add_systems(
+        Update,
+        (
+            power::apply,
+            trail::fade,
+            popup::spawn.after(GameSet::Tick),
+            popup::float,
+            plan::draw.after(GameSet::Tick),
+            heatmap::update.after(GameSet::Tick),
+        ),
+    )
+    .add_systems(
+        Update,
+        (
+            (
+                // c picks the confusion in the menu, and the keys spell out
+                // initials while a high score is named
+                camera::toggle_mode
+                    .run_if(in_state(AppState::Playing).or_else(in_state(AppState::Watching)))
+                    .run_if(not(resource_exists::<scores::NameEntry>)),
+                camera::zoom.run_if(not(resource_exists::<scores::NameEntry>)),
+                camera::pan,
+                camera::follow,
+            )
+                .chain(),
+            minimap::update,
+            fog::update,
+            hud::update_countdown,
+            hud::update_pause,
+            hud::update_game_over,
+            hud::update_name_entry,
+            hud::update_score,
+            hud::update_lives,
+            hud::update_magnet,
+            powerup::show_shield,
+            powerup::animate_bursts,
+            keys::animate_doors,
+            hud::update_invulnerable,
+            hud::update_slow_motion,
+            hud::update_food_value,
+            (hud::update_time_limit, hud::update_objectives),
+            hud::update_toast,
+            hud::update_inputs,
+            screenshot::capture,
+        )
+            .chain(),
+    );
+
+    app.add_systems(
+        Update,
+        (
+            mouse::steer.before(GameSet::Tick),
+            rumble::feedback.after(GameSet::Tick),
+        )
+            .run_if(in_state(AppState::Playing)),
+    )
+    .add_systems(
+        Update,
+        (
+            segments::shape.after(GameSet::Tick),
+            theme::animate_food.after(GameSet::Tick),
+            cheats::rainbow.after(GameSet::Tick),
+        )
+            .run_if(resource_exists::<Game>),
+    );
+
+    if let Some(script) = script {
+        app.insert_resource(script);
+    }
+
+    if options.quit_on_escape {
+        app.insert_resource(QuitOnEscape);
+    }
+
+    if online {
+        app.add_systems(Startup, lobby::open);
+    } else if tas.is_none() {
+        app.add_systems(
+            Startup,
+            tutorial::open
+                .after(replay::open_link)
+                .after(level::open_link),
+        );
+    }
+
+    if let Some(tas) = tas {
+        app.insert_resource(tas)
+            .add_systems(Startup, tas::start)
+            .add_systems(
+                Update,
+                (
+                    tas::control.before(GameSet::Tick),
+                    tas::pause_after_step.after(GameSet::Tick),
+                )
+                    .run_if(in_state(AppState::Watching)),
+            );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(chat) = chat {
+        app.insert_resource(chat);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    match reload::Watcher::new(options.level.as_deref()) {
+        Ok(watcher) => {
+            app.insert_resource(watcher)
+                .add_systems(Update, reload::apply);
+        }
+        Err(e) => warn!("{e}"),
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    app.add_systems(PreStartup, touch::setup).add_systems(
+        Update,
+        (
+            touch::show,
+            (touch::press, tilt::steer)
+                .before(GameSet::Tick)
+                .run_if(in_state(AppState::Playing)),
+        ),
+    );
+
+    #[cfg(feature = "audio")]
+    app.add_plugins(bevy::audio::AudioPlugin::default());
+
+    #[cfg(feature = "gizmos")]
+    app.add_plugins(bevy::gizmos::GizmoPlugin);
+
+    #[cfg(feature = "telemetry")]
+    app.add_systems(
+        Update,
+        telemetry::send
+            .after(GameSet::Tick)
+            .run_if(in_state(AppState::Playing)),
+    );
+
+    #[cfg(all(feature = "discord", not(target_arch = "wasm32")))]
+    if let Some(presence) = presence::Presence::connect() {
+        app.insert_resource(presence)
+            .add_systems(Last, presence::update);
+    }
+
+    app.run();
+
+    Ok(())
+}
+
+// the game's states, rules and menus without the window, camera and hud, so
+// it can also run headless
+#[derive(Default)]
+pub struct SnakePlugin {
+    rules: rules::Rules,
+}
+
+impl SnakePlugin {
+    pub fn with_rule(mut self, rule: impl rules::GameRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+}
+
+impl Plugin for SnakePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.rules.clone())
+            .init_state::<AppState>()
+            .init_state::<PauseState>()
+            .add_event::<SnakeDied>()
+            .add_event::<FoodEaten>()
+            .add_event::<RoundOver>()
+            // the window plugin adds this too, but headless apps have none
+            .add_event::<WindowFocused>()
+            .init_resource::<scores::HighScores>()
+            .init_resource::<daily::DailyRecord>()
+            .init_resource::<settings::Settings>()
+            .init_resource::<savegame::SaveSlot>()
+            .init_resource::<skins::SkinRecord>()
+            .init_resource::<GameConfig>()
+            .add_event::<hud::ShowToast>()
+            .init_resource::<hud::ToastQueue>()
+            .init_resource::<clip::Clip>()
+            .init_resource::<lobby::Lobby>()
+            .init_resource::<cheats::Cheats>()
+            .add_systems(PreStartup, setup)
+            .add_systems(Update, locale::translate)
+            .add_systems(
+                Update,
+                (configure_spawner, theme::apply, skins::apply).run_if(
+                    resource_changed::<GameConfig>
+                        .or_else(resource_changed::<settings::Settings>)
+                        .or_else(resource_changed::<skins::SkinRecord>),
+                ),
+            )
+            .add_systems(OnEnter(AppState::Menu), menu::setup)
+            .add_systems(OnExit(AppState::Menu), menu::cleanup)
+            .add_systems(
+                Update,
+                (menu::navigate, menu::refresh).run_if(in_state(AppState::Menu)),
+            )
+            .add_systems(OnEnter(AppState::Settings), settings::setup)
+            .add_systems(OnExit(AppState::Settings), settings::cleanup)
+            .add_systems(
+                Update,
+                (settings::navigate, settings::refresh)
+                    .chain()
+                    .run_if(in_state(AppState::Settings)),
+            )
+            .add_systems(OnEnter(AppState::Bindings), bindings::setup)
+            .add_systems(OnExit(AppState::Bindings), bindings::cleanup)
+            .add_systems(
+                Update,
+                (bindings::navigate, bindings::refresh)
+                    .chain()
+                    .run_if(in_state(AppState::Bindings)),
+            )
+            .add_systems(OnEnter(AppState::Skins), skins::setup)
+            .add_systems(OnExit(AppState::Skins), skins::cleanup)
+            .add_systems(
+                Update,
+                (skins::navigate, skins::refresh)
+                    .chain()
+                    .run_if(in_state(AppState::Skins)),
+            )
+            .add_systems(OnEnter(AppState::Leaderboard), leaderboard::setup)
+            .add_systems(OnExit(AppState::Leaderboard), leaderboard::cleanup)
+            .add_systems(
+                Update,
+                (leaderboard::navigate, leaderboard::refresh)
+                    .chain()
+                    .run_if(in_state(AppState::Leaderboard)),
+            )
+            .add_systems(OnEnter(AppState::Editor), editor::setup)
+            .add_systems(OnExit(AppState::Editor), editor::cleanup)
+            .add_systems(
+                Update,
+                (
+                    editor::input,
+                    editor::paint,
+                    editor::redraw,
+                    editor::refresh,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Editor)),
+            )
+            .add_systems(OnEnter(AppState::Playing), setup_game)
+            .add_systems(
+                OnExit(AppState::Playing),
+                (
+                    exit_game,
+                    scores::skip_name.run_if(resource_exists::<scores::NameEntry>),
+                    pause::close,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    auto_pause,
+                    confusion::rotate,
+                    // the keys spell out initials while a high score is named
+                    input.run_if(not(resource_exists::<scores::NameEntry>)),
+                    cheats::listen.run_if(not(resource_exists::<scores::NameEntry>)),
+                    update.in_set(GameSet::Tick),
+                    rewind::record,
+                    adaptive::adjust.in_set(GameSet::Adjust),
+                    script::run.run_if(resource_exists::<script::Script>),
+                    scores::record,
+                    history::record,
+                    scores::enter_name.run_if(resource_exists::<scores::NameEntry>),
+                    skins::unlock,
+                    clip::record,
+                    clip::export.run_if(not(resource_exists::<scores::NameEntry>)),
+                    history::export.run_if(not(resource_exists::<scores::NameEntry>)),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnEnter(PauseState::Paused), pause::setup)
+            .add_systems(OnExit(PauseState::Paused), pause::cleanup)
+            .add_systems(
+                Update,
+                (pause::navigate, pause::refresh)
+                    .chain()
+                    .run_if(in_state(PauseState::Paused)),
+            )
+            .add_systems(OnEnter(PauseState::Settings), settings::setup)
+            .add_systems(OnExit(PauseState::Settings), settings::cleanup)
+            .add_systems(
+                Update,
+                (settings::navigate, settings::refresh)
+                    .chain()
+                    .run_if(in_state(PauseState::Settings)),
+            )
+            .add_systems(OnEnter(PauseState::Bindings), bindings::setup)
+            .add_systems(OnExit(PauseState::Bindings), bindings::cleanup)
+            .add_systems(
+                Update,
+                (bindings::navigate, bindings::refresh)
+                    .chain()
+                    .run_if(in_state(PauseState::Bindings)),
+            )
+            .add_systems(
+                Update,
+                pause::sync
+                    .after(GameSet::Tick)
+                    .after(pause::navigate)
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(OnEnter(AppState::Watching), setup_game)
+            .add_systems(OnExit(AppState::Watching), (exit_game, replay::cleanup))
+            .add_systems(
+                Update,
+                (
+                    replay::watch_input,
+                    update.in_set(GameSet::Tick),
+                    adaptive::adjust.in_set(GameSet::Adjust),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Watching)),
+            )
+            .add_systems(
+                OnEnter(AppState::Tutorial),
+                (tutorial::setup, setup_game).chain(),
+            )
+            .add_systems(OnExit(AppState::Tutorial), (exit_game, tutorial::cleanup))
+            .add_systems(
+                Update,
+                (
+                    tutorial::steer,
+                    update.run_if(tutorial::moving).in_set(GameSet::Tick),
+                    tutorial::advance,
+                    tutorial::refresh,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Tutorial)),
+            )
+            .add_systems(OnEnter(AppState::Lobby), lobby::setup)
+            .add_systems(OnExit(AppState::Lobby), lobby::cleanup)
+            .add_systems(
+                Update,
+                (
+                    online::wait.run_if(resource_exists::<online::Session>),
+                    lobby::navigate,
+                    lobby::refresh,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Lobby)),
+            )
+            .add_systems(
+                Update,
+                (
+                    online::sync.after(input).before(GameSet::Tick),
+                    online::interpolate.after(GameSet::Tick),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_exists::<online::Session>),
+            )
+            .add_systems(
+                OnExit(AppState::Playing),
+                online::leave.run_if(resource_exists::<online::Session>),
+            );
+
+        // training and twitch chat need threads, which the web build lacks
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(OnEnter(AppState::Training), training::setup)
+            .add_systems(OnExit(AppState::Training), training::cleanup)
+            .add_systems(
+                Update,
+                (training::navigate, training::refresh)
+                    .chain()
+                    .run_if(in_state(AppState::Training)),
+            )
+            .add_systems(
+                Update,
+                twitch::vote
+                    .after(GameSet::Tick)
+                    .before(GameSet::Adjust)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_exists::<twitch::Chat>),
+            );
+
+        #[cfg(feature = "debug-tools")]
+        app.init_resource::<console::Console>()
+            .add_systems(Startup, console::setup)
+            .add_systems(
+                PreUpdate,
+                console::input
+                    .after(bevy::input::InputSystem)
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(Update, console::refresh);
+
+        #[cfg(feature = "debug-tools")]
+        app.init_resource::<debug::Stepping>()
+            .init_resource::<debug::Overlay>()
+            .add_plugins(bevy::diagnostic::FrameTimeDiagnosticsPlugin)
+            .add_systems(Startup, debug::setup_overlay)
+            .add_systems(
+                Update,
+                (
+                    debug::input
+                        .before(GameSet::Tick)
+                        .run_if(in_state(AppState::Playing).or_else(in_state(AppState::Watching))),
+                    debug::toggle_overlay,
+                    debug::start_tick.before(GameSet::Tick),
+                    debug::end_tick.after(GameSet::Tick),
+                    debug::update_overlay.after(debug::end_tick),
+                ),
+            );
+    }
 }
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 struct Position {
     x: usize,
     y: usize,
@@ -58,14 +668,21 @@ impl std::ops::Add<Offset> for Position {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 struct Offset {
     x: isize,
     y: isize,
 }
 
 impl Offset {
-    fn new(x: isize, y: isize) -> Self {
+    const DIRECTIONS: [Offset; 4] = [
+        Offset::new(0, -1),
+        Offset::new(1, 0),
+        Offset::new(0, 1),
+        Offset::new(-1, 0),
+    ];
+
+    const fn new(x: isize, y: isize) -> Self {
         Self { x, y }
     }
 }
@@ -81,231 +698,2182 @@ impl std::ops::Neg for Offset {
     }
 }
 
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AppState {
+    #[default]
+    Menu,
+    Settings,
+    Playing,
+    Watching,
+    Editor,
+    Skins,
+    #[cfg(not(target_arch = "wasm32"))]
+    Training,
+    Lobby,
+    Leaderboard,
+    Tutorial,
+    Bindings,
+}
+
+// the tick and the speed tuning after it, for systems outside the chains
+// that run them to order against. both run in more than one state, so the
+// systems themselves are ambiguous to order by
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+enum GameSet {
+    Tick,
+    Adjust,
+}
+
+// escape quits the game from a round instead of pausing it, for whoever
+// relies on the old behaviour
 #[derive(Resource)]
-struct Game {
-    dead: bool,
+struct QuitOnEscape;
 
-    food: Option<SnakeFood>,
-    player: Snake,
-    tick_timer: Timer,
-    input_queue: VecDeque<Offset>,
+// whether a round being played is paused, and if so which of the pause
+// screens is up. kept apart from `AppState` so pausing doesn't tear the round
+// down
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PauseState {
+    #[default]
+    Running,
+    Paused,
+    Settings,
+    Bindings,
 }
 
-struct Snake {
-    nodes: Vec<SnakeNode>,
-    facing: Offset,
+// what ended a player's snake, and the cell it happened on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum DeathCause {
+    Edge,
+    Wall,
+    Enemy,
+    Body,
+    Snake,
+    Timer,
 }
 
-struct SnakeNode {
-    entity: Entity,
+impl DeathCause {
+    fn describe(self) -> &'static str {
+        match self {
+            DeathCause::Edge => "ran off the edge",
+            DeathCause::Wall => "hit a wall",
+            DeathCause::Enemy => "hit an enemy",
+            DeathCause::Body => "ran into itself",
+            DeathCause::Snake => "ran into another snake",
+            DeathCause::Timer => "ran out of time",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Death {
+    cause: DeathCause,
     position: Position,
 }
 
-struct SnakeFood {
-    entity: Entity,
+#[derive(Event)]
+struct SnakeDied(Death);
+
+// a player's snake ate food or a pellet, worth `points` after the multiplier
+#[derive(Event, Debug, Clone, Copy)]
+struct FoodEaten {
     position: Position,
+    points: u32,
 }
 
-fn get_transform(position: Position) -> Transform {
-    Transform::from_xyz(
-        (position.x as f32 - (WIDTH / 2) as f32 + 0.5) * GRID_SCALE,
-        -(position.y as f32 - (HEIGHT / 2) as f32 + 0.5) * GRID_SCALE,
-        0.0,
-    )
+#[derive(Event)]
+struct RoundOver {
+    score: u32,
+    length: usize,
+    // none when the round was won or a replay ran out
+    death: Option<Death>,
 }
 
-fn is_out_of_bounds(position: Position) -> bool {
-    position.x >= WIDTH || position.y >= HEIGHT
+#[derive(Resource, Clone)]
+struct GameConfig {
+    difficulty: difficulty::Difficulty,
+    mode: mode::GameMode,
+    width: usize,
+    height: usize,
+    tick_interval: f32,
+    speed_ramp: f32,
+    min_tick_interval: f32,
+    wall_density: f32,
+    score_multiplier: u32,
+    time_limit: Option<f32>,
+    seed: Option<u64>,
+    shrink_interval: Option<f32>,
+    escalation_interval: Option<f32>,
+    // radius in cells around the player's head that isn't covered by fog
+    vision: Option<usize>,
+    wrap: bool,
+    // snakes grow every tick and never move their tail, leaving a permanent trail
+    trail: bool,
+    // the players' snakes share one set of controls and only the one in
+    // control moves, passing control along with tab
+    swap: bool,
+    // walls are generated around the player as it travels, see `endless`
+    endless: bool,
+    // speed and food placement follow how the player is doing, see `adaptive`
+    adaptive: bool,
+    // the seed stays put between rounds and upcoming food is shown, see
+    // `practice`
+    practice: bool,
+    forgiving: bool,
+    input_buffering: InputBuffering,
+    players: usize,
+    opponents: usize,
+    // per-opponent ai levels, falling back to the difficulty's default
+    ai_levels: Vec<ai::AiLevel>,
+    // what the evolved ai level plays with
+    evolved: ai::Weights,
+    friendly_collision: FriendlyCollision,
+    confusion: confusion::Confusion,
+    topology: topology::Topology,
+    theme: theme::Theme,
+    // draw cells as gapless blocks and zoom in whole pixel steps
+    pixelated: bool,
+    level: Option<level::Level>,
+    mask: Option<mask::Mask>,
+    lives: u32,
 }
 
-fn input(
-    mut cmd: Commands,
-    transforms: Query<&mut Transform>,
-    input: Res<ButtonInput<KeyCode>>,
-    spawner: Res<Spawner>,
-    mut game: ResMut<Game>,
-    mut exit: EventWriter<AppExit>,
-) {
-    if !game.dead {
-        if input.just_pressed(KeyCode::ArrowUp) {
-            game.input_queue.push_back(Offset::new(0, -1));
-        }
-        if input.just_pressed(KeyCode::ArrowDown) {
-            game.input_queue.push_back(Offset::new(0, 1));
-        }
-        if input.just_pressed(KeyCode::ArrowRight) {
-            game.input_queue.push_back(Offset::new(1, 0));
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+enum InputBuffering {
+    #[default]
+    Queue,
+    Latest,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum FriendlyCollision {
+    #[default]
+    Deadly,
+    PassThrough,
+}
+
+impl GameConfig {
+    fn new(difficulty: difficulty::Difficulty, mode: mode::GameMode) -> Self {
+        Self::sized(difficulty, mode, None)
+    }
+
+    // with the difficulty's board size replaced, before modes that need a
+    // size of their own change it again
+    fn sized(
+        difficulty: difficulty::Difficulty,
+        mode: mode::GameMode,
+        size: Option<(usize, usize)>,
+    ) -> Self {
+        let mut config = difficulty.config();
+
+        if let Some((width, height)) = size {
+            config.width = width;
+            config.height = height;
         }
-        if input.just_pressed(KeyCode::ArrowLeft) {
-            game.input_queue.push_back(Offset::new(-1, 0));
+
+        mode.apply(&mut config);
+        config
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::new(default(), default())
+    }
+}
+
+#[derive(Resource)]
+struct Game {
+    dead: bool,
+    finished: bool,
+    ranked: bool,
+    paused: bool,
+    replay: replay::Replay,
+    playback: Option<replay::Replay>,
+    // lowest speed multiplier used during the run, recorded with the score
+    speed: f32,
+    // seconds spent playing, not counting pauses or countdowns
+    played: f32,
+    countdown: Option<Timer>,
+    time_limit: Option<Timer>,
+    shrink_timer: Option<Timer>,
+    escalation_timer: Option<Timer>,
+    margin: usize,
+    score: u32,
+    // pieces of food and pellets the players have eaten this round
+    food_eaten: u32,
+    // lives left including the current one, shared between co-op players
+    lives: u32,
+    invulnerable: u32,
+    // ticks of near-miss slow motion left, and whether the head was already
+    // next to an obstacle on the previous tick
+    slow_motion: u32,
+    near_miss: bool,
+
+    food: Option<SnakeFood>,
+    // live point value of the regular food, see `food_value`
+    food_value: f32,
+    pellets: Vec<SnakeFood>,
+    pickups: Vec<powerup::Pickup>,
+    // ticks left on the food magnet
+    magnet: u32,
+    walls: Vec<SnakeWall>,
+    enemies: Vec<enemy::Hazard>,
+    terrain: HashMap<Position, terrain::Terrain>,
+    keys: Vec<keys::Key>,
+    // doors live in `walls` too, this only records their colors
+    doors: Vec<(Position, keys::KeyColor)>,
+    tiles: Vec<Entity>,
+    snakes: Vec<Snake>,
+    tick_timer: Timer,
+    rng: ChaCha8Rng,
+    // power-up drops roll on their own stream so they don't shift food and
+    // wall placement in replays recorded before power-ups existed
+    drop_rng: ChaCha8Rng,
+    rewind_rng: ChaCha8Rng,
+    controls: confusion::Controls,
+    rules: rules::Rules,
+    // the player whose snake is in control in swap mode
+    active: usize,
+    // chunks of an endless board that have been generated
+    chunks: HashSet<(usize, usize)>,
+    adaptive: adaptive::Tracker,
+    preview: Option<practice::Preview>,
+    // snapshots of the last few ticks in practice, newest last
+    history: VecDeque<(u32, savegame::SavedGame)>,
+    // where a rewind power-up will send the player back to
+    checkpoint: Option<rewind::Checkpoint>,
+    // the last few directions pressed, with the tick they were pressed on,
+    // for the input display
+    inputs: VecDeque<(u32, Offset)>,
+    // what the players ate on the last tick, sent on by `update`
+    eaten: Vec<FoodEaten>,
+    // how the player's snake last died, kept for the game over screen
+    death: Option<Death>,
+    // where a player's head first touched a wall, for levels that forbid it
+    touched_wall: Option<Position>,
+}
+
+impl Game {
+    fn is_over(&self) -> bool {
+        self.dead || self.finished
+    }
+
+    fn player(&self) -> &Snake {
+        &self.snakes[self.active]
+    }
+
+    // hand control to the next player's snake, dropping turns queued for
+    // the one giving it up
+    fn swap(&mut self, config: &GameConfig) {
+        self.snakes[self.active].input_queue.clear();
+        self.active = (self.active + 1) % config.players;
+    }
+
+    // a player's snake waiting for its turn in swap mode
+    fn is_idle(&self, config: &GameConfig, index: usize) -> bool {
+        config.swap
+            && self.snakes[index].controller.is_player()
+            && self.snakes[index].controller != Controller::Player(self.active)
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+
+        if !self.paused {
+            self.countdown = Some(Timer::from_seconds(RESUME_COUNTDOWN, TimerMode::Once));
         }
     }
 
-    if input.just_released(KeyCode::KeyR) {
-        cleanup_game(&mut cmd, &*game);
-        setup_game(cmd, transforms, spawner);
+    // everything that decides how the round plays on from here, in the
+    // savegame format. entities aren't part of it, `restore` respawns them
+    fn snapshot(&self) -> savegame::SavedGame {
+        savegame::SavedGame::new(self)
     }
 
-    if input.pressed(KeyCode::Escape) {
-        exit.send(AppExit);
+    // swap the round for a snapshot of it, despawning what's on the board and
+    // spawning the snapshot's entities in its place. the rules, controls and
+    // playback aren't in snapshots so they're kept, and like a continued save
+    // it comes back with the resume countdown running
+    fn restore(
+        &mut self,
+        cmd: &mut Commands,
+        spawner: &Spawner,
+        config: &GameConfig,
+        snapshot: savegame::SavedGame,
+    ) {
+        cleanup_game(cmd, self);
+
+        let mut restored = snapshot.restore(cmd, spawner, config);
+        restored.rules = self.rules.clone();
+        restored.controls = std::mem::take(&mut self.controls);
+        restored.playback = self.playback.take();
+
+        *self = restored;
     }
-}
 
-fn update(
-    mut cmd: Commands,
-    mut transforms: Query<&mut Transform>,
-    spawner: Res<Spawner>,
-    mut game: ResMut<Game>,
-    time: Res<Time>,
-) {
-    if !game.dead && game.tick_timer.tick(time.delta()).just_finished() {
-        while let Some(next) = game.input_queue.pop_front() {
-            if next != game.player.facing && next != -game.player.facing {
-                game.player.facing = next;
-                break;
-            }
+    fn push_input(&mut self, config: &GameConfig, player: usize, offset: Offset) {
+        let counting_down = self.countdown.is_some();
+
+        if self.inputs.len() == hud::INPUT_LOG {
+            self.inputs.pop_front();
         }
+        self.inputs.push_back((self.replay.ticks, offset));
 
-        let head_position = game.player.nodes.last().unwrap().position;
-        let next_position = head_position + game.player.facing;
+        let Some(snake) = self
+            .snakes
+            .iter_mut()
+            .find(|s| s.controller == Controller::Player(player))
+        else {
+            return;
+        };
 
-        if Some(next_position) == game.food.as_ref().map(|f| f.position) {
-            let node = spawner.new_node(&mut cmd, next_position);
+        let facing = snake.facing;
+        let queue = &mut snake.input_queue;
+        let topology = config.topology.get();
 
-            game.player.nodes.push(node);
+        match config.input_buffering {
+            InputBuffering::Queue => {
+                let full = queue.len() >= INPUT_QUEUE_CAP;
 
-            new_food(&mut cmd, &mut transforms, &spawner, &mut *game);
-        } else {
-            let mut position = next_position;
+                let previous = if full {
+                    queue.len().checked_sub(2).and_then(|i| queue.get(i))
+                } else {
+                    queue.back()
+                };
 
-            for node in game.player.nodes.iter_mut().rev() {
-                swap(&mut position, &mut node.position);
-                *transforms.get_mut(node.entity).unwrap() = get_transform(node.position);
+                let previous = previous.copied().unwrap_or(facing);
+
+                let Some(offset) = topology.steer(offset, previous) else {
+                    return;
+                };
+
+                if offset == previous || offset == -previous {
+                    return;
+                }
+
+                if full {
+                    queue.pop_back();
+                }
+
+                queue.push_back(offset);
+            }
+            InputBuffering::Latest => {
+                if counting_down {
+                    return;
+                }
+
+                let Some(offset) = topology.steer(offset, facing) else {
+                    return;
+                };
+
+                queue.clear();
+                queue.push_back(offset);
             }
         }
+    }
 
-        let overlapping = game
-            .player
-            .nodes
+    fn is_occupied(&self, position: Position) -> bool {
+        self.snakes
             .iter()
-            .filter(|n| n.position == next_position)
-            .count();
+            .flat_map(|s| s.nodes.iter())
+            .any(|n| n.position == position)
+            || self.food.as_ref().is_some_and(|f| f.position == position)
+            || self.pellets.iter().any(|p| p.position == position)
+            || self.pickups.iter().any(|p| p.position == position)
+            || self.keys.iter().any(|k| k.position == position)
+            || self.walls.iter().any(|w| w.position == position)
+            || self.enemies.iter().any(|e| e.route.position == position)
+    }
 
-        if overlapping > 1 || is_out_of_bounds(next_position) {
-            game.dead = true;
+    fn random_free_cell(&mut self, config: &GameConfig) -> Position {
+        if config.endless {
+            return endless::free_cell(config, self);
+        }
+
+        loop {
+            let x = self.rng.gen_range(self.margin..config.width - self.margin);
+            let y = self.rng.gen_range(self.margin..config.height - self.margin);
+            let position = Position::new(x, y);
+
+            if !is_out_of_bounds(config, position) && !self.is_occupied(position) {
+                return position;
+            }
         }
     }
+
+    fn is_live(&self, config: &GameConfig, position: Position) -> bool {
+        position.x >= self.margin
+            && position.y >= self.margin
+            && position.x < config.width - self.margin
+            && position.y < config.height - self.margin
+    }
 }
 
-#[derive(Resource)]
-struct Spawner {
-    mesh: Mesh2dHandle,
-    material: Handle<ColorMaterial>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Controller {
+    Player(usize),
+    Ai,
 }
 
-impl Spawner {
-    fn setup(meshes: &mut Assets<Mesh>, materials: &mut Assets<ColorMaterial>) -> Self {
-        let mesh = Mesh2dHandle(meshes.add(Rectangle::new(GRID_SCALE - 1.0, GRID_SCALE - 1.0)));
+impl Controller {
+    fn is_player(self) -> bool {
+        matches!(self, Controller::Player(_))
+    }
+}
 
-        let color = Color::rgb(0.0, 0.0, 0.0);
-        let material = materials.add(color);
+struct Snake {
+    nodes: Vec<SnakeNode>,
+    facing: Offset,
+    input_queue: VecDeque<Offset>,
+    controller: Controller,
+    alive: bool,
+    shielded: bool,
+    // where the tail was before the last move, or none if the snake grew,
+    // so a shield can put the snake back
+    last_tail: Option<Position>,
+    // the cells an ai snake was headed along when it last steered
+    plan: Vec<Position>,
+}
 
-        Self { mesh, material }
+impl Snake {
+    fn new(controller: Controller) -> Self {
+        Self {
+            nodes: vec![],
+            facing: Offset::new(1, 0),
+            input_queue: VecDeque::new(),
+            controller,
+            alive: true,
+            shielded: false,
+            last_tail: None,
+            plan: vec![],
+        }
     }
 
-    pub fn new_node(&self, cmd: &mut Commands, position: Position) -> SnakeNode {
-        let entity = cmd
-            .spawn(MaterialMesh2dBundle {
-                mesh: self.mesh.clone(),
-                material: self.material.clone(),
-                transform: get_transform(position),
-                ..Default::default()
-            })
-            .id();
-
-        SnakeNode { entity, position }
+    fn head(&self) -> Position {
+        self.nodes.last().unwrap().position
     }
 
-    pub fn new_food(&self, cmd: &mut Commands, position: Position) -> SnakeFood {
-        let entity = cmd
-            .spawn(MaterialMesh2dBundle {
-                mesh: self.mesh.clone(),
-                material: self.material.clone(),
-                transform: get_transform(position),
-                ..Default::default()
-            })
-            .id();
+    fn next_facing(&mut self) -> Offset {
+        while let Some(next) = self.input_queue.pop_front() {
+            if next != self.facing && next != -self.facing {
+                return next;
+            }
+        }
 
-        SnakeFood { entity, position }
+        self.facing
     }
 }
 
-fn setup(
-    mut cmd: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    cmd.spawn(Camera2dBundle::default());
+struct SnakeNode {
+    entity: Entity,
+    position: Position,
+}
 
-    let spawner = Spawner::setup(&mut *meshes, &mut *materials);
-    cmd.insert_resource(spawner);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FoodKind {
+    Regular,
+    Pellet,
 }
 
-fn cleanup_game(cmd: &mut Commands, game: &Game) {
-    for node in game.player.nodes.iter() {
-        cmd.entity(node.entity).despawn();
-    }
+struct SnakeFood {
+    entity: Entity,
+    position: Position,
+}
 
-    if let Some(food) = game.food.as_ref() {
-        cmd.entity(food.entity).despawn();
+struct SnakeWall {
+    entity: Entity,
+    position: Position,
+}
+
+fn get_transform(topology: topology::Topology, position: Position) -> Transform {
+    Transform::from_translation(topology.get().to_world(position).extend(0.0))
+}
+
+fn is_out_of_bounds(config: &GameConfig, position: Position) -> bool {
+    position.x >= config.width
+        || position.y >= config.height
+        || config.mask.as_ref().is_some_and(|m| !m.contains(position))
+}
+
+fn advance(config: &GameConfig, position: Position, facing: Offset) -> Position {
+    let (x, y) = config.topology.get().neighbor(position, facing);
+
+    if config.wrap {
+        Position::new(
+            x.rem_euclid(config.width as isize) as usize,
+            y.rem_euclid(config.height as isize) as usize,
+        )
+    } else {
+        Position::new(x as usize, y as usize)
     }
 }
 
-fn setup_game(mut cmd: Commands, mut transforms: Query<&mut Transform>, spawner: Res<Spawner>) {
-    let mut game = Game {
-        dead: false,
-        food: None,
-        player: Snake {
-            nodes: vec![],
-            facing: Offset::new(1, 0),
-        },
-        tick_timer: Timer::from_seconds(1.0 / 30.0, TimerMode::Repeating),
-        input_queue: VecDeque::new(),
-    };
+fn start_positions(config: &GameConfig) -> Vec<Position> {
+    let count = config.players + config.opponents;
 
-    for i in 0..5 {
-        game.player
-            .nodes
-            .push(spawner.new_node(&mut cmd, Position::new(5 + i, 5)));
+    if let Some(mask) = config.mask.as_ref() {
+        return mask.starts(count);
     }
+    let spacing = match count {
+        1 => 0,
+        _ => config.height.saturating_sub(START_MARGIN * 2) / (count - 1),
+    };
 
-    new_food(&mut cmd, &mut transforms, &*spawner, &mut game);
+    // endless boards start in the middle, far from the seam where they wrap
+    let origin = if config.endless {
+        Position::new(config.width / 2, config.height / 2)
+    } else {
+        Position::new(START_MARGIN, START_MARGIN)
+    };
 
-    cmd.insert_resource(game);
+    (0..count)
+        .map(|i| Position::new(origin.x, origin.y + i * spacing))
+        .collect()
 }
 
-fn new_food(
-    cmd: &mut Commands,
-    transforms: &mut Query<&mut Transform>,
-    spawner: &Spawner,
-    game: &mut Game,
-) {
-    let mut rng = rand::thread_rng();
+// the smallest board the default start layout fits every snake on, with a
+// free cell ahead of each head, or `None` when no snake starts from it
+fn min_start_size(config: &GameConfig) -> Option<(usize, usize)> {
+    let count = config.players + config.opponents;
+    let levelled = usize::from(config.level.is_some());
 
-    let position = loop {
-        let x = rng.gen_range(0..WIDTH);
-        let y = rng.gen_range(0..HEIGHT);
-        let position = Position::new(x, y);
+    if config.mask.is_some() || config.endless || count <= levelled {
+        return None;
+    }
 
-        if !game.player.nodes.iter().any(|n| n.position == position) {
-            break position;
-        }
+    let width = START_MARGIN + START_LENGTH + 1;
+    let height = match count {
+        1 => START_MARGIN + 1,
+        _ => START_MARGIN * 2 + count - 1,
     };
 
-    if let Some(food) = game.food.as_mut() {
-        *transforms.get_mut(food.entity).unwrap() = get_transform(position);
-        food.position = position;
-    } else {
-        game.food = Some(spawner.new_food(cmd, position));
+    Some((width.max(MIN_LIVE_SIZE), height.max(MIN_LIVE_SIZE)))
+}
+
+// why the snakes would not all fit on the board at the start of a round
+fn check_start_room(config: &GameConfig) -> Result<(), String> {
+    match min_start_size(config) {
+        Some((width, height)) if config.width < width || config.height < height => Err(format!(
+            "a {}x{} board has no room for {} snakes to start, the minimum is {width}x{height}",
+            config.width,
+            config.height,
+            config.players + config.opponents
+        )),
+        _ => Ok(()),
+    }
+}
+
+// grows an overridden board a mode picked later in the menu has too many
+// snakes for, which launch could not check up front
+fn fit_start_room(config: &mut GameConfig) {
+    if let Some((width, height)) = min_start_size(config) {
+        config.width = config.width.max(width);
+        config.height = config.height.max(height);
+    }
+}
+
+// the facing and cells, tail first, a snake starts a round with
+fn start_nodes(config: &GameConfig, index: usize) -> (Offset, Vec<Position>) {
+    match config.level.as_ref() {
+        Some(level) if index == PLAYER => {
+            (level.facing, spawn_nodes(config, level.spawn, level.facing))
+        }
+        _ => {
+            let start = start_positions(config)[index];
+            let positions = (0..START_LENGTH)
+                .map(|i| Position::new(start.x + i, start.y))
+                .collect();
+
+            (Offset::new(1, 0), positions)
+        }
+    }
+}
+
+// the cells of a freshly spawned snake with its head on `head`, tail first
+fn spawn_nodes(config: &GameConfig, head: Position, facing: Offset) -> Vec<Position> {
+    let mut nodes = vec![head];
+
+    while nodes.len() < START_LENGTH {
+        let position = advance(config, *nodes.last().unwrap(), -facing);
+
+        if is_out_of_bounds(config, position) {
+            break;
+        }
+
+        nodes.push(position);
+    }
+
+    nodes.reverse();
+    nodes
+}
+
+fn is_spawn_area(starts: &[Position], position: Position) -> bool {
+    starts
+        .iter()
+        .any(|s| position.y.abs_diff(s.y) <= 2 && position.x < s.x + START_LENGTH * 3)
+}
+
+fn input(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    spawner: Res<Spawner>,
+    config: Res<GameConfig>,
+    settings: Res<settings::Settings>,
+    mut slot: ResMut<savegame::SaveSlot>,
+    mut toast: hud::Toasts,
+    mut game: ResMut<Game>,
+    session: Option<Res<online::Session>>,
+    quit_on_escape: Option<Res<QuitOnEscape>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    // a match goes on for everyone in it, so nobody can pause, restart, save
+    // or swap snakes on their own
+    let online = session.is_some();
+
+    if input.just_pressed(KeyCode::KeyP) && !game.is_over() && !online {
+        game.toggle_pause();
+    }
+
+    // the pause menu takes over the keys until the game is resumed
+    if !game.paused && !game.is_over() {
+        if config.swap && !online && input.just_pressed(KeyCode::Tab) {
+            let ticks = game.replay.ticks;
+            game.replay.swaps.push(ticks);
+            game.swap(&config);
+        }
+
+        let sets = settings.bindings.controls();
+
+        // in swap mode every set of keys steers the snake in control, and
+        // online the first set steers this client's snake, unless it's only
+        // watching
+        let count = match session.as_ref() {
+            Some(session) => usize::from(session.player.is_some()),
+            None if config.swap => sets.len(),
+            None => config.players,
+        };
+
+        for (player, controls) in sets.iter().enumerate().take(count) {
+            let player = match session.as_ref() {
+                Some(session) => session.player.unwrap_or(PLAYER),
+                None if config.swap => game.active,
+                None => player,
+            };
+
+            for &(key, offset) in controls {
+                if input.just_pressed(key) {
+                    let offset = game.controls.transform(config.confusion, offset);
+                    game.push_input(&config, player, offset);
+                }
+            }
+        }
+    }
+
+    if config.practice && input.just_pressed(KeyCode::KeyZ) {
+        rewind::undo(&mut cmd, &spawner, &config, &mut game);
+    }
+
+    if input.just_released(KeyCode::KeyR) && !online {
+        cleanup_game(&mut cmd, &game);
+        cmd.add(|world: &mut World| world.run_system_once(setup_game));
+    }
+
+    if game.is_over() && input.just_pressed(KeyCode::KeyX) && replay::export(&game.replay) {
+        toast.show("replay exported");
+    }
+
+    if game.is_over() && game.ranked && input.just_pressed(KeyCode::KeyU) {
+        match submission::export(&game.replay, game.score) {
+            Ok(()) => toast.show("signed score exported"),
+            Err(e) => toast.show(e),
+        }
+    }
+
+    if input.just_released(KeyCode::KeyM) {
+        next_state.set(AppState::Menu);
+    }
+
+    if input.just_pressed(KeyCode::KeyQ) && !game.is_over() && !online {
+        slot.store(&game);
+        next_state.set(AppState::Menu);
+    }
+
+    // a stray escape pauses rather than throwing the round away
+    if input.just_pressed(KeyCode::Escape) {
+        if quit_on_escape.is_some() {
+            exit.send(AppExit);
+        } else if game.is_over() {
+            next_state.set(AppState::Menu);
+        } else if !game.paused && !online {
+            game.toggle_pause();
+        }
+    }
+}
+
+// hiding the tab pauses the game and showing it again resumes, but only a
+// pause this made, never one the player asked for
+#[cfg(target_arch = "wasm32")]
+fn auto_pause(mut game: ResMut<Game>, mut paused_here: Local<bool>) {
+    if visibility::take_hidden() && !game.is_over() && !game.paused {
+        game.paused = true;
+        *paused_here = true;
+    }
+
+    if !*paused_here || visibility::is_hidden() {
+        return;
+    }
+
+    *paused_here = false;
+
+    if game.paused {
+        game.paused = false;
+        game.countdown = Some(Timer::from_seconds(RESUME_COUNTDOWN, TimerMode::Once));
+    }
+}
+
+// losing focus pauses the game, and it stays paused until p is pressed so
+// switching back to the window doesn't drop the player straight into play
+#[cfg(not(target_arch = "wasm32"))]
+fn auto_pause(mut game: ResMut<Game>, mut focus: EventReader<WindowFocused>) {
+    let lost = focus.read().fold(false, |_, e| !e.focused);
+
+    if lost && !game.is_over() && !game.paused {
+        game.paused = true;
+    }
+}
+
+fn update(
+    mut cmd: Commands,
+    mut transforms: Query<&mut Transform>,
+    spawner: Res<Spawner>,
+    config: Res<GameConfig>,
+    settings: Res<settings::Settings>,
+    mut game: ResMut<Game>,
+    time: Res<Time>,
+    #[cfg(feature = "debug-tools")] stepping: Res<debug::Stepping>,
+    mut died: EventWriter<SnakeDied>,
+    mut eaten: EventWriter<FoodEaten>,
+    mut round_over: EventWriter<RoundOver>,
+    session: Option<Res<online::Session>>,
+) {
+    if game.is_over() || game.paused {
+        return;
+    }
+
+    if let Some(countdown) = game.countdown.as_mut() {
+        if !countdown.tick(time.delta()).finished() {
+            return;
+        }
+
+        game.countdown = None;
+        game.tick_timer.reset();
+    }
+
+    // everything holds still between steps, and a step runs the tick at once
+    #[cfg(feature = "debug-tools")]
+    if stepping.enabled {
+        if !stepping.step {
+            return;
+        }
+
+        let duration = game.tick_timer.duration();
+        game.tick_timer.set_elapsed(duration);
+    }
+
+    game.played += time.delta_seconds();
+
+    // online every client has to shrink, escalate and run out of time on the
+    // same tick, so in a session the timers go by ticks instead of the clock
+    let lockstep = session.is_some();
+
+    if !lockstep
+        && run_timers(
+            &mut cmd,
+            &mut transforms,
+            &spawner,
+            &config,
+            &mut game,
+            &mut round_over,
+            time.delta(),
+        )
+    {
+        return;
+    }
+
+    game.speed = game.speed.min(settings.speed);
+
+    let slowdown = if game.slow_motion > 0 {
+        NEAR_MISS_SLOWDOWN
+    } else {
+        1.0
+    };
+
+    let in_mud = game.player().alive
+        && game.terrain.get(&game.player().head()) == Some(&terrain::Terrain::Mud);
+    let slowdown = if in_mud { slowdown / 2.0 } else { slowdown };
+
+    if game
+        .tick_timer
+        .tick(time.delta().mul_f32(settings.speed * slowdown))
+        .just_finished()
+    {
+        let ticks = game.replay.ticks;
+
+        let (ended, shrinks, escalations, swaps) = match game.playback.as_ref() {
+            Some(playback) => (
+                ticks >= playback.ticks,
+                playback.shrinks.iter().filter(|&&t| t == ticks).count(),
+                playback.escalations.iter().filter(|&&t| t == ticks).count(),
+                playback.swaps.iter().filter(|&&t| t == ticks).count(),
+            ),
+            None => (false, 0, 0, 0),
+        };
+
+        if ended {
+            game.finished = true;
+            game.death = None;
+            return;
+        }
+
+        let elapsed = game.tick_timer.duration();
+
+        if lockstep
+            && run_timers(
+                &mut cmd,
+                &mut transforms,
+                &spawner,
+                &config,
+                &mut game,
+                &mut round_over,
+                elapsed,
+            )
+        {
+            return;
+        }
+
+        for _ in 0..shrinks {
+            shrink_board(&mut cmd, &mut transforms, &spawner, &config, &mut game);
+        }
+
+        for _ in 0..escalations {
+            escalate(&mut cmd, &spawner, &config, &mut game);
+        }
+
+        for _ in 0..swaps {
+            game.swap(&config);
+        }
+
+        tick(&mut cmd, &mut transforms, &spawner, &config, &mut game);
+
+        eaten.send_batch(game.eaten.iter().copied());
+
+        game.slow_motion = game.slow_motion.saturating_sub(1);
+
+        let interval = game.tick_timer.duration().as_secs_f32() / settings.speed;
+        let near_miss = is_near_miss(&config, &game);
+
+        if settings.near_miss_slowdown
+            && interval < NEAR_MISS_INTERVAL
+            && near_miss
+            && !game.near_miss
+        {
+            game.slow_motion = NEAR_MISS_TICKS;
+        }
+
+        game.near_miss = near_miss;
+
+        let dead_players: Vec<_> = (0..game.snakes.len())
+            .filter(|&i| game.snakes[i].controller.is_player() && !game.snakes[i].alive)
+            .collect();
+
+        if !dead_players.is_empty() && rewind::to_checkpoint(&mut cmd, &spawner, &config, &mut game)
+        {
+            return;
+        }
+
+        if let Some(death) = game.death.filter(|_| !dead_players.is_empty()) {
+            died.send(SnakeDied(death));
+        }
+
+        let player_died = !dead_players.is_empty() && game.lives <= 1;
+
+        if !dead_players.is_empty() && !player_died {
+            game.lives -= 1;
+            game.invulnerable = RESPAWN_GRACE_TICKS;
+
+            for index in dead_players {
+                respawn(&mut cmd, &spawner, &config, &mut game, index);
+            }
+        }
+
+        let opponents_left = game
+            .snakes
+            .iter()
+            .any(|s| s.controller == Controller::Ai && s.alive);
+
+        if player_died {
+            game.dead = true;
+
+            round_over.send(RoundOver {
+                score: game.score,
+                length: game.player().nodes.len(),
+                death: game.death,
+            });
+        } else if config.opponents > 0 && !opponents_left {
+            game.finished = true;
+            game.death = None;
+
+            round_over.send(RoundOver {
+                score: game.score,
+                length: game.player().nodes.len(),
+                death: None,
+            });
+        }
+
+        if !game.is_over() && objectives::check(&config, &mut game) {
+            round_over.send(RoundOver {
+                score: game.score,
+                length: game.player().nodes.len(),
+                death: game.death,
+            });
+        }
+
+        if !game.is_over() {
+            rewind::update_checkpoint(&mut game);
+        }
+    }
+}
+
+// the time limit, board shrinking and escalation, returning whether the time
+// ran out
+fn run_timers(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+    round_over: &mut EventWriter<RoundOver>,
+    elapsed: Duration,
+) -> bool {
+    if let Some(time_limit) = game.time_limit.as_mut() {
+        if time_limit.tick(elapsed).finished() {
+            game.finished = true;
+            game.death = (!objectives::is_survival(config)).then(|| Death {
+                cause: DeathCause::Timer,
+                position: game.player().head(),
+            });
+
+            round_over.send(RoundOver {
+                score: game.score,
+                length: game.player().nodes.len(),
+                death: game.death,
+            });
+
+            return true;
+        }
+    }
+
+    if let Some(shrink_timer) = game.shrink_timer.as_mut() {
+        if shrink_timer.tick(elapsed).just_finished() {
+            let ticks = game.replay.ticks;
+            game.replay.shrinks.push(ticks);
+            shrink_board(cmd, transforms, spawner, config, game);
+        }
+    }
+
+    if let Some(escalation_timer) = game.escalation_timer.as_mut() {
+        if escalation_timer.tick(elapsed).just_finished() {
+            let ticks = game.replay.ticks;
+            game.replay.escalations.push(ticks);
+            escalate(cmd, spawner, config, game);
+        }
+    }
+
+    false
+}
+
+fn tick(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+) {
+    game.invulnerable = game.invulnerable.saturating_sub(1);
+    game.eaten.clear();
+
+    steer_snakes(config, game);
+
+    enemy::step(transforms, spawner, config, game);
+
+    powerup::pull_food(transforms, spawner, config, game);
+
+    let moved = move_snakes(cmd, transforms, spawner, config, game);
+
+    collide(cmd, transforms, spawner, config, game, moved.clone());
+
+    let carried = carry_snakes(cmd, transforms, spawner, config, game, &moved);
+
+    collide(cmd, transforms, spawner, config, game, carried);
+
+    if config.endless {
+        endless::generate(cmd, spawner, config, game);
+    }
+
+    // light-cycles score for every tick they stay alive
+    if config.trail
+        && game
+            .snakes
+            .iter()
+            .any(|s| s.controller.is_player() && s.alive)
+    {
+        game.score += config.score_multiplier;
+    }
+}
+
+fn steer_snakes(config: &GameConfig, game: &mut Game) {
+    let ticks = game.replay.ticks;
+    game.replay.ticks += 1;
+
+    for index in 0..game.snakes.len() {
+        if !game.snakes[index].alive || game.is_idle(config, index) {
+            continue;
+        }
+
+        let current = game.snakes[index].facing;
+        let on_ice = game.terrain.get(&game.snakes[index].head()) == Some(&terrain::Terrain::Ice);
+
+        let facing = match game.snakes[index].controller {
+            // turns made on ice are lost, not saved for later
+            Controller::Player(_) if on_ice && game.playback.is_none() => {
+                game.snakes[index].input_queue.clear();
+                current
+            }
+            _ if on_ice => current,
+            Controller::Player(player) => match game.playback.as_ref() {
+                Some(playback) => playback
+                    .turn(config, ticks, player)
+                    .filter(|&facing| facing != -current)
+                    .unwrap_or(current),
+                None => game.snakes[index].next_facing(),
+            },
+            Controller::Ai => {
+                let (facing, plan) = ai::steer(game, config, index);
+                game.snakes[index].plan = plan;
+                facing
+            }
+        };
+
+        if let Controller::Player(player) = game.snakes[index].controller {
+            if facing != current {
+                game.replay.record_turn(config, ticks, player, facing);
+            }
+        }
+
+        game.snakes[index].facing = facing;
+    }
+}
+
+fn move_snakes(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+) -> Vec<usize> {
+    let mut moved = vec![];
+
+    game.food_value = (game.food_value - FOOD_DECAY).max(FOOD_POINTS as f32);
+
+    for index in 0..game.snakes.len() {
+        if !game.snakes[index].alive || game.is_idle(config, index) {
+            continue;
+        }
+
+        let facing = game.snakes[index].facing;
+
+        if move_snake(cmd, transforms, spawner, config, game, index, facing) {
+            moved.push(index);
+        }
+    }
+
+    moved
+}
+
+// a conveyor a head has just moved onto pushes the snake one more cell its
+// way, without turning it, unless it points straight back into the neck
+fn carry_snakes(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+    moved: &[usize],
+) -> Vec<usize> {
+    let mut carried = vec![];
+
+    for &index in moved {
+        let snake = &game.snakes[index];
+
+        if !snake.alive {
+            continue;
+        }
+
+        let Some(&terrain::Terrain::Conveyor(direction)) = game.terrain.get(&snake.head()) else {
+            continue;
+        };
+
+        if direction != -snake.facing
+            && move_snake(cmd, transforms, spawner, config, game, index, direction)
+        {
+            carried.push(index);
+        }
+    }
+
+    carried
+}
+
+// moves one snake a cell towards `facing`, eating and collecting whatever is
+// there. false if a forgiving board held it back instead
+fn move_snake(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+    index: usize,
+    facing: Offset,
+) -> bool {
+    let controller = game.snakes[index].controller;
+    let next_position = advance(config, game.snakes[index].head(), facing);
+
+    let food = game.food.as_ref().map(|f| f.position) == Some(next_position);
+    let pellet = game
+        .pellets
+        .iter()
+        .position(|p| p.position == next_position);
+    let eating = food || pellet.is_some();
+    let pickup = game
+        .pickups
+        .iter()
+        .position(|p| p.position == next_position)
+        .filter(|_| controller.is_player());
+    let key = game
+        .keys
+        .iter()
+        .position(|k| k.position == next_position)
+        .filter(|_| controller.is_player());
+
+    if config.forgiving && is_blocked(game, index, next_position, eating) {
+        return false;
+    }
+
+    if eating || config.trail {
+        let length = game.snakes[index].nodes.len();
+        let node = spawner.new_node(cmd, next_position, controller, length);
+
+        game.snakes[index].nodes.push(node);
+        game.snakes[index].last_tail = None;
+    } else {
+        let mut position = next_position;
+
+        for node in game.snakes[index].nodes.iter_mut().rev() {
+            swap(&mut position, &mut node.position);
+            *transforms.get_mut(node.entity).unwrap() = spawner.transform(node.position);
+        }
+
+        game.snakes[index].last_tail = Some(position);
+
+        if spawner.motion_trail {
+            trail::spawn(cmd, spawner, position);
+        }
+    }
+
+    if eating {
+        if let Some(pellet) = pellet {
+            let pellet = game.pellets.swap_remove(pellet);
+            cmd.entity(pellet.entity).despawn();
+        }
+
+        if controller.is_player() {
+            game.food_eaten += 1;
+
+            let points = if food {
+                game.food_value.round() as u32
+            } else {
+                FOOD_POINTS
+            };
+            let points = game.rules.score(
+                points,
+                &rules::FoodEaten {
+                    x: next_position.x,
+                    y: next_position.y,
+                    pellet: !food,
+                    player: true,
+                    length: game.snakes[index].nodes.len(),
+                },
+            );
+            game.score += points * config.score_multiplier;
+            game.eaten.push(FoodEaten {
+                position: next_position,
+                points: points * config.score_multiplier,
+            });
+
+            let interval = game.tick_timer.duration().as_secs_f32() * config.speed_ramp;
+            let interval = interval.max(config.min_tick_interval);
+            game.tick_timer
+                .set_duration(Duration::from_secs_f32(interval));
+        }
+
+        if food {
+            new_food(cmd, transforms, spawner, config, game);
+
+            if controller.is_player() {
+                powerup::try_drop(cmd, spawner, config, game);
+            }
+        }
+    }
+
+    if let Some(pickup) = pickup {
+        powerup::collect(cmd, config, game, index, pickup);
+    }
+
+    if let Some(key) = key {
+        keys::collect(cmd, game, key);
+    }
+
+    true
+}
+
+fn collide(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+    moved: Vec<usize>,
+) {
+    let mut occupied = HashMap::<Position, Vec<usize>>::new();
+
+    for (index, snake) in game.snakes.iter().enumerate().filter(|(_, s)| s.alive) {
+        for node in snake.nodes.iter() {
+            occupied.entry(node.position).or_default().push(index);
+        }
+    }
+
+    for index in moved {
+        let head = game.snakes[index].head();
+        let nodes = &game.snakes[index].nodes;
+        let neck = nodes.len().checked_sub(2).map(|i| nodes[i].position);
+        let hit_wall = game.walls.iter().any(|w| w.position == head);
+        let hit_enemy = game.enemies.iter().any(|e| e.hits(head, neck));
+        // the head is one of the snake's own cells, so any more is its body
+        let hit_body = occupied[&head]
+            .iter()
+            .filter(|&&other| other == index)
+            .count()
+            > 1;
+
+        if hit_wall && game.snakes[index].controller.is_player() && game.touched_wall.is_none() {
+            game.touched_wall = Some(head);
+        }
+
+        let collisions = occupied[&head]
+            .iter()
+            .filter(|&&other| {
+                let friendly = game.snakes[index].controller.is_player()
+                    && game.snakes[other].controller.is_player();
+
+                other == index
+                    || !friendly
+                    || config.friendly_collision == FriendlyCollision::Deadly
+            })
+            .count();
+
+        let grace = game.invulnerable > 0 && game.snakes[index].controller.is_player();
+
+        let kind = if is_out_of_bounds(config, head) {
+            rules::CollisionKind::OutOfBounds
+        } else if grace {
+            continue;
+        } else if hit_wall || hit_enemy {
+            rules::CollisionKind::Wall
+        } else if collisions > 1 {
+            rules::CollisionKind::Snake
+        } else {
+            continue;
+        };
+
+        let outcome = game.rules.collision(&rules::Collision {
+            kind,
+            x: head.x,
+            y: head.y,
+            player: game.snakes[index].controller.is_player(),
+        });
+
+        match outcome {
+            rules::Outcome::Survive => undo_move(cmd, transforms, spawner, game, index),
+            rules::Outcome::Default if game.snakes[index].shielded => {
+                powerup::absorb(cmd, transforms, spawner, game, index);
+            }
+            _ => {
+                if game.snakes[index].controller.is_player() {
+                    let cause = match kind {
+                        rules::CollisionKind::OutOfBounds => DeathCause::Edge,
+                        _ if hit_wall => DeathCause::Wall,
+                        _ if hit_enemy => DeathCause::Enemy,
+                        _ if hit_body => DeathCause::Body,
+                        _ => DeathCause::Snake,
+                    };
+
+                    game.death = Some(Death {
+                        cause,
+                        position: death_cell(config, &game.snakes[index]),
+                    });
+                }
+
+                kill(cmd, spawner, config, game, index);
+            }
+        }
+    }
+}
+
+// where a snake died. off the edge of the board that's the last cell it was
+// on, since its head isn't on any
+fn death_cell(config: &GameConfig, snake: &Snake) -> Position {
+    let head = snake.head();
+
+    if !is_out_of_bounds(config, head) {
+        return head;
+    }
+
+    snake
+        .nodes
+        .iter()
+        .rev()
+        .map(|n| n.position)
+        .find(|&p| !is_out_of_bounds(config, p))
+        .unwrap_or(head)
+}
+
+// step the snake at `index` back to where it was before this tick's move
+fn undo_move(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    game: &mut Game,
+    index: usize,
+) {
+    let snake = &mut game.snakes[index];
+
+    match snake.last_tail.take() {
+        Some(tail) => {
+            let mut position = tail;
+
+            for node in snake.nodes.iter_mut() {
+                swap(&mut position, &mut node.position);
+
+                if let Ok(mut transform) = transforms.get_mut(node.entity) {
+                    *transform = spawner.transform(node.position);
+                }
+            }
+        }
+        None => {
+            if let Some(node) = snake.nodes.pop() {
+                cmd.entity(node.entity).despawn();
+            }
+        }
+    }
+}
+
+// put a dead player back at its starting cells at the starting length
+fn respawn(
+    cmd: &mut Commands,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+    index: usize,
+) {
+    game.death = None;
+
+    let snake = &mut game.snakes[index];
+
+    for node in snake.nodes.drain(..) {
+        cmd.entity(node.entity).despawn();
+    }
+
+    let (facing, positions) = start_nodes(config, index);
+
+    snake.facing = facing;
+    snake.input_queue.clear();
+    snake.alive = true;
+    snake.nodes = positions
+        .into_iter()
+        .enumerate()
+        .map(|(i, position)| spawner.new_node(cmd, position, snake.controller, i))
+        .collect();
+}
+
+// whether the player's head sits next to a wall or its own body, not
+// counting the segment right behind it
+fn is_near_miss(config: &GameConfig, game: &Game) -> bool {
+    let snake = game.player();
+
+    if !snake.alive || snake.nodes.len() < 2 {
+        return false;
+    }
+
+    let head = snake.head();
+    let body = &snake.nodes[..snake.nodes.len() - 2];
+
+    config.topology.get().directions().iter().any(|&direction| {
+        let cell = advance(config, head, direction);
+
+        // without wrapping the board edge is as deadly as a wall
+        is_out_of_bounds(config, cell)
+            || body.iter().any(|n| n.position == cell)
+            || game.walls.iter().any(|w| w.position == cell)
+    })
+}
+
+fn kill(cmd: &mut Commands, spawner: &Spawner, config: &GameConfig, game: &mut Game, index: usize) {
+    let snake = &mut game.snakes[index];
+    snake.alive = false;
+
+    if snake.controller.is_player() {
+        return;
+    }
+
+    let nodes: Vec<_> = snake.nodes.drain(..).collect();
+
+    for node in nodes {
+        cmd.entity(node.entity).despawn();
+
+        let position = node.position;
+        let occupied = is_out_of_bounds(config, position)
+            || game.food.as_ref().is_some_and(|f| f.position == position)
+            || game.pellets.iter().any(|p| p.position == position)
+            || game.pickups.iter().any(|p| p.position == position)
+            || game.walls.iter().any(|w| w.position == position);
+
+        if !occupied {
+            game.pellets
+                .push(spawner.new_food(cmd, position, FoodKind::Pellet));
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Spawner {
+    topology: topology::Topology,
+    mesh: Mesh2dHandle,
+    square_mesh: Mesh2dHandle,
+    pixel_mesh: Mesh2dHandle,
+    hex_mesh: Mesh2dHandle,
+    circle_mesh: Mesh2dHandle,
+    diamond_mesh: Mesh2dHandle,
+    triangle_mesh: Mesh2dHandle,
+    arrow_mesh: Mesh2dHandle,
+    // the pieces a snake on a square board is drawn joined up with
+    straight_mesh: Mesh2dHandle,
+    bend_mesh: Mesh2dHandle,
+    tail_mesh: Mesh2dHandle,
+    shape_coded_food: bool,
+    // whether cells a snake moves off leave a fading trail behind
+    motion_trail: bool,
+    material: Handle<ColorMaterial>,
+    skin_materials: Vec<Handle<ColorMaterial>>,
+    partner_material: Handle<ColorMaterial>,
+    opponent_material: Handle<ColorMaterial>,
+    food_material: Handle<ColorMaterial>,
+    wall_material: Handle<ColorMaterial>,
+    ring_material: Handle<ColorMaterial>,
+    enemy_material: Handle<ColorMaterial>,
+    shrink_material: Handle<ColorMaterial>,
+    shield_material: Handle<ColorMaterial>,
+    rewind_material: Handle<ColorMaterial>,
+    ice_material: Handle<ColorMaterial>,
+    mud_material: Handle<ColorMaterial>,
+    conveyor_material: Handle<ColorMaterial>,
+    key_materials: Vec<Handle<ColorMaterial>>,
+    door_materials: Vec<Handle<ColorMaterial>>,
+    magnet_material: Handle<ColorMaterial>,
+    fog_material: Handle<ColorMaterial>,
+    void_material: Handle<ColorMaterial>,
+    preview_material: Handle<ColorMaterial>,
+    trail_materials: Vec<Handle<ColorMaterial>>,
+    plan_material: Handle<ColorMaterial>,
+    heat_materials: Vec<Handle<ColorMaterial>>,
+}
+
+impl Spawner {
+    fn setup(meshes: &mut Assets<Mesh>, materials: &mut Assets<ColorMaterial>) -> Self {
+        let square_mesh = Mesh2dHandle(meshes.add(topology::Square.cell_mesh()));
+        let pixel_mesh = Mesh2dHandle(meshes.add(Rectangle::new(GRID_SCALE, GRID_SCALE)));
+        let hex_mesh = Mesh2dHandle(meshes.add(topology::Hex.cell_mesh()));
+        let circle_mesh = Mesh2dHandle(meshes.add(Circle::new(GRID_SCALE / 2.0 - 0.5)));
+        let diamond_mesh = Mesh2dHandle(meshes.add(RegularPolygon::new(GRID_SCALE / 2.0, 4)));
+        let triangle_mesh = Mesh2dHandle(meshes.add(RegularPolygon::new(GRID_SCALE / 2.0, 3)));
+        let arrow_mesh = Mesh2dHandle(meshes.add(Triangle2d::new(
+            Vec2::new(GRID_SCALE * 0.35, 0.0),
+            Vec2::new(-GRID_SCALE * 0.25, GRID_SCALE * 0.3),
+            Vec2::new(-GRID_SCALE * 0.25, -GRID_SCALE * 0.3),
+        )));
+        let straight_mesh = Mesh2dHandle(meshes.add(segments::straight_mesh()));
+        let bend_mesh = Mesh2dHandle(meshes.add(segments::bend_mesh()));
+        let tail_mesh = Mesh2dHandle(meshes.add(segments::tail_mesh()));
+
+        let theme = theme::Theme::default();
+
+        let material = materials.add(theme.snake);
+        let partner_material = materials.add(theme.partner);
+        let opponent_material = materials.add(theme.opponent);
+        let food_material = materials.add(theme.food);
+        let wall_material = materials.add(theme.wall);
+        let ring_material = materials.add(theme.ring);
+        let enemy_material = materials.add(theme.enemy);
+        let shrink_material = materials.add(theme.shrink);
+        let shield_material = materials.add(theme.shield);
+        let rewind_material = materials.add(theme.rewind);
+        let ice_material = materials.add(theme.ice);
+        let mud_material = materials.add(theme.mud);
+        let conveyor_material = materials.add(theme.conveyor);
+        let key_materials = keys::KeyColor::ALL
+            .iter()
+            .map(|c| materials.add(c.color(&theme)))
+            .collect();
+        let door_materials = keys::KeyColor::ALL
+            .iter()
+            .map(|c| materials.add(c.door_color(&theme)))
+            .collect();
+        let magnet_material = materials.add(theme.magnet);
+        let fog_material = materials.add(theme.background.with_a(fog::FOG_ALPHA));
+        let void_material = materials.add(theme.wall.with_a(mask::VOID_ALPHA));
+        let preview_material = materials.add(theme.food.with_a(practice::PREVIEW_ALPHA));
+        let trail_materials = (0..trail::TRAIL_STEPS)
+            .map(|step| materials.add(theme.snake.with_a(trail::alpha(step))))
+            .collect();
+        let plan_material = materials.add(theme.opponent.with_a(plan::PLAN_ALPHA));
+        let heat_materials = (0..heatmap::HEAT_LEVELS)
+            .map(|level| materials.add(theme.snake.with_a(heatmap::alpha(level))))
+            .collect();
+
+        Self {
+            topology: default(),
+            mesh: square_mesh.clone(),
+            square_mesh,
+            pixel_mesh,
+            hex_mesh,
+            circle_mesh,
+            diamond_mesh,
+            triangle_mesh,
+            arrow_mesh,
+            straight_mesh,
+            bend_mesh,
+            tail_mesh,
+            shape_coded_food: false,
+            motion_trail: false,
+            material,
+            skin_materials: vec![],
+            partner_material,
+            opponent_material,
+            food_material,
+            wall_material,
+            ring_material,
+            enemy_material,
+            shrink_material,
+            shield_material,
+            rewind_material,
+            ice_material,
+            mud_material,
+            conveyor_material,
+            key_materials,
+            door_materials,
+            magnet_material,
+            fog_material,
+            void_material,
+            preview_material,
+            trail_materials,
+            plan_material,
+            heat_materials,
+        }
+    }
+
+    pub fn transform(&self, position: Position) -> Transform {
+        get_transform(self.topology, position)
+    }
+
+    // the material for the segment `index` cells from the tail
+    pub fn node_material(&self, controller: Controller, index: usize) -> &Handle<ColorMaterial> {
+        match controller {
+            Controller::Player(0) if !self.skin_materials.is_empty() => {
+                &self.skin_materials[index % self.skin_materials.len()]
+            }
+            Controller::Player(0) => &self.material,
+            Controller::Player(_) => &self.partner_material,
+            Controller::Ai => &self.opponent_material,
+        }
+    }
+
+    pub fn new_node(
+        &self,
+        cmd: &mut Commands,
+        position: Position,
+        controller: Controller,
+        index: usize,
+    ) -> SnakeNode {
+        let entity = cmd
+            .spawn(MaterialMesh2dBundle {
+                mesh: self.mesh.clone(),
+                material: self.node_material(controller, index).clone(),
+                transform: self.transform(position),
+                ..Default::default()
+            })
+            .id();
+
+        SnakeNode { entity, position }
+    }
+
+    pub fn new_food(&self, cmd: &mut Commands, position: Position, kind: FoodKind) -> SnakeFood {
+        let mesh = match (self.shape_coded_food, kind) {
+            (false, _) => &self.mesh,
+            (true, FoodKind::Regular) => &self.circle_mesh,
+            (true, FoodKind::Pellet) => &self.diamond_mesh,
+        };
+
+        let entity = cmd
+            .spawn(MaterialMesh2dBundle {
+                mesh: mesh.clone(),
+                material: self.food_material.clone(),
+                transform: self.transform(position),
+                ..Default::default()
+            })
+            .id();
+
+        SnakeFood { entity, position }
+    }
+
+    pub fn new_wall(&self, cmd: &mut Commands, position: Position) -> SnakeWall {
+        let entity = cmd
+            .spawn(MaterialMesh2dBundle {
+                mesh: self.mesh.clone(),
+                material: self.wall_material.clone(),
+                transform: self.transform(position),
+                ..Default::default()
+            })
+            .id();
+
+        SnakeWall { entity, position }
+    }
+
+    pub fn new_enemy(&self, cmd: &mut Commands, position: Position) -> Entity {
+        cmd.spawn(MaterialMesh2dBundle {
+            mesh: self.mesh.clone(),
+            material: self.enemy_material.clone(),
+            transform: self.transform(position),
+            ..Default::default()
+        })
+        .id()
+    }
+
+    pub fn new_pickup(
+        &self,
+        cmd: &mut Commands,
+        position: Position,
+        kind: powerup::PowerUp,
+    ) -> Entity {
+        let material = match kind {
+            powerup::PowerUp::Shrink => &self.shrink_material,
+            powerup::PowerUp::Magnet => &self.magnet_material,
+            powerup::PowerUp::Shield => &self.shield_material,
+            powerup::PowerUp::Rewind => &self.rewind_material,
+        };
+
+        // power-ups are the third kind of food
+        let mesh = match self.shape_coded_food {
+            true => &self.triangle_mesh,
+            false => &self.circle_mesh,
+        };
+
+        cmd.spawn(MaterialMesh2dBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            transform: self.transform(position),
+            ..Default::default()
+        })
+        .id()
+    }
+
+    pub fn new_ring(&self, cmd: &mut Commands, position: Position) -> SnakeWall {
+        let entity = cmd
+            .spawn(MaterialMesh2dBundle {
+                mesh: self.mesh.clone(),
+                material: self.ring_material.clone(),
+                transform: self.transform(position),
+                ..Default::default()
+            })
+            .id();
+
+        SnakeWall { entity, position }
+    }
+}
+
+fn configure_spawner(
+    config: Res<GameConfig>,
+    settings: Res<settings::Settings>,
+    mut spawner: ResMut<Spawner>,
+) {
+    spawner.topology = config.topology;
+    spawner.shape_coded_food = settings.shape_coded_food;
+    spawner.motion_trail = settings.motion_trail && !settings.reduced_motion;
+    spawner.mesh = match config.topology {
+        topology::Topology::Square if config.pixelated => spawner.pixel_mesh.clone(),
+        topology::Topology::Square => spawner.square_mesh.clone(),
+        topology::Topology::Hex => spawner.hex_mesh.clone(),
+    };
+}
+
+fn setup(
+    mut cmd: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    cmd.spawn(Camera2dBundle::default());
+
+    let spawner = Spawner::setup(&mut meshes, &mut materials);
+    cmd.insert_resource(spawner);
+}
+
+fn cleanup_game(cmd: &mut Commands, game: &Game) {
+    for node in game.snakes.iter().flat_map(|s| s.nodes.iter()) {
+        cmd.entity(node.entity).despawn();
+    }
+
+    if let Some(food) = game.food.as_ref() {
+        cmd.entity(food.entity).despawn();
+    }
+
+    for pellet in game.pellets.iter() {
+        cmd.entity(pellet.entity).despawn();
+    }
+
+    for pickup in game.pickups.iter() {
+        cmd.entity(pickup.entity).despawn();
+    }
+
+    for key in game.keys.iter() {
+        cmd.entity(key.entity).despawn();
+    }
+
+    for wall in game.walls.iter() {
+        cmd.entity(wall.entity).despawn();
+    }
+
+    for hazard in game.enemies.iter() {
+        cmd.entity(hazard.entity).despawn();
+    }
+
+    for &tile in game.tiles.iter() {
+        cmd.entity(tile).despawn_recursive();
+    }
+
+    if let Some(preview) = game.preview.as_ref() {
+        preview.despawn(cmd);
+    }
+}
+
+fn exit_game(mut cmd: Commands, game: Res<Game>) {
+    cleanup_game(&mut cmd, &game);
+    cmd.remove_resource::<Game>();
+}
+
+fn setup_game(
+    mut cmd: Commands,
+    mut transforms: Query<&mut Transform>,
+    spawner: Res<Spawner>,
+    config: Res<GameConfig>,
+    mut daily: ResMut<daily::DailyRecord>,
+    settings: Res<settings::Settings>,
+    rules: Res<rules::Rules>,
+    playback: Option<Res<replay::Playback>>,
+    resume: Option<ResMut<savegame::Resume>>,
+    heatmap: Option<ResMut<heatmap::Heatmap>>,
+    mut script: Option<ResMut<script::Script>>,
+    #[cfg(not(target_arch = "wasm32"))] mut chat: Option<ResMut<twitch::Chat>>,
+) {
+    if let Some(mut heatmap) = heatmap {
+        heatmap.start_round(&mut cmd);
+    }
+
+    let mut start_round = |game: &mut Game| {
+        if let Some(script) = script.as_mut() {
+            script.start_round(game);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(chat) = chat.as_mut() {
+            chat.start_round(game);
+        }
+    };
+
+    if let Some(saved) = resume.and_then(|mut r| r.0.take()) {
+        cmd.remove_resource::<savegame::Resume>();
+
+        let mut game = saved.restore(&mut cmd, &spawner, &config);
+        game.rules = rules.clone();
+        start_round(&mut game);
+
+        cmd.insert_resource(game);
+        return;
+    }
+
+    let playback = playback.map(|p| p.0.clone());
+
+    // the tables are kept per mode and difficulty, so scores only compare on
+    // the board, lives and seed those pick, not on a board size from the
+    // settings or anything set from the command line
+    let standard = GameConfig::new(config.difficulty, config.mode);
+    let customized = (config.width, config.height, config.lives, config.seed)
+        != (
+            standard.width,
+            standard.height,
+            standard.lives,
+            standard.seed,
+        );
+
+    let ranked = match config.mode {
+        _ if playback.is_some() || config.level.is_some() || customized => false,
+        mode::GameMode::Practice => false,
+        mode::GameMode::Daily => daily.claim(daily::today()),
+        _ => true,
+    };
+
+    // a seed is always picked up front so the round can be replayed
+    let seed = config.seed.unwrap_or_else(rand::random);
+
+    let mut game = Game {
+        dead: false,
+        finished: false,
+        ranked,
+        paused: false,
+        replay: replay::Replay::new(&config, seed),
+        speed: settings.speed,
+        played: 0.0,
+        countdown: Some(Timer::from_seconds(START_COUNTDOWN, TimerMode::Once)),
+        time_limit: objectives::time_limit(&config)
+            .filter(|_| playback.is_none())
+            .map(|t| Timer::from_seconds(t, TimerMode::Once)),
+        shrink_timer: config
+            .shrink_interval
+            .filter(|_| playback.is_none())
+            .map(|t| Timer::from_seconds(t, TimerMode::Repeating)),
+        escalation_timer: config
+            .escalation_interval
+            .filter(|_| playback.is_none())
+            .map(|t| Timer::from_seconds(t, TimerMode::Repeating)),
+        margin: 0,
+        score: 0,
+        food_eaten: 0,
+        lives: config.lives,
+        invulnerable: 0,
+        slow_motion: 0,
+        near_miss: false,
+        food: None,
+        food_value: FOOD_POINTS as f32,
+        pellets: vec![],
+        pickups: vec![],
+        magnet: 0,
+        walls: vec![],
+        enemies: vec![],
+        terrain: HashMap::new(),
+        keys: vec![],
+        doors: vec![],
+        tiles: vec![],
+        snakes: vec![],
+        tick_timer: Timer::from_seconds(config.tick_interval, TimerMode::Repeating),
+        rng: ChaCha8Rng::seed_from_u64(seed),
+        drop_rng: powerup::drop_rng(seed),
+        rewind_rng: powerup::rewind_rng(seed),
+        controls: default(),
+        rules: rules.clone(),
+        active: PLAYER,
+        chunks: HashSet::new(),
+        adaptive: adaptive::Tracker::new(config.lives),
+        preview: config.practice.then(|| practice::Preview::new(seed)),
+        history: VecDeque::new(),
+        checkpoint: None,
+        inputs: VecDeque::new(),
+        eaten: vec![],
+        death: None,
+        touched_wall: None,
+        playback,
+    };
+
+    let starts = start_positions(&config);
+
+    (game.terrain, game.tiles) = terrain::spawn_level(&mut cmd, &spawner, &config);
+
+    if let Some(level) = config.level.as_ref() {
+        for &position in level.walls.iter() {
+            game.walls.push(spawner.new_wall(&mut cmd, position));
+        }
+
+        for &(position, color) in level.doors.iter() {
+            let entity = keys::new_door(&mut cmd, &spawner, position, color);
+            game.walls.push(SnakeWall { entity, position });
+            game.doors.push((position, color));
+        }
+
+        for &(position, color) in level.keys.iter() {
+            game.keys
+                .push(keys::new_key(&mut cmd, &spawner, position, color));
+        }
+
+        for enemy in level.enemies.iter() {
+            if let Some(route) = enemy::Route::new(enemy.clone()) {
+                game.enemies.push(enemy::spawn(&mut cmd, &spawner, route));
+            }
+        }
+    } else if !config.endless {
+        for y in 0..config.height {
+            for x in 0..config.width {
+                let position = Position::new(x, y);
+
+                if !is_spawn_area(&starts, position)
+                    && game.rng.gen_bool(config.wall_density as f64)
+                {
+                    game.walls.push(spawner.new_wall(&mut cmd, position));
+                }
+            }
+        }
+    }
+
+    for index in 0..starts.len() {
+        let controller = if index < config.players {
+            Controller::Player(index)
+        } else {
+            Controller::Ai
+        };
+
+        let mut snake = Snake::new(controller);
+
+        let (facing, positions) = start_nodes(&config, index);
+        snake.facing = facing;
+
+        for (i, position) in positions.into_iter().enumerate() {
+            snake
+                .nodes
+                .push(spawner.new_node(&mut cmd, position, controller, i));
+        }
+
+        game.snakes.push(snake);
+    }
+
+    if config.endless {
+        endless::generate(&mut cmd, &spawner, &config, &mut game);
+    }
+
+    match config.level.as_ref().and_then(|l| l.food) {
+        // trails fill the board, so there is nothing to eat
+        _ if config.trail => {}
+        Some(position) => {
+            game.food = Some(spawner.new_food(&mut cmd, position, FoodKind::Regular));
+            game.food_value = food_value(&config, &game, position);
+        }
+        None => new_food(&mut cmd, &mut transforms, &spawner, &config, &mut game),
+    }
+
+    start_round(&mut game);
+    cmd.insert_resource(game);
+}
+
+fn new_food(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+) {
+    let rules = game.rules.clone();
+    let position = match rules
+        .food_cell(config, game)
+        .or_else(|| practice::food_cell(config, game))
+    {
+        Some(position) => position,
+        None if config.adaptive => adaptive::food_cell(config, game),
+        None => game.random_free_cell(config),
+    };
+
+    if let Some(preview) = game.preview.as_mut() {
+        preview.redraw(cmd, spawner);
+    }
+
+    if let Some(food) = game.food.as_mut() {
+        *transforms.get_mut(food.entity).unwrap() = spawner.transform(position);
+        food.position = position;
+    } else {
+        game.food = Some(spawner.new_food(cmd, position, FoodKind::Regular));
+    }
+
+    game.food_value = food_value(config, game, position);
+}
+
+// food placed farther from the player's head is worth more
+fn food_value(config: &GameConfig, game: &Game, position: Position) -> f32 {
+    let Some(head) = game.player().nodes.last() else {
+        return FOOD_POINTS as f32;
+    };
+
+    let topology = config.topology.get();
+    let distance = topology
+        .to_world(head.position)
+        .distance(topology.to_world(position))
+        / GRID_SCALE;
+
+    FOOD_POINTS as f32 + distance.round() * FOOD_DISTANCE_POINTS
+}
+
+fn is_blocked(game: &Game, index: usize, position: Position, eating: bool) -> bool {
+    let hits_snake = game
+        .snakes
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.alive)
+        .any(|(i, s)| {
+            let tail = if i == index && !eating { 1 } else { 0 };
+            s.nodes[tail..].iter().any(|n| n.position == position)
+        });
+
+    hits_snake || game.walls.iter().any(|w| w.position == position)
+}
+
+// drop a wall on a random free cell, keeping clear of the players' heads
+// and never cutting off any of the space they can currently reach
+fn escalate(cmd: &mut Commands, spawner: &Spawner, config: &GameConfig, game: &mut Game) {
+    const ATTEMPTS: usize = 100;
+    const HEAD_CLEARANCE: usize = 3;
+
+    let heads: Vec<_> = game
+        .snakes
+        .iter()
+        .filter(|s| s.controller.is_player() && s.alive)
+        .map(|s| s.head())
+        .collect();
+
+    let reachable: Vec<_> = heads
+        .iter()
+        .map(|&h| reachable_cells(config, game, h, None))
+        .collect();
+
+    for _ in 0..ATTEMPTS {
+        let x = game.rng.gen_range(game.margin..config.width - game.margin);
+        let y = game.rng.gen_range(game.margin..config.height - game.margin);
+        let position = Position::new(x, y);
+
+        let occupied = game.is_occupied(position);
+
+        let near_head = heads
+            .iter()
+            .any(|h| h.x.abs_diff(x) + h.y.abs_diff(y) < HEAD_CLEARANCE);
+
+        if occupied || near_head {
+            continue;
+        }
+
+        let boxes_in = heads.iter().zip(reachable.iter()).any(|(&head, &before)| {
+            reachable_cells(config, game, head, Some(position)) + 1 < before
+        });
+
+        if !boxes_in {
+            game.walls.push(spawner.new_wall(cmd, position));
+            return;
+        }
+    }
+}
+
+// flood fill over cells free of walls and snakes, starting next to `head`,
+// giving up at `MAX_GRID_CELLS` so a huge board doesn't fill for ever
+fn reachable_cells(
+    config: &GameConfig,
+    game: &Game,
+    head: Position,
+    blocked: Option<Position>,
+) -> usize {
+    let directions = config.topology.get().directions();
+
+    let mut taken: HashSet<Position> = game
+        .walls
+        .iter()
+        .map(|w| w.position)
+        .chain(
+            game.snakes
+                .iter()
+                .flat_map(|s| s.nodes.iter().map(|n| n.position)),
+        )
+        .chain(blocked)
+        .collect();
+
+    let mut count = 0;
+    let mut stack = vec![head];
+
+    while let Some(position) = stack.pop() {
+        for &direction in directions {
+            let next = advance(config, position, direction);
+
+            if !is_out_of_bounds(config, next) && taken.insert(next) {
+                count += 1;
+                stack.push(next);
+
+                if count >= MAX_GRID_CELLS {
+                    return count;
+                }
+            }
+        }
+    }
+
+    count
+}
+
+fn shrink_board(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+) {
+    let live_width = config.width - game.margin * 2;
+    let live_height = config.height - game.margin * 2;
+
+    if live_width <= MIN_LIVE_SIZE || live_height <= MIN_LIVE_SIZE {
+        return;
+    }
+
+    let (min, max_x, max_y) = (
+        game.margin,
+        config.width - game.margin - 1,
+        config.height - game.margin - 1,
+    );
+
+    for y in min..=max_y {
+        for x in min..=max_x {
+            if x != min && x != max_x && y != min && y != max_y {
+                continue;
+            }
+
+            let position = Position::new(x, y);
+
+            if !game.walls.iter().any(|w| w.position == position) {
+                game.walls.push(spawner.new_ring(cmd, position));
+            }
+        }
+    }
+
+    game.margin += 1;
+
+    let food_position = game.food.as_ref().map(|f| f.position);
+    if food_position.is_some_and(|p| !game.is_live(config, p)) {
+        new_food(cmd, transforms, spawner, config, game);
     }
 }
@@ -0,0 +1,341 @@
+use bevy::prelude::*;
+use snake_protocol::{Role, MAX_NAME_LENGTH, ROOM_CODE_LENGTH};
+
+#[cfg(target_arch = "wasm32")]
+use crate::peer::Peers;
+use crate::{
+    hud::{self, Toasts},
+    net::Connection,
+    online::Session,
+    settings::Settings,
+    AppState, GameConfig,
+};
+
+const SELECTED: Color = Color::BLACK;
+const UNSELECTED: Color = Color::GRAY;
+
+// the web build only has a relay server if it was built with one, and
+// otherwise plays between peers
+const DEFAULT_SERVER: Option<&str> = match option_env!("SNAKE_SERVER_URL") {
+    Some(url) => Some(url),
+    None if cfg!(target_arch = "wasm32") => None,
+    None => Some("ws://localhost:7878"),
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Code,
+    Role,
+}
+
+// what's been typed into the lobby, kept between visits
+#[derive(Resource)]
+pub struct Lobby {
+    // the relay server to play on, if there is one
+    server: Option<String>,
+    name: String,
+    code: String,
+    role: Role,
+    field: Field,
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+impl Lobby {
+    pub fn new(server: Option<String>, code: Option<String>) -> Self {
+        Self {
+            server: server.or(DEFAULT_SERVER.map(str::to_owned)),
+            name: String::new(),
+            code: code.unwrap_or_default().to_uppercase(),
+            role: Role::Player,
+            field: Field::Name,
+        }
+    }
+
+    fn connect(&self, room: Option<String>) -> Result<(Connection, Option<String>), String> {
+        match self.server.as_deref() {
+            Some(url) => Ok((Connection::relay(url)?, room)),
+            None => peers(room),
+        }
+    }
+}
+
+// a new room between peers needs a code of its own, which the relay server
+// would otherwise hand out
+#[cfg(target_arch = "wasm32")]
+fn peers(room: Option<String>) -> Result<(Connection, Option<String>), String> {
+    let room = room.unwrap_or_else(Peers::new_code);
+    Ok((Connection::peers(&room), Some(room)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn peers(_room: Option<String>) -> Result<(Connection, Option<String>), String> {
+    Err("there's no server to play on".to_owned())
+}
+
+#[derive(Component)]
+pub struct LobbyRoot;
+
+#[derive(Component)]
+pub struct LobbyField(Field);
+
+#[derive(Component)]
+pub struct LobbyRoom;
+
+#[derive(Component)]
+pub struct LobbyHint;
+
+// straight into the lobby, when launched to play online
+pub fn open(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::Lobby);
+}
+
+pub fn setup(mut cmd: Commands, mut lobby: ResMut<Lobby>, settings: Res<Settings>) {
+    if lobby.name.is_empty() {
+        lobby.name = settings.nickname.clone();
+    }
+
+    cmd.spawn((
+        LobbyRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "online",
+            TextStyle {
+                font_size: 40.0,
+                color: SELECTED,
+                ..default()
+            },
+        ));
+
+        for field in [Field::Name, Field::Code, Field::Role] {
+            parent.spawn((
+                LobbyField(field),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 30.0,
+                        color: UNSELECTED,
+                        ..default()
+                    },
+                ),
+            ));
+        }
+
+        parent.spawn((
+            LobbyRoom,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.0,
+                    color: SELECTED,
+                    ..default()
+                },
+            ),
+        ));
+
+        parent.spawn((
+            LobbyHint,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: UNSELECTED,
+                    ..default()
+                },
+            ),
+        ));
+    });
+}
+
+pub fn cleanup(mut cmd: Commands, roots: Query<Entity, With<LobbyRoot>>) {
+    for entity in roots.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn navigate(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    config: Res<GameConfig>,
+    mut lobby: ResMut<Lobby>,
+    mut settings: ResMut<Settings>,
+    session: Option<ResMut<Session>>,
+    mut toast: Toasts,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        cmd.remove_resource::<Session>();
+        next_state.set(AppState::Menu);
+        return;
+    }
+
+    if let Some(mut session) = session {
+        // there's nothing to be ready for until the room has let us in, and
+        // spectators are always ready
+        if session.code.is_some()
+            && session.role == Role::Player
+            && input.any_just_pressed([KeyCode::Enter, KeyCode::Space])
+        {
+            let ready = !session.ready;
+            session.set_ready(ready);
+        }
+
+        return;
+    }
+
+    if input.just_pressed(KeyCode::Tab) {
+        lobby.field = match lobby.field {
+            Field::Name => Field::Code,
+            Field::Code => Field::Role,
+            Field::Role => Field::Name,
+        };
+    }
+
+    let lobby = &mut *lobby;
+    let typing = match lobby.field {
+        Field::Name => Some((&mut lobby.name, MAX_NAME_LENGTH)),
+        Field::Code => Some((&mut lobby.code, ROOM_CODE_LENGTH)),
+        Field::Role => None,
+    };
+
+    if let Some((text, length)) = typing {
+        for c in hud::typed(&input) {
+            if text.len() < length {
+                match lobby.field {
+                    Field::Code => text.push(c.to_ascii_uppercase()),
+                    _ => text.push(c),
+                }
+            }
+        }
+
+        if input.just_pressed(KeyCode::Backspace) {
+            text.pop();
+        }
+    }
+
+    if lobby.field == Field::Role
+        && input.any_just_pressed([KeyCode::ArrowLeft, KeyCode::ArrowRight, KeyCode::Space])
+    {
+        lobby.role = match lobby.role {
+            Role::Player => Role::Spectator,
+            Role::Spectator => Role::Player,
+        };
+    }
+
+    if input.just_pressed(KeyCode::Enter) {
+        if settings.nickname != lobby.name {
+            settings.nickname = lobby.name.clone();
+            settings.save();
+        }
+
+        // an empty code makes a new room
+        let room = Some(lobby.code.clone()).filter(|code| !code.is_empty());
+
+        match lobby.connect(room) {
+            Ok((connection, room)) => cmd.insert_resource(Session::new(
+                connection,
+                &lobby.name,
+                room,
+                lobby.role,
+                &config,
+                &settings,
+            )),
+            Err(e) => toast.show(e),
+        }
+    }
+}
+
+pub fn refresh(
+    lobby: Res<Lobby>,
+    session: Option<Res<Session>>,
+    mut fields: Query<(&LobbyField, &mut Text)>,
+    mut rooms: Query<&mut Text, (With<LobbyRoom>, Without<LobbyField>)>,
+    mut hints: Query<&mut Text, (With<LobbyHint>, Without<LobbyField>, Without<LobbyRoom>)>,
+) {
+    let code = session
+        .as_ref()
+        .and_then(|s| s.code.clone())
+        .unwrap_or_else(|| lobby.code.clone());
+
+    for (field, mut text) in fields.iter_mut() {
+        let value = match field.0 {
+            Field::Name => format!("name  {}", lobby.name),
+            Field::Code if code.is_empty() => "room  (new)".to_owned(),
+            Field::Code => format!("room  {code}"),
+            Field::Role => match lobby.role {
+                Role::Player => "< play >".to_owned(),
+                Role::Spectator => "< watch >".to_owned(),
+            },
+        };
+
+        let color = if session.is_none() && field.0 == lobby.field {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+
+    let room = match session.as_ref() {
+        Some(session) if session.code.is_some() => session
+            .members
+            .iter()
+            .map(|member| {
+                let status = match member.role {
+                    Role::Spectator => "watching",
+                    Role::Player if member.ready => "ready",
+                    Role::Player => "not ready",
+                };
+                format!("{}  {status}", member.name)
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Some(_) => "joining...".to_owned(),
+        None => String::new(),
+    };
+
+    for mut text in rooms.iter_mut() {
+        if text.sections[0].value != room {
+            text.sections[0].value = room.clone();
+        }
+    }
+
+    let hint = match session.as_ref().map(|s| s.role) {
+        Some(Role::Player) => {
+            "enter to toggle ready, the match starts once every player is, escape to leave"
+        }
+        Some(Role::Spectator) => "waiting for the players to get ready, escape to leave",
+        None => "type a name and a room code to join, or no code for a new room, tab to switch, left/right to play or watch, enter to connect, escape to go back",
+    };
+
+    for mut text in hints.iter_mut() {
+        if text.sections[0].value != hint {
+            text.sections[0].value = hint.to_owned();
+        }
+    }
+}
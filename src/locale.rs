@@ -0,0 +1,239 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+// translations are looked up by the english text, which doubles as the
+// fallback for anything a language doesn't have yet. the bundled font only
+// has ascii glyphs, so translations have to stay within it
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Dutch,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Dutch];
+
+    // each language is listed under its own name
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "english",
+            Language::Dutch => "nederlands",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag.get(..2)?.to_ascii_lowercase().as_str() {
+            "en" => Some(Language::English),
+            "nl" => Some(Language::Dutch),
+            _ => None,
+        }
+    }
+
+    // the browser's preferred language on the web, the locale environment
+    // natively
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find_map(|tag| Self::from_tag(&tag))
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn detect() -> Self {
+        web_sys::window()
+            .and_then(|w| w.navigator().language())
+            .and_then(|tag| Self::from_tag(&tag))
+            .unwrap_or_default()
+    }
+
+    pub fn text(self, english: &'static str) -> &'static str {
+        let table = match self {
+            Language::English => return english,
+            Language::Dutch => DUTCH,
+        };
+
+        table
+            .iter()
+            .find(|&&(key, _)| key == english)
+            .map_or(english, |&(_, text)| text)
+    }
+
+    // the translated text with each {} filled in with the next argument
+    pub fn format(self, english: &'static str, args: &[&dyn Display]) -> String {
+        let mut parts = self.text(english).split("{}");
+        let mut value = parts.next().unwrap_or_default().to_owned();
+        let mut args = args.iter();
+
+        for part in parts {
+            if let Some(arg) = args.next() {
+                value.push_str(&arg.to_string());
+            }
+            value.push_str(part);
+        }
+
+        value
+    }
+}
+
+// a label that never changes apart from its language
+#[derive(Component)]
+pub struct Localized(pub &'static str);
+
+pub fn translate(settings: Res<Settings>, mut texts: Query<(Ref<Localized>, &mut Text)>) {
+    for (localized, mut text) in texts.iter_mut() {
+        if !settings.is_changed() && !localized.is_added() {
+            continue;
+        }
+
+        let value = settings.language.text(localized.0);
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value.to_owned();
+        }
+    }
+}
+
+const DUTCH: &[(&str, &str)] = &[
+    // menu
+    ("l to continue your saved game", "l om je opgeslagen spel te hervatten"),
+    (
+        "left/right to change mode, up/down to choose, c to confuse the controls, enter to start, s for settings, k for skins, e for the level editor, i to import a replay, o to play online, h for high scores, u for the tutorial",
+        "links/rechts kiest de modus, omhoog/omlaag kiest, c verwart de besturing, enter start, s voor instellingen, k voor skins, e voor de leveleditor, i importeert een herhaling, o om online te spelen, h voor topscores, u voor de uitleg",
+    ),
+    ("t to train an ai opponent", "t om een ai-tegenstander te trainen"),
+    ("< {} (played) >", "< {} (gespeeld) >"),
+    ("< {} (custom level) >", "< {} (eigen level) >"),
+    (
+        "< {} (seed {}, type to change, z to rewind) >",
+        "< {} (seed {}, typ om te wijzigen, z spoelt terug) >",
+    ),
+    ("< {} (tab to switch snakes) >", "< {} (tab wisselt van slang) >"),
+    ("< {} (f: {} collisions) >", "< {} (f: {} botsingen) >"),
+    ("deadly", "dodelijke"),
+    ("pass-through", "doorlaatbare"),
+    ("{}\nc: {} controls", "{}\nc: {} besturing"),
+    ("mirrored", "gespiegelde"),
+    ("rotating", "draaiende"),
+    // difficulties
+    ("easy", "makkelijk"),
+    ("normal", "normaal"),
+    ("hard", "moeilijk"),
+    ("insane", "krankzinnig"),
+    // modes
+    ("classic", "klassiek"),
+    ("time attack", "tijdrace"),
+    ("survival", "overleven"),
+    ("escalation", "escalatie"),
+    ("daily", "dagelijks"),
+    ("co-op", "samen"),
+    ("fog of war", "oorlogsmist"),
+    ("swap", "wissel"),
+    ("endless", "eindeloos"),
+    ("adaptive", "adaptief"),
+    ("practice", "oefenen"),
+    // settings
+    ("settings", "instellingen"),
+    (
+        "up/down to choose, left/right to change, escape to go back",
+        "omhoog/omlaag kiest, links/rechts wijzigt, escape gaat terug",
+    ),
+    ("theme: {}", "thema: {}"),
+    ("palette: {}", "palet: {}"),
+    ("shape-coded food: {}", "voedsel met vormen: {}"),
+    ("high contrast: {}", "hoog contrast: {}"),
+    ("reduced motion: {}", "minder beweging: {}"),
+    ("large cells: {}", "grote vakjes: {}"),
+    ("near-miss slowdown: {}", "vertragen bij bijna-botsing: {}"),
+    ("input display: {}", "invoerweergave: {}"),
+    ("motion trail: {}", "bewegingsspoor: {}"),
+    ("ai paths: {}", "ai-routes: {}"),
+    ("visit heatmap: {}", "hittekaart van bezoeken: {}"),
+    ("speed: {}", "snelheid: {}"),
+    ("board size: {}", "bordgrootte: {}"),
+    ("keys: {}", "toetsen: {}"),
+    ("language: {}", "taal: {}"),
+    ("rebind keys", "toetsen instellen"),
+    ("custom", "eigen"),
+    ("mouse steering: {}", "muisbesturing: {}"),
+    ("rumble: {}", "trillen: {}"),
+    ("low power mode: {}", "energiebesparing: {}"),
+    ("share anonymous stats: {}", "anonieme statistieken delen: {}"),
+    ("tilt steering: {}", "kantelbesturing: {}"),
+    ("tilt sensitivity: {}", "kantelgevoeligheid: {}"),
+    (
+        "calibrate tilt: hold the device level and press enter",
+        "kanteling ijken: houd het apparaat recht en druk op enter",
+    ),
+    (
+        "calibrate tilt: no tilt sensor",
+        "kanteling ijken: geen kantelsensor",
+    ),
+    ("on", "aan"),
+    ("off", "uit"),
+    ("by difficulty", "per moeilijkheid"),
+    ("small (30x24)", "klein (30x24)"),
+    ("medium (50x40)", "middel (50x40)"),
+    ("large (70x56)", "groot (70x56)"),
+    ("huge (100x80)", "enorm (100x80)"),
+    ("by mode", "per modus"),
+    ("default", "standaard"),
+    ("deuteranopia", "deuteranopie"),
+    ("protanopia", "protanopie"),
+    ("tritanopia", "tritanopie"),
+    ("arrows", "pijltjes"),
+    ("low", "laag"),
+    ("medium", "middel"),
+    ("high", "hoog"),
+    // hud
+    (
+        "paused\nspeed {}\nleft/right to change, p to resume",
+        "gepauzeerd\nsnelheid {}\nlinks/rechts wijzigt, p speelt verder",
+    ),
+    (
+        "new high score {}\n{}\nup/down or type to pick letters, enter to save",
+        "nieuwe topscore {}\n{}\nomhoog/omlaag of typ om letters te kiezen, enter slaat op",
+    ),
+    ("lives: {}", "levens: {}"),
+    ("game over\n{} at {}, {}", "game over\n{} op {}, {}"),
+    ("ran off the edge", "van het bord gegleden"),
+    ("hit a wall", "tegen een muur gebotst"),
+    ("hit an enemy", "tegen een vijand gebotst"),
+    ("ran into itself", "in zichzelf gebeten"),
+    ("ran into another snake", "tegen een andere slang gebotst"),
+    ("ran out of time", "tijd is op"),
+    // level objectives
+    ("length {}/{}", "lengte {}/{}"),
+    ("food {}/{}", "voedsel {}/{}"),
+    ("don't touch the walls", "raak de muren niet aan"),
+    ("touched a wall", "muur aangeraakt"),
+    ("level complete", "level voltooid"),
+    ("objectives failed", "doelen niet gehaald"),
+    // pause menu
+    ("paused", "gepauzeerd"),
+    ("resume", "verder spelen"),
+    ("restart", "opnieuw beginnen"),
+    ("main menu", "hoofdmenu"),
+    (
+        "up/down to choose, enter or click to pick, left/right to change the speed, p or escape to resume",
+        "omhoog/omlaag kiest, enter of klikken selecteert, links/rechts wijzigt de snelheid, p of escape speelt verder",
+    ),
+    ("queue [{}]", "wachtrij [{}]"),
+    // key bindings
+    ("keys", "toetsen"),
+    ("player {} {}: {}", "speler {} {}: {}"),
+    ("up", "omhoog"),
+    ("down", "omlaag"),
+    ("right", "rechts"),
+    ("left", "links"),
+    (
+        "up/down to choose, enter to rebind, escape to go back",
+        "omhoog/omlaag kiest, enter stelt opnieuw in, escape gaat terug",
+    ),
+];
@@ -1,3 +1,94 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(about = "snake")]
+struct Cli {
+    /// Board width in cells
+    #[arg(long)]
+    width: Option<usize>,
+
+    /// Board height in cells
+    #[arg(long)]
+    height: Option<usize>,
+
+    /// Game speed multiplier, from 0.5 to 3
+    #[arg(long)]
+    speed: Option<f32>,
+
+    /// Seed for walls and food placement
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of lives, overriding the difficulty's default
+    #[arg(long)]
+    lives: Option<u32>,
+
+    /// Comma-separated ai level per opponent (easy, medium, hard or evolved), e.g. easy,hard
+    #[arg(long)]
+    ai: Option<String>,
+
+    /// Game mode to start in, e.g. classic, zen or time-attack
+    #[arg(long)]
+    mode: Option<String>,
+
+    /// Level file to play, as saved by the editor
+    #[arg(long)]
+    level: Option<PathBuf>,
+
+    /// Board shape to play on: an image, playable where light, or a text file with '#' for playable cells
+    #[arg(long)]
+    mask: Option<PathBuf>,
+
+    /// Rhai script with custom rules, defining on_tick, on_food_eaten or on_death
+    #[arg(long)]
+    script: Option<PathBuf>,
+
+    /// Twitch channel whose chat steers the snake by voting up, down, left or right
+    #[arg(long)]
+    twitch: Option<String>,
+
+    /// TAS script of per-tick turns to play against --seed, with frame advance and savestates
+    #[arg(long)]
+    tas: Option<PathBuf>,
+
+    /// Relay server to play online on, opening the lobby at startup, e.g. ws://localhost:7878
+    #[arg(long)]
+    connect: Option<String>,
+
+    /// Start in borderless fullscreen
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// Quit as soon as escape is pressed in a round, instead of opening the pause menu
+    #[arg(long)]
+    quit_on_escape: bool,
+}
+
 fn main() {
-    mfro_snake::start();
+    let cli = Cli::parse();
+
+    let options = mfro_snake::LaunchOptions {
+        width: cli.width,
+        height: cli.height,
+        speed: cli.speed,
+        seed: cli.seed,
+        lives: cli.lives,
+        ai: cli.ai,
+        mode: cli.mode,
+        level: cli.level,
+        mask: cli.mask,
+        script: cli.script,
+        twitch: cli.twitch,
+        tas: cli.tas,
+        connect: cli.connect,
+        fullscreen: cli.fullscreen,
+        quit_on_escape: cli.quit_on_escape,
+    };
+
+    if let Err(e) = mfro_snake::launch(options) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,249 @@
+use std::path::Path;
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        texture::{CompressedImageFormats, ImageSampler, ImageType},
+    },
+    sprite::MaterialMesh2dBundle,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{topology::Topology, GameConfig, Position, Spawner, MIN_LIVE_SIZE, START_LENGTH};
+
+// how faint the cells outside the mask are drawn, as a shade of the walls
+pub const VOID_ALPHA: f32 = 0.15;
+
+const VOID_Z: f32 = -1.0;
+
+// the largest mask taken, the same as the largest level a share code holds
+const MAX_SIZE: usize = 1000;
+
+// the playable cells of a board that isn't a rectangle. cells outside the
+// mask are out of bounds, so they kill like walls and nothing is placed on
+// them. stored in replays as text rows to keep them short
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct Mask {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+impl Mask {
+    // one line per row, with '#' marking the playable cells
+    fn parse(text: &str) -> Self {
+        let rows: Vec<_> = text.lines().map(|l| l.trim_end()).collect();
+        let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+
+        let cells = rows
+            .iter()
+            .flat_map(|row| {
+                let mut row: Vec<_> = row.chars().map(|c| c == '#').collect();
+                row.resize(width, false);
+                row
+            })
+            .collect();
+
+        Self {
+            width,
+            height: rows.len(),
+            cells,
+        }
+    }
+
+    // playable where the image is light and opaque, so a 1-bit image works
+    // as it is
+    fn from_image(bytes: &[u8], extension: &str) -> Result<Self, String> {
+        let image = Image::from_buffer(
+            bytes,
+            ImageType::Extension(extension),
+            CompressedImageFormats::NONE,
+            false,
+            ImageSampler::Default,
+            RenderAssetUsages::default(),
+        )
+        .map_err(|e| e.to_string())?;
+
+        let image = image
+            .try_into_dynamic()
+            .map_err(|e| e.to_string())?
+            .to_luma_alpha8();
+
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let cells = image
+            .pixels()
+            .map(|p| p.0[0] >= 128 && p.0[1] >= 128)
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    // png images by their extension, anything else as a text mask
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let mask = if extension == "png" {
+            let bytes = std::fs::read(path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+
+            Self::from_image(&bytes, &extension)
+                .map_err(|e| format!("failed to decode {}: {e}", path.display()))?
+        } else {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+
+            Self::parse(&text)
+        };
+
+        mask.validate()
+            .map_err(|e| format!("mask {} {e}", path.display()))
+    }
+
+    // masks also come in through replays, saves and online matches, which
+    // are checked the same way as files
+    fn validate(self) -> Result<Self, String> {
+        if self.width < MIN_LIVE_SIZE || self.height < MIN_LIVE_SIZE {
+            return Err(format!(
+                "is {}x{}, the minimum is {MIN_LIVE_SIZE}x{MIN_LIVE_SIZE}",
+                self.width, self.height
+            ));
+        }
+
+        if self.width > MAX_SIZE || self.height > MAX_SIZE {
+            return Err(format!(
+                "is {}x{}, the maximum is {MAX_SIZE}x{MAX_SIZE}",
+                self.width, self.height
+            ));
+        }
+
+        if self.rows_with_room().is_empty() {
+            return Err("has no row with room for a snake to start".to_owned());
+        }
+
+        Ok(self)
+    }
+
+    pub(crate) fn apply(self, config: &mut GameConfig) {
+        config.width = self.width;
+        config.height = self.height;
+        config.topology = Topology::Square;
+        config.mask = Some(self);
+    }
+
+    pub(crate) fn contains(&self, position: Position) -> bool {
+        position.x < self.width
+            && position.y < self.height
+            && self.cells[position.y * self.width + position.x]
+    }
+
+    // whether `count` snakes can start on the mask without overlapping
+    pub fn fits(&self, count: usize) -> bool {
+        self.slots().len() >= count
+    }
+
+    // the leftmost start on rows spread evenly over the ones a snake fits on.
+    // with fewer such rows than snakes they start side by side, and a mask
+    // that doesn't `fit` them all has the last ones start on top of others
+    pub(crate) fn starts(&self, count: usize) -> Vec<Position> {
+        let rows = self.rows_with_room();
+
+        if rows.len() >= count {
+            return (0..count)
+                .map(|i| rows[(i + 1) * rows.len() / (count + 1)])
+                .collect();
+        }
+
+        let slots = self.slots();
+
+        (0..count).map(|i| slots[i % slots.len()]).collect()
+    }
+
+    // per row, the first cell with a snake's length and as much again of
+    // open cells to its right
+    fn rows_with_room(&self) -> Vec<Position> {
+        let room = START_LENGTH * 2;
+
+        (0..self.height)
+            .filter_map(|y| {
+                (0..=self.width.saturating_sub(room))
+                    .find(|&x| (x..x + room).all(|x| self.contains(Position::new(x, y))))
+                    .map(|x| Position::new(x, y))
+            })
+            .collect()
+    }
+
+    // every start that doesn't overlap another one, row by row
+    fn slots(&self) -> Vec<Position> {
+        let room = START_LENGTH * 2;
+        let mut slots = vec![];
+
+        for y in 0..self.height {
+            let mut x = 0;
+
+            while x + room <= self.width {
+                if (x..x + room).all(|x| self.contains(Position::new(x, y))) {
+                    slots.push(Position::new(x, y));
+                    x += room;
+                } else {
+                    x += 1;
+                }
+            }
+        }
+
+        slots
+    }
+}
+
+impl TryFrom<Vec<String>> for Mask {
+    type Error = String;
+
+    fn try_from(rows: Vec<String>) -> Result<Self, String> {
+        Self::parse(&rows.join("\n"))
+            .validate()
+            .map_err(|e| format!("mask {e}"))
+    }
+}
+
+impl From<Mask> for Vec<String> {
+    fn from(mask: Mask) -> Self {
+        mask.cells
+            .chunks(mask.width.max(1))
+            .map(|row| row.iter().map(|&c| if c { '#' } else { '.' }).collect())
+            .collect()
+    }
+}
+
+// shade the cells outside the mask so only the playable region reads as
+// board
+pub fn spawn_void(cmd: &mut Commands, spawner: &Spawner, config: &GameConfig) -> Vec<Entity> {
+    let Some(mask) = config.mask.as_ref() else {
+        return vec![];
+    };
+
+    (0..config.height)
+        .flat_map(|y| (0..config.width).map(move |x| Position::new(x, y)))
+        .filter(|&p| !mask.contains(p))
+        .map(|position| {
+            let mut transform = spawner.transform(position);
+            transform.translation.z = VOID_Z;
+
+            cmd.spawn(MaterialMesh2dBundle {
+                mesh: spawner.pixel_mesh.clone(),
+                material: spawner.void_material.clone(),
+                transform,
+                ..default()
+            })
+            .id()
+        })
+        .collect()
+}
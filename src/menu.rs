@@ -0,0 +1,354 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    confusion::Confusion,
+    daily::{self, DailyRecord},
+    difficulty::Difficulty,
+    launch::Overrides,
+    locale::Localized,
+    mode::GameMode,
+    practice, replay,
+    savegame::{Resume, SaveSlot},
+    scores::HighScores,
+    settings::Settings,
+    AppState, FriendlyCollision, GameConfig,
+};
+
+const SELECTED: Color = Color::BLACK;
+const UNSELECTED: Color = Color::GRAY;
+
+#[derive(Component)]
+pub struct MenuRoot;
+
+#[derive(Component)]
+pub struct MenuMode;
+
+#[derive(Component)]
+pub struct MenuEntry(Difficulty);
+
+pub fn setup(mut cmd: Commands, slot: Res<SaveSlot>) {
+    cmd.spawn((
+        MenuRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "snake",
+            TextStyle {
+                font_size: 60.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        ));
+
+        parent.spawn((
+            MenuMode,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+        ));
+
+        for difficulty in Difficulty::ALL {
+            parent.spawn((
+                MenuEntry(difficulty),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 30.0,
+                        color: UNSELECTED,
+                        ..default()
+                    },
+                ),
+            ));
+        }
+
+        if !slot.is_empty() {
+            parent.spawn((
+                Localized("l to continue your saved game"),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::BLACK,
+                        ..default()
+                    },
+                ),
+            ));
+        }
+
+        parent.spawn((
+            Localized("left/right to change mode, up/down to choose, c to confuse the controls, enter to start, s for settings, k for skins, e for the level editor, i to import a replay, o to play online, h for high scores, u for the tutorial"),
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: UNSELECTED,
+                    ..default()
+                },
+            ),
+        ));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        parent.spawn((
+            Localized("t to train an ai opponent"),
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: UNSELECTED,
+                    ..default()
+                },
+            ),
+        ));
+    });
+}
+
+pub fn cleanup(mut cmd: Commands, roots: Query<Entity, With<MenuRoot>>) {
+    for entity in roots.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn navigate(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    mut config: ResMut<GameConfig>,
+    mut settings: ResMut<Settings>,
+    mut slot: ResMut<SaveSlot>,
+    overrides: Option<Res<Overrides>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let difficulty = step(
+        &Difficulty::ALL,
+        config.difficulty,
+        &input,
+        KeyCode::ArrowUp,
+        KeyCode::ArrowDown,
+    );
+    let mode = step(
+        &GameMode::ALL,
+        config.mode,
+        &input,
+        KeyCode::ArrowLeft,
+        KeyCode::ArrowRight,
+    );
+
+    if difficulty != config.difficulty || mode != config.mode {
+        let confusion = config.confusion;
+        let practice_seed = config.seed.filter(|_| config.practice);
+        *config = GameConfig::sized(difficulty, mode, settings.board_size.cells());
+        config.confusion = confusion;
+
+        if let Some(overrides) = overrides.as_ref() {
+            overrides.apply(&mut config);
+            crate::fit_start_room(&mut config);
+        }
+
+        // a typed seed outlasts switching difficulty
+        if config.practice && practice_seed.is_some() {
+            config.seed = practice_seed;
+        }
+    }
+
+    if config.practice {
+        let seed = config.seed.unwrap_or(practice::DEFAULT_SEED);
+        let edited = practice::edit_seed(seed, &input);
+
+        if edited != seed {
+            config.seed = Some(edited);
+        }
+    }
+
+    if input.just_pressed(KeyCode::KeyC) {
+        let index = Confusion::ALL
+            .iter()
+            .position(|&c| c == config.confusion)
+            .unwrap_or(0);
+        config.confusion = Confusion::ALL[(index + 1) % Confusion::ALL.len()];
+    }
+
+    // swapping snakes only works if they block each other
+    if config.players > 1 && !config.swap && input.just_pressed(KeyCode::KeyF) {
+        config.friendly_collision = match config.friendly_collision {
+            FriendlyCollision::Deadly => FriendlyCollision::PassThrough,
+            FriendlyCollision::PassThrough => FriendlyCollision::Deadly,
+        };
+    }
+
+    if input.any_just_pressed([KeyCode::Enter, KeyCode::Space]) {
+        if settings.last_mode != config.mode {
+            settings.last_mode = config.mode;
+            settings.save();
+        }
+
+        next_state.set(AppState::Playing);
+    }
+
+    if input.just_pressed(KeyCode::KeyS) {
+        next_state.set(AppState::Settings);
+    }
+
+    if input.just_pressed(KeyCode::KeyL) {
+        if let Some(saved) = slot.take() {
+            *config = saved.config();
+            cmd.insert_resource(Resume(Some(saved)));
+            next_state.set(AppState::Playing);
+        }
+    }
+
+    if input.just_pressed(KeyCode::KeyK) {
+        next_state.set(AppState::Skins);
+    }
+
+    if input.just_pressed(KeyCode::KeyE) {
+        next_state.set(AppState::Editor);
+    }
+
+    if input.just_pressed(KeyCode::KeyO) {
+        next_state.set(AppState::Lobby);
+    }
+
+    if input.just_pressed(KeyCode::KeyH) {
+        next_state.set(AppState::Leaderboard);
+    }
+
+    if input.just_pressed(KeyCode::KeyU) {
+        next_state.set(AppState::Tutorial);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if input.just_pressed(KeyCode::KeyT) {
+        next_state.set(AppState::Training);
+    }
+
+    if input.just_pressed(KeyCode::KeyI) {
+        match replay::import() {
+            Some(replay) => replay::watch(&mut cmd, replay, &mut next_state),
+            None => warn!("no valid replay to import"),
+        }
+    }
+
+    if input.just_pressed(KeyCode::Escape) {
+        exit.send(AppExit);
+    }
+}
+
+pub fn step<T: Copy + PartialEq>(
+    all: &[T],
+    current: T,
+    input: &ButtonInput<KeyCode>,
+    previous: KeyCode,
+    next: KeyCode,
+) -> T {
+    let mut index = all.iter().position(|&v| v == current).unwrap_or(0);
+
+    if input.just_pressed(previous) {
+        index = index.saturating_sub(1);
+    }
+    if input.just_pressed(next) {
+        index = (index + 1).min(all.len() - 1);
+    }
+
+    all[index]
+}
+
+pub fn refresh(
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    scores: Res<HighScores>,
+    daily: Res<DailyRecord>,
+    mut modes: Query<&mut Text, With<MenuMode>>,
+    mut entries: Query<(&MenuEntry, &mut Text), Without<MenuMode>>,
+) {
+    let language = settings.language;
+    let mode = language.text(config.mode.name());
+
+    let value = if config.mode == GameMode::Daily && daily.played(daily::today()) {
+        language.format("< {} (played) >", &[&mode])
+    } else if config.level.is_some() {
+        language.format("< {} (custom level) >", &[&mode])
+    } else if config.practice {
+        language.format(
+            "< {} (seed {}, type to change, z to rewind) >",
+            &[&mode, &config.seed.unwrap_or_default()],
+        )
+    } else if config.swap {
+        language.format("< {} (tab to switch snakes) >", &[&mode])
+    } else if config.players > 1 {
+        let friendly = match config.friendly_collision {
+            FriendlyCollision::Deadly => "deadly",
+            FriendlyCollision::PassThrough => "pass-through",
+        };
+
+        language.format(
+            "< {} (f: {} collisions) >",
+            &[&mode, &language.text(friendly)],
+        )
+    } else {
+        format!("< {mode} >")
+    };
+
+    let value = match config.confusion {
+        Confusion::Off => value,
+        confusion => language.format(
+            "{}\nc: {} controls",
+            &[&value, &language.text(confusion.name())],
+        ),
+    };
+
+    for mut text in modes.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+
+    for (entry, mut text) in entries.iter_mut() {
+        let name = language.text(entry.0.name());
+        let value = match scores.best(config.mode, entry.0) {
+            Some(best) => {
+                let speed = if best.speed != 1.0 {
+                    format!(" ({}x)", best.speed)
+                } else {
+                    String::new()
+                };
+
+                format!("{name}  {}{speed}  {}", best.score, best.name)
+                    .trim_end()
+                    .to_owned()
+            }
+            None => format!("{name}  0"),
+        };
+
+        let color = if entry.0 == config.difficulty {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::ImageSampler,
+    },
+};
+
+use crate::{camera::CameraMode, Controller, Game, GameConfig, MAX_GRID_CELLS};
+
+const MINIMAP_SIZE: f32 = 150.0;
+const MINIMAP_MARGIN: f32 = 10.0;
+
+const BACKGROUND: [u8; 4] = [255, 255, 255, 200];
+const SNAKE: [u8; 4] = [0, 0, 0, 255];
+const OPPONENT: [u8; 4] = [50, 100, 200, 255];
+const WALL: [u8; 4] = [128, 128, 128, 255];
+const FOOD: [u8; 4] = [220, 40, 40, 255];
+const ENEMY: [u8; 4] = [200, 30, 200, 255];
+const PICKUP: [u8; 4] = [140, 50, 190, 255];
+
+#[derive(Component)]
+pub struct Minimap;
+
+pub fn setup(mut cmd: Commands) {
+    cmd.spawn((
+        Minimap,
+        ImageBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(MINIMAP_MARGIN),
+                bottom: Val::Px(MINIMAP_MARGIN),
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        BorderColor(Color::BLACK),
+    ));
+}
+
+pub fn update(
+    mode: Res<CameraMode>,
+    config: Res<GameConfig>,
+    game: Option<Res<Game>>,
+    mut images: ResMut<Assets<Image>>,
+    mut minimaps: Query<(&mut UiImage, &mut Style, &mut Visibility), With<Minimap>>,
+    mut pixels: Local<Vec<u8>>,
+) {
+    for (mut ui_image, mut style, mut visibility) in minimaps.iter_mut() {
+        // the minimap would give away everything the fog hides, and an
+        // endless board won't fit in one
+        if *mode == CameraMode::Board
+            || config.vision.is_some()
+            || config.width * config.height > MAX_GRID_CELLS
+        {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        *visibility = Visibility::Inherited;
+
+        let Some(game) = game.as_ref() else {
+            continue;
+        };
+
+        let size = Extent3d {
+            width: config.width as u32,
+            height: config.height as u32,
+            depth_or_array_layers: 1,
+        };
+
+        let outdated = images
+            .get(&ui_image.texture)
+            .is_none_or(|image| image.texture_descriptor.size != size);
+
+        if outdated {
+            ui_image.texture = images.add(new_image(size));
+
+            let scale = MINIMAP_SIZE / config.width.max(config.height) as f32;
+            style.width = Val::Px(config.width as f32 * scale);
+            style.height = Val::Px(config.height as f32 * scale);
+        }
+
+        // drawn aside first, since touching the image at all uploads the
+        // whole texture again
+        pixels.clear();
+        pixels.extend(BACKGROUND.repeat(config.width * config.height));
+
+        let mut plot = |x: usize, y: usize, color: [u8; 4]| {
+            if x < config.width && y < config.height {
+                let index = (y * config.width + x) * 4;
+                pixels[index..index + 4].copy_from_slice(&color);
+            }
+        };
+
+        for wall in game.walls.iter() {
+            plot(wall.position.x, wall.position.y, WALL);
+        }
+
+        for snake in game.snakes.iter() {
+            let color = match snake.controller {
+                Controller::Player(_) => SNAKE,
+                Controller::Ai => OPPONENT,
+            };
+
+            for node in snake.nodes.iter() {
+                plot(node.position.x, node.position.y, color);
+            }
+        }
+
+        for food in game.food.iter().chain(game.pellets.iter()) {
+            plot(food.position.x, food.position.y, FOOD);
+        }
+
+        for pickup in game.pickups.iter() {
+            plot(pickup.position.x, pickup.position.y, PICKUP);
+        }
+
+        for hazard in game.enemies.iter() {
+            plot(hazard.route.position.x, hazard.route.position.y, ENEMY);
+        }
+
+        let unchanged = images
+            .get(&ui_image.texture)
+            .is_some_and(|image| image.data == *pixels);
+
+        if !unchanged {
+            let image = images.get_mut(&ui_image.texture).unwrap();
+            image.data.copy_from_slice(&pixels);
+        }
+    }
+}
+
+fn new_image(size: Extent3d) -> Image {
+    let mut image = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &BACKGROUND,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+
+    image.sampler = ImageSampler::nearest();
+
+    image
+}
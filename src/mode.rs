@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    daily, difficulty::Difficulty, endless::ENDLESS_SIZE, practice, theme::Theme,
+    topology::Topology, GameConfig, InputBuffering,
+};
+
+const TIME_ATTACK_LIMIT: f32 = 120.0;
+const SURVIVAL_SHRINK_INTERVAL: f32 = 10.0;
+const ESCALATION_INTERVAL: f32 = 10.0;
+const FOG_VISION: usize = 5;
+const HARDCORE_SPEEDUP: f32 = 0.75;
+const HARDCORE_MULTIPLIER: u32 = 2;
+const DAILY_WALL_DENSITY: f32 = 0.01;
+const BATTLE_ROYALE_WIDTH: usize = 80;
+const BATTLE_ROYALE_HEIGHT: usize = 64;
+const NOKIA_WIDTH: usize = 20;
+const NOKIA_HEIGHT: usize = 12;
+const NOKIA_TICK_INTERVAL: f32 = 1.0 / 7.0;
+const NOKIA_SPEED_RAMP: f32 = 0.995;
+const NOKIA_MIN_TICK_INTERVAL: f32 = 1.0 / 12.0;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    TimeAttack,
+    Survival,
+    Escalation,
+    Zen,
+    Hardcore,
+    Daily,
+    BattleRoyale,
+    Coop,
+    Hex,
+    Tron,
+    Nokia,
+    Fog,
+    Swap,
+    Endless,
+    Adaptive,
+    Practice,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 17] = [
+        GameMode::Classic,
+        GameMode::TimeAttack,
+        GameMode::Survival,
+        GameMode::Escalation,
+        GameMode::Zen,
+        GameMode::Hardcore,
+        GameMode::Daily,
+        GameMode::BattleRoyale,
+        GameMode::Coop,
+        GameMode::Hex,
+        GameMode::Tron,
+        GameMode::Nokia,
+        GameMode::Fog,
+        GameMode::Swap,
+        GameMode::Endless,
+        GameMode::Adaptive,
+        GameMode::Practice,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Classic => "classic",
+            GameMode::TimeAttack => "time attack",
+            GameMode::Survival => "survival",
+            GameMode::Escalation => "escalation",
+            GameMode::Zen => "zen",
+            GameMode::Hardcore => "hardcore",
+            GameMode::Daily => "daily",
+            GameMode::BattleRoyale => "battle royale",
+            GameMode::Coop => "co-op",
+            GameMode::Hex => "hex",
+            GameMode::Tron => "tron",
+            GameMode::Nokia => "nokia",
+            GameMode::Fog => "fog of war",
+            GameMode::Swap => "swap",
+            GameMode::Endless => "endless",
+            GameMode::Adaptive => "adaptive",
+            GameMode::Practice => "practice",
+        }
+    }
+
+    // matches names loosely so "time-attack" or "TimeAttack" work on the command line
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalize = |s: &str| {
+            s.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        };
+
+        let name = normalize(name);
+
+        Self::ALL.into_iter().find(|m| normalize(m.name()) == name)
+    }
+
+    pub(crate) fn apply(self, config: &mut GameConfig) {
+        config.mode = self;
+
+        match self {
+            GameMode::Classic => {}
+            GameMode::TimeAttack => {
+                config.time_limit = Some(TIME_ATTACK_LIMIT);
+            }
+            GameMode::Survival => {
+                config.shrink_interval = Some(SURVIVAL_SHRINK_INTERVAL);
+            }
+            GameMode::Escalation => {
+                config.escalation_interval = Some(ESCALATION_INTERVAL);
+            }
+            GameMode::Zen => {
+                config.wrap = true;
+                config.forgiving = true;
+                config.theme = Theme::ZEN;
+            }
+            GameMode::Hardcore => {
+                config.tick_interval *= HARDCORE_SPEEDUP;
+                config.min_tick_interval *= HARDCORE_SPEEDUP;
+                config.score_multiplier *= HARDCORE_MULTIPLIER;
+                config.input_buffering = InputBuffering::Latest;
+                config.lives = 1;
+            }
+            GameMode::Daily => {
+                *config = Difficulty::Normal.config();
+                config.mode = self;
+                config.wall_density = DAILY_WALL_DENSITY;
+                config.seed = Some(daily::seed(daily::today()));
+            }
+            GameMode::BattleRoyale => {
+                config.width = config.width.max(BATTLE_ROYALE_WIDTH);
+                config.height = config.height.max(BATTLE_ROYALE_HEIGHT);
+                config.opponents = match config.difficulty {
+                    Difficulty::Easy => 3,
+                    Difficulty::Normal => 4,
+                    Difficulty::Hard => 5,
+                    Difficulty::Insane => 7,
+                };
+            }
+            GameMode::Coop => {
+                config.players = 2;
+            }
+            GameMode::Hex => {
+                config.topology = Topology::Hex;
+            }
+            GameMode::Tron => {
+                config.trail = true;
+                // easy is a solo run, other difficulties add an ai light-cycle
+                config.opponents = match config.difficulty {
+                    Difficulty::Easy => 0,
+                    _ => 1,
+                };
+            }
+            GameMode::Nokia => {
+                config.width = NOKIA_WIDTH;
+                config.height = NOKIA_HEIGHT;
+                config.tick_interval = NOKIA_TICK_INTERVAL;
+                config.speed_ramp = NOKIA_SPEED_RAMP;
+                config.min_tick_interval = NOKIA_MIN_TICK_INTERVAL;
+                config.wall_density = 0.0;
+                config.wrap = false;
+                config.theme = Theme::NOKIA;
+                config.pixelated = true;
+            }
+            GameMode::Fog => {
+                config.vision = Some(FOG_VISION);
+            }
+            GameMode::Swap => {
+                config.players = 2;
+                config.swap = true;
+            }
+            GameMode::Endless => {
+                config.width = ENDLESS_SIZE;
+                config.height = ENDLESS_SIZE;
+                config.wrap = true;
+                config.wall_density = 0.0;
+                config.opponents = 0;
+                config.endless = true;
+            }
+            GameMode::Adaptive => {
+                config.adaptive = true;
+            }
+            GameMode::Practice => {
+                config.practice = true;
+                config.seed.get_or_insert(practice::DEFAULT_SEED);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    settings::Settings, topology::Topology, Controller, Game, GameConfig, Offset, GRID_SCALE,
+    PLAYER,
+};
+
+// with mouse steering on, the snake turns towards the cell under the cursor,
+// along whichever axis the cursor is furthest away on. it turns once each
+// time that direction changes, so holding the cursor still doesn't keep
+// queueing turns
+pub fn steer(
+    settings: Res<Settings>,
+    config: Res<GameConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut game: ResMut<Game>,
+    mut held: Local<Option<Offset>>,
+) {
+    // hex boards only turn left and right, which pointing at a cell doesn't
+    // map onto
+    if !settings.mouse_steering || config.topology != Topology::Square {
+        return;
+    }
+
+    let Some(cursor) = windows.get_single().ok().and_then(|w| w.cursor_position()) else {
+        return;
+    };
+
+    let Some(world) = cameras
+        .iter()
+        .find_map(|(camera, transform)| camera.viewport_to_world_2d(transform, cursor))
+    else {
+        return;
+    };
+
+    let player = if config.swap { game.active } else { PLAYER };
+
+    let Some(snake) = game
+        .snakes
+        .iter()
+        .find(|s| s.controller == Controller::Player(player) && s.alive)
+    else {
+        return;
+    };
+
+    // cells count rightwards and downwards from the board's top left corner
+    // at the world origin
+    let head = snake.head();
+    let dx = (world.x / GRID_SCALE).floor() as isize - head.x as isize;
+    let dy = (-world.y / GRID_SCALE).floor() as isize - head.y as isize;
+
+    let (major, minor) = if dx.abs() >= dy.abs() {
+        (Offset::new(dx.signum(), 0), Offset::new(0, dy.signum()))
+    } else {
+        (Offset::new(0, dy.signum()), Offset::new(dx.signum(), 0))
+    };
+
+    // the snake can't turn back on itself, so a cursor behind it turns it
+    // towards the side the cursor is on instead
+    let heading = snake.input_queue.back().copied().unwrap_or(snake.facing);
+    let offset = if major == -heading { minor } else { major };
+
+    let offset = if offset == Offset::default() {
+        None
+    } else {
+        Some(offset)
+    };
+
+    if offset == *held {
+        return;
+    }
+
+    *held = offset;
+
+    let Some(offset) = offset else {
+        return;
+    };
+
+    if game.is_over() || game.paused {
+        return;
+    }
+
+    let offset = game.controls.transform(config.confusion, offset);
+    game.push_input(&config, player, offset);
+}
@@ -0,0 +1,246 @@
+#[cfg(target_arch = "wasm32")]
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{io::ErrorKind, net::TcpStream, thread::JoinHandle};
+
+use snake_protocol::{decode, encode, ClientMessage, ServerMessage};
+#[cfg(not(target_arch = "wasm32"))]
+use tungstenite::{stream::MaybeTlsStream, Error, Message, WebSocket};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[cfg(target_arch = "wasm32")]
+use crate::peer::Peers;
+
+// how an online session reaches the other players. both kinds take the
+// session's messages and hand back what a relay server would send
+pub enum Connection {
+    Relay(Relay),
+    #[cfg(target_arch = "wasm32")]
+    Peers(Peers),
+}
+
+impl Connection {
+    pub fn relay(url: &str) -> Result<Self, String> {
+        Relay::connect(url).map(Self::Relay)
+    }
+
+    // without a relay server the web build plays in a room between peers
+    #[cfg(target_arch = "wasm32")]
+    pub fn peers(room: &str) -> Self {
+        Self::Peers(Peers::connect(room))
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) {
+        match self {
+            Self::Relay(relay) => relay.send(message),
+            #[cfg(target_arch = "wasm32")]
+            Self::Peers(peers) => peers.send(message),
+        }
+    }
+
+    pub fn receive(&mut self) -> Result<Option<ServerMessage>, String> {
+        match self {
+            Self::Relay(relay) => relay.receive(),
+            #[cfg(target_arch = "wasm32")]
+            Self::Peers(peers) => peers.receive(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+// a websocket to the relay server that never blocks the frame: it connects
+// on a thread of its own, and messages are picked up whenever the game asks
+// for the next one
+#[cfg(not(target_arch = "wasm32"))]
+pub struct Relay {
+    socket: Option<Socket>,
+    connecting: Option<JoinHandle<Result<Socket, String>>>,
+    // messages sent before the socket has connected
+    outbox: Vec<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Relay {
+    pub fn connect(url: &str) -> Result<Self, String> {
+        let url = url.to_owned();
+
+        let connecting = std::thread::spawn(move || {
+            let (mut socket, _) = tungstenite::connect(&url)
+                .map_err(|e| format!("failed to connect to {url}: {e}"))?;
+
+            match socket.get_mut() {
+                MaybeTlsStream::Plain(stream) => stream
+                    .set_nonblocking(true)
+                    .map_err(|e| format!("failed to connect to {url}: {e}"))?,
+                _ => return Err("only ws:// servers are supported".to_owned()),
+            }
+
+            Ok(socket)
+        });
+
+        Ok(Self {
+            socket: None,
+            connecting: Some(connecting),
+            outbox: vec![],
+        })
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) {
+        let text = encode(message);
+
+        // a full socket keeps the message queued until the next flush
+        match self.socket.as_mut() {
+            Some(socket) => {
+                let _ = socket.send(Message::Text(text));
+            }
+            None => self.outbox.push(text),
+        }
+    }
+
+    // the next message from the server, if one has come in, or an error once
+    // the connection is gone or couldn't be made
+    pub fn receive(&mut self) -> Result<Option<ServerMessage>, String> {
+        if self.connecting.as_ref().is_some_and(|c| c.is_finished()) {
+            let mut connected = self
+                .connecting
+                .take()
+                .and_then(|c| c.join().ok())
+                .unwrap_or_else(|| Err("failed to connect".to_owned()))?;
+
+            for text in self.outbox.drain(..) {
+                let _ = connected.send(Message::Text(text));
+            }
+
+            self.socket = Some(connected);
+        }
+
+        let Some(socket) = self.socket.as_mut() else {
+            return Ok(None);
+        };
+
+        let _ = socket.flush();
+
+        loop {
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    if let Some(message) = decode(&text) {
+                        return Ok(Some(message));
+                    }
+                }
+                Ok(_) => {}
+                Err(Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    // the browser's socket can't leave the main thread, so it can't live in
+    // a resource
+    static SOCKET: RefCell<Option<web_sys::WebSocket>> = Default::default();
+}
+
+#[cfg(target_arch = "wasm32")]
+pub struct Relay {
+    inbox: Arc<Mutex<VecDeque<String>>>,
+    closed: Arc<AtomicBool>,
+    // messages sent before the socket has opened
+    outbox: Vec<String>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Relay {
+    pub fn connect(url: &str) -> Result<Self, String> {
+        let socket =
+            web_sys::WebSocket::new(url).map_err(|_| format!("failed to connect to {url}"))?;
+
+        let inbox = Arc::<Mutex<VecDeque<String>>>::default();
+        let closed = Arc::<AtomicBool>::default();
+
+        let received = inbox.clone();
+        let on_message = Closure::<dyn FnMut(web_sys::MessageEvent)>::new(
+            move |event: web_sys::MessageEvent| {
+                if let Some(text) = event.data().as_string() {
+                    received.lock().unwrap().push_back(text);
+                }
+            },
+        );
+
+        let gone = closed.clone();
+        let on_close = Closure::<dyn FnMut()>::new(move || gone.store(true, Ordering::Relaxed));
+
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        on_message.forget();
+        on_close.forget();
+
+        SOCKET.with(|s| *s.borrow_mut() = Some(socket));
+
+        Ok(Self {
+            inbox,
+            closed,
+            outbox: vec![],
+        })
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) {
+        self.outbox.push(encode(message));
+        self.flush();
+    }
+
+    fn flush(&mut self) {
+        SOCKET.with(|socket| {
+            let socket = socket.borrow();
+
+            let Some(socket) = socket
+                .as_ref()
+                .filter(|s| s.ready_state() == web_sys::WebSocket::OPEN)
+            else {
+                return;
+            };
+
+            for text in self.outbox.drain(..) {
+                let _ = socket.send_with_str(&text);
+            }
+        });
+    }
+
+    pub fn receive(&mut self) -> Result<Option<ServerMessage>, String> {
+        self.flush();
+
+        while let Some(text) = self.inbox.lock().unwrap().pop_front() {
+            if let Some(message) = decode(&text) {
+                return Ok(Some(message));
+            }
+        }
+
+        if self.closed.load(Ordering::Relaxed) {
+            return Err("the server closed the connection".to_owned());
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for Relay {
+    fn drop(&mut self) {
+        SOCKET.with(|socket| {
+            if let Some(socket) = socket.borrow_mut().take() {
+                let _ = socket.close();
+            }
+        });
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{level::Objectives, Death, DeathCause, Game, GameConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Playing,
+    Complete,
+    Failed,
+}
+
+// the objectives of the level being played, if it has any
+pub fn of(config: &GameConfig) -> Option<&Objectives> {
+    config
+        .level
+        .as_ref()
+        .map(|l| &l.objectives)
+        .filter(|o| !o.is_empty())
+}
+
+// a level's own time limit takes the place of the mode's
+pub fn time_limit(config: &GameConfig) -> Option<f32> {
+    of(config).and_then(|o| o.time_limit).or(config.time_limit)
+}
+
+fn has_targets(objectives: &Objectives) -> bool {
+    objectives.length.is_some() || objectives.food.is_some()
+}
+
+// with nothing to reach, running out the clock is the win
+pub fn is_survival(config: &GameConfig) -> bool {
+    of(config).is_some_and(|o| !has_targets(o))
+}
+
+pub fn status(objectives: &Objectives, game: &Game) -> Status {
+    let targets_met = objectives
+        .length
+        .is_none_or(|l| game.player().nodes.len() >= l)
+        && objectives.food.is_none_or(|f| game.food_eaten >= f);
+
+    if objectives.no_walls && game.touched_wall.is_some() {
+        Status::Failed
+    } else if (has_targets(objectives) && targets_met)
+        || (game.finished && !has_targets(objectives))
+    {
+        Status::Complete
+    } else if game.is_over() {
+        Status::Failed
+    } else {
+        Status::Playing
+    }
+}
+
+// ends the round once the level's objectives are met, or once touching a wall
+// has failed them, returning whether it did. running out of time is left to
+// the time limit
+pub fn check(config: &GameConfig, game: &mut Game) -> bool {
+    let Some(objectives) = of(config) else {
+        return false;
+    };
+
+    match status(objectives, game) {
+        Status::Playing => return false,
+        Status::Complete => {
+            game.finished = true;
+            game.death = None;
+        }
+        Status::Failed => {
+            game.dead = true;
+
+            // a shield or a forgiving rule may have kept the snake alive
+            if let Some(position) = game.touched_wall.filter(|_| game.death.is_none()) {
+                game.death = Some(Death {
+                    cause: DeathCause::Wall,
+                    position,
+                });
+            }
+        }
+    }
+
+    true
+}
@@ -0,0 +1,382 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+use snake_protocol::{ClientMessage, Member, Role, ServerMessage};
+
+use crate::{
+    camera::CameraMode, hud::Toasts, net::Connection, replay::Replay, savegame::SavedGame,
+    settings::Settings, AppState, Controller, Game, GameConfig, Spawner, GRID_SCALE,
+};
+
+// how many ticks the game may run ahead of the server's frames on guesses
+const MAX_PREDICTION: u32 = 8;
+// how far the game can fall behind the frames before it hurries to catch up
+const MAX_LAG: u32 = 2;
+// how quickly other players' snakes slide into place, per second
+const INTERPOLATION_RATE: f32 = 30.0;
+
+// a match on a relay server, or with peers in the browser, set up in the
+// lobby. every client plays the whole game from the same seed with the
+// frames as its playback, so they all see the same game. the local snake
+// doesn't wait for its own turns to come back: the game runs a few ticks
+// ahead on the guess that nobody else turns, and when a frame says otherwise
+// it goes back to the snapshot from before that tick and plays on from there
+#[derive(Resource)]
+pub struct Session {
+    connection: Connection,
+    // the room this client is in, once it's been let in
+    pub code: Option<String>,
+    pub members: Vec<Member>,
+    pub role: Role,
+    pub ready: bool,
+    // the snake this client steers once the match has started, which a
+    // spectator doesn't have
+    pub player: Option<usize>,
+    // frames have come in for every tick before this one
+    confirmed: u32,
+    // local turns sent to the server and not confirmed yet, as (tick, direction)
+    predicted: Vec<(u32, u8)>,
+    // the game at the start of each tick that hasn't been confirmed
+    history: VecDeque<(u32, SavedGame)>,
+}
+
+impl Session {
+    // the match plays whatever the first player to join asks for, described
+    // by an empty replay of it
+    pub fn new(
+        mut connection: Connection,
+        name: &str,
+        room: Option<String>,
+        role: Role,
+        config: &GameConfig,
+        settings: &Settings,
+    ) -> Self {
+        let setup = ron::to_string(&Replay::new(config, 0)).unwrap_or_default();
+        let tick_ms = (config.tick_interval / settings.speed * 1000.0) as u64;
+
+        connection.send(&ClientMessage::Join {
+            name: name.to_owned(),
+            room,
+            role,
+            setup,
+            tick_ms,
+        });
+
+        Self {
+            connection,
+            code: None,
+            members: vec![],
+            role,
+            ready: false,
+            player: None,
+            confirmed: 0,
+            predicted: vec![],
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn set_ready(&mut self, ready: bool) {
+        self.ready = ready;
+        self.connection.send(&ClientMessage::Ready(ready));
+    }
+}
+
+// in the lobby until everyone is ready, then into the match
+pub fn wait(
+    mut cmd: Commands,
+    mut session: ResMut<Session>,
+    mut toast: Toasts,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    loop {
+        let message = match session.connection.receive() {
+            Ok(Some(message)) => message,
+            Ok(None) => return,
+            Err(e) => {
+                toast.show(format!("disconnected: {e}"));
+                cmd.remove_resource::<Session>();
+                return;
+            }
+        };
+
+        match message {
+            ServerMessage::Room { code, members } => {
+                if session.code.is_none() {
+                    toast.show(format!("joined room {code}"));
+                } else {
+                    let names =
+                        |from: &[Member]| from.iter().map(|m| m.name.clone()).collect::<Vec<_>>();
+                    let (before, after) = (names(&session.members), names(&members));
+
+                    for name in after.iter().filter(|n| !before.contains(n)) {
+                        toast.show(format!("{name} joined"));
+                    }
+                    for name in before.iter().filter(|n| !after.contains(n)) {
+                        toast.show(format!("{name} left"));
+                    }
+                }
+
+                session.code = Some(code);
+                session.members = members;
+            }
+            ServerMessage::Refused(reason) => {
+                toast.show(format!("couldn't join: {reason}"));
+                cmd.remove_resource::<Session>();
+                return;
+            }
+            ServerMessage::Start {
+                seed,
+                players,
+                player,
+                setup,
+            } => {
+                let Ok(mut replay) = ron::from_str::<Replay>(&setup) else {
+                    toast.show("the match is for a game this version can't play");
+                    cmd.remove_resource::<Session>();
+                    return;
+                };
+
+                replay.seed = seed;
+
+                let mut config = replay.config();
+                config.players = players;
+
+                if config
+                    .mask
+                    .as_ref()
+                    .is_some_and(|m| !m.fits(players + config.opponents))
+                {
+                    toast.show("the match's board has no room for every player");
+                    cmd.remove_resource::<Session>();
+                    return;
+                }
+
+                cmd.insert_resource(config);
+                session.player = player;
+                next_state.set(AppState::Playing);
+                return;
+            }
+            ServerMessage::Frame { .. } => {}
+        }
+    }
+}
+
+pub fn sync(
+    mut cmd: Commands,
+    spawner: Res<Spawner>,
+    config: Res<GameConfig>,
+    mut session: ResMut<Session>,
+    mut toast: Toasts,
+    mut camera: ResMut<CameraMode>,
+    mut game: ResMut<Game>,
+) {
+    // every snake follows the frames, this client's own included
+    if game.is_added() {
+        let mut playback = game.replay.clone();
+        playback.ticks = u32::MAX;
+
+        game.playback = Some(playback);
+        game.ranked = false;
+
+        if session.player.is_none() {
+            *camera = CameraMode::Free;
+            toast.show("spectating, drag or use the arrow keys to look around");
+        }
+    }
+
+    let session = &mut *session;
+    let mut rollback: Option<u32> = None;
+
+    loop {
+        let message = match session.connection.receive() {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) => {
+                toast.show(format!("disconnected: {e}"));
+                game.finished = true;
+                cmd.remove_resource::<Session>();
+                return;
+            }
+        };
+
+        let ServerMessage::Frame { tick, mut turns } = message else {
+            continue;
+        };
+
+        let played = tick < game.replay.ticks;
+        let Some(playback) = game.playback.as_mut() else {
+            return;
+        };
+
+        let mut guessed: Vec<_> = playback
+            .turns
+            .iter()
+            .filter(|&&(t, _, _)| t == tick)
+            .map(|&(_, player, direction)| (player, direction))
+            .collect();
+
+        playback.turns.retain(|&(t, _, _)| t != tick);
+        playback.turns.extend(
+            turns
+                .iter()
+                .map(|&(player, direction)| (tick, player, direction)),
+        );
+
+        guessed.sort_unstable();
+        turns.sort_unstable();
+
+        if played && guessed != turns {
+            rollback = Some(rollback.map_or(tick, |r| r.min(tick)));
+        }
+
+        session.predicted.retain(|&(t, _)| t != tick);
+        session.confirmed = tick + 1;
+    }
+
+    if let Some(tick) = rollback {
+        if let Some(index) = session.history.iter().position(|&(t, _)| t == tick) {
+            let snapshot = session.history[index].1.clone();
+            session.history.truncate(index);
+
+            game.restore(&mut cmd, &spawner, &config, snapshot);
+            game.countdown = None;
+        }
+    }
+
+    // confirmed ticks are never played again
+    while session
+        .history
+        .front()
+        .is_some_and(|&(t, _)| t < session.confirmed)
+    {
+        session.history.pop_front();
+    }
+
+    if game.is_over() {
+        return;
+    }
+
+    let ticks = game.replay.ticks;
+
+    if ticks >= session.confirmed {
+        if session.history.back().is_none_or(|&(t, _)| t < ticks) {
+            let snapshot = game.snapshot();
+            session.history.push_back((ticks, snapshot));
+        }
+
+        // the next queued turn goes to the server for the coming tick, and
+        // into the playback straight away as a guess
+        if !session.predicted.iter().any(|&(t, _)| t == ticks) {
+            predict(&config, session, &mut game, ticks);
+        }
+    }
+
+    // spectators have no turns of their own to guess ahead on, so they only
+    // play what's been confirmed
+    let prediction = match session.player {
+        Some(_) => MAX_PREDICTION,
+        None => 0,
+    };
+
+    if ticks >= session.confirmed + prediction {
+        game.tick_timer.pause();
+    } else {
+        game.tick_timer.unpause();
+    }
+
+    if session.confirmed.saturating_sub(ticks) > MAX_LAG && game.countdown.is_none() {
+        let duration = game.tick_timer.duration();
+        game.tick_timer.set_elapsed(duration);
+    }
+}
+
+fn predict(config: &GameConfig, session: &mut Session, game: &mut Game, tick: u32) {
+    let Some(player) = session.player else {
+        return;
+    };
+
+    let controller = Controller::Player(player);
+    let Some(snake) = game
+        .snakes
+        .iter_mut()
+        .find(|s| s.controller == controller && s.alive)
+    else {
+        return;
+    };
+
+    let facing = snake.facing;
+    let next = snake.next_facing();
+
+    if next == facing {
+        return;
+    }
+
+    let directions = config.topology.get().directions();
+    let Some(direction) = directions.iter().position(|&d| d == next) else {
+        return;
+    };
+    let direction = direction as u8;
+
+    if let Some(playback) = game.playback.as_mut() {
+        playback.turns.push((tick, player as u8, direction));
+    }
+
+    session.predicted.push((tick, direction));
+    session
+        .connection
+        .send(&ClientMessage::Input { tick, direction });
+}
+
+// other players' snakes only move when a frame comes in, so they slide from
+// cell to cell rather than jumping
+pub fn interpolate(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    spawner: Res<Spawner>,
+    session: Res<Session>,
+    game: Res<Game>,
+    mut transforms: Query<&mut Transform>,
+    mut shown: Local<HashMap<Entity, Vec3>>,
+) {
+    let t = if settings.reduced_motion {
+        1.0
+    } else {
+        1.0 - (-INTERPOLATION_RATE * time.delta_seconds()).exp()
+    };
+
+    // a spectator sees every snake slide
+    let local = session.player.map(Controller::Player);
+    let mut next = HashMap::new();
+
+    for snake in game
+        .snakes
+        .iter()
+        .filter(|s| s.controller.is_player() && Some(s.controller) != local)
+    {
+        for node in snake.nodes.iter() {
+            let Ok(mut transform) = transforms.get_mut(node.entity) else {
+                continue;
+            };
+
+            let target = spawner.transform(node.position).translation;
+            let current = shown.get(&node.entity).copied().unwrap_or(target);
+
+            // wrapping round the board jumps instead of sliding across it
+            let translation = if current.distance(target) > GRID_SCALE * 1.5 {
+                target
+            } else {
+                current.lerp(target, t)
+            };
+
+            transform.translation = translation;
+            next.insert(node.entity, translation);
+        }
+    }
+
+    *shown = next;
+}
+
+// a session is good for one match
+pub fn leave(mut cmd: Commands) {
+    cmd.remove_resource::<Session>();
+}
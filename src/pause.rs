@@ -0,0 +1,237 @@
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+
+use crate::{
+    cleanup_game, locale::Localized, settings::Settings, setup_game, AppState, Game, PauseState,
+};
+
+const SELECTED: Color = Color::BLACK;
+const UNSELECTED: Color = Color::GRAY;
+// the board shows through faintly so it's clear the round is still there
+const BACKDROP: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Resume,
+    Restart,
+    Speed,
+    Settings,
+    Menu,
+}
+
+impl Entry {
+    const ALL: [Entry; 5] = [
+        Entry::Resume,
+        Entry::Restart,
+        Entry::Speed,
+        Entry::Settings,
+        Entry::Menu,
+    ];
+
+    fn label(self, settings: &Settings) -> String {
+        let language = settings.language;
+
+        match self {
+            Entry::Resume => language.text("resume").to_owned(),
+            Entry::Restart => language.text("restart").to_owned(),
+            Entry::Speed => language.format(
+                "speed: {}",
+                &[&crate::settings::speed_label(settings.speed)],
+            ),
+            Entry::Settings => language.text("settings").to_owned(),
+            Entry::Menu => language.text("main menu").to_owned(),
+        }
+    }
+}
+
+// kept across trips to the settings so the cursor comes back where it was
+#[derive(Resource, Default)]
+pub struct PauseMenu {
+    selected: usize,
+}
+
+#[derive(Component)]
+pub struct PauseRoot;
+
+#[derive(Component)]
+pub struct PauseEntry(usize);
+
+// the pause state follows the game's pause flag, however it got set: p, the
+// window losing focus, the touch button or the menu itself
+pub fn sync(
+    game: Res<Game>,
+    state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    match (game.paused, state.get()) {
+        (true, PauseState::Running) => next_state.set(PauseState::Paused),
+        (false, PauseState::Paused | PauseState::Settings | PauseState::Bindings) => {
+            next_state.set(PauseState::Running)
+        }
+        _ => {}
+    }
+}
+
+// leaving a round never leaves its pause menu behind
+pub fn close(mut next_state: ResMut<NextState<PauseState>>) {
+    next_state.set(PauseState::Running);
+}
+
+pub fn setup(mut cmd: Commands) {
+    cmd.init_resource::<PauseMenu>();
+
+    cmd.spawn((
+        PauseRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            background_color: BackgroundColor(BACKDROP),
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            Localized("paused"),
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 40.0,
+                    color: SELECTED,
+                    ..default()
+                },
+            ),
+        ));
+
+        for index in 0..Entry::ALL.len() {
+            parent.spawn((
+                PauseEntry(index),
+                Interaction::None,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: UNSELECTED,
+                        ..default()
+                    },
+                ),
+            ));
+        }
+
+        parent.spawn((
+            Localized("up/down to choose, enter or click to pick, left/right to change the speed, p or escape to resume"),
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: UNSELECTED,
+                    ..default()
+                },
+            ),
+        ));
+    });
+}
+
+pub fn cleanup(mut cmd: Commands, roots: Query<Entity, With<PauseRoot>>) {
+    for entity in roots.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn navigate(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut menu: ResMut<PauseMenu>,
+    entries: Query<(&PauseEntry, &Interaction), Changed<Interaction>>,
+    mut game: ResMut<Game>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if input.any_just_pressed([KeyCode::KeyP, KeyCode::Escape]) {
+        game.toggle_pause();
+        return;
+    }
+
+    if input.just_pressed(KeyCode::ArrowUp) {
+        menu.selected = menu.selected.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        menu.selected = (menu.selected + 1).min(Entry::ALL.len() - 1);
+    }
+
+    let mut picked = input.any_just_pressed([KeyCode::Enter, KeyCode::Space]);
+
+    for (entry, interaction) in entries.iter() {
+        match interaction {
+            Interaction::Hovered => menu.selected = entry.0,
+            Interaction::Pressed => {
+                menu.selected = entry.0;
+                picked = true;
+            }
+            Interaction::None => {}
+        }
+    }
+
+    let entry = Entry::ALL[menu.selected];
+
+    if entry == Entry::Speed {
+        let delta = if input.just_pressed(KeyCode::ArrowLeft) {
+            -1
+        } else if input.just_pressed(KeyCode::ArrowRight) || picked {
+            1
+        } else {
+            0
+        };
+
+        if delta != 0 {
+            settings.step_speed(delta);
+            settings.save();
+        }
+    }
+
+    if !picked {
+        return;
+    }
+
+    match entry {
+        Entry::Resume => game.toggle_pause(),
+        Entry::Restart => {
+            cleanup_game(&mut cmd, &game);
+            cmd.add(|world: &mut World| world.run_system_once(setup_game));
+        }
+        Entry::Speed => {}
+        Entry::Settings => pause_state.set(PauseState::Settings),
+        Entry::Menu => next_state.set(AppState::Menu),
+    }
+}
+
+pub fn refresh(
+    menu: Res<PauseMenu>,
+    settings: Res<Settings>,
+    mut entries: Query<(&PauseEntry, &mut Text)>,
+) {
+    for (entry, mut text) in entries.iter_mut() {
+        let value = Entry::ALL[entry.0].label(&settings);
+
+        let color = if entry.0 == menu.selected {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use bevy::{log::warn, utils::Instant};
+use matchbox_socket::{PeerId, PeerState, SingleChannel, WebRtcSocket};
+use rand::Rng;
+use snake_protocol::{
+    decode, encode, ClientMessage, Member, PeerMessage, Role, ServerMessage, MAX_NAME_LENGTH,
+    MAX_TICK_MS, MIN_TICK_MS, ROOM_CODE_LENGTH, START_DELAY_MS,
+};
+
+// the matchbox signaling server that introduces the peers in a room
+const SIGNALING: &str = match option_env!("SNAKE_SIGNALING_URL") {
+    Some(url) => url,
+    None => "ws://localhost:3536",
+};
+const MAX_PLAYERS: usize = 4;
+
+// browser-to-browser matches over webrtc data channels, with no server in the
+// middle. every peer does for its own turns what the relay server does for
+// everyone's: it picks the tick each one lands on, on its own clock, and
+// tells the others. a frame is only complete once every peer's turn for that
+// tick is in, so the games go in lockstep and the session can't tell this
+// from the relay
+pub struct Peers {
+    socket: WebRtcSocket<SingleChannel>,
+    code: String,
+    // what this peer would like to play, as sent in its join
+    join: Option<(String, u64)>,
+    member: Option<Member>,
+    // the other peers in the room, as they've introduced themselves
+    members: HashMap<PeerId, Member>,
+    // the games peers have picked by who sent them, held until everyone's
+    // connected. only the first player's counts
+    hellos: HashMap<PeerId, (u64, String, u64)>,
+    lockstep: Option<Lockstep>,
+    // turns from the other peers, kept until the match has started here too
+    received: Vec<(PeerId, u32, Option<u8>)>,
+    inbox: VecDeque<ServerMessage>,
+}
+
+struct Lockstep {
+    // the players in the room in player order, this peer too unless it spectates
+    order: Vec<PeerId>,
+    // none for a spectator, who has no turns to send
+    player: Option<usize>,
+    interval: Duration,
+    // when the local turn for `closing` goes out
+    deadline: Instant,
+    closing: u32,
+    // local turns waiting for their tick, as (tick, direction)
+    pending: Vec<(u32, u8)>,
+    // each player's turn on the ticks not complete yet
+    turns: HashMap<u32, Vec<Option<Option<u8>>>>,
+    next_frame: u32,
+}
+
+impl Lockstep {
+    fn add(&mut self, player: usize, tick: u32, direction: Option<u8>) {
+        let players = self.order.len();
+        let turns = self
+            .turns
+            .entry(tick)
+            .or_insert_with(|| vec![None; players]);
+        turns[player] = Some(direction);
+    }
+}
+
+impl Peers {
+    // a room nobody's likely to be in yet
+    pub fn new_code() -> String {
+        let mut rng = rand::thread_rng();
+
+        (0..ROOM_CODE_LENGTH)
+            .map(|_| rng.gen_range(b'A'..=b'Z') as char)
+            .collect()
+    }
+
+    pub fn connect(room: &str) -> Self {
+        let (socket, driver) = WebRtcSocket::new_reliable(format!("{SIGNALING}/{room}"));
+
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(e) = driver.await {
+                warn!("lost the signaling server: {e}");
+            }
+        });
+
+        Self {
+            socket,
+            code: room.to_owned(),
+            join: None,
+            member: None,
+            members: HashMap::new(),
+            hellos: HashMap::new(),
+            lockstep: None,
+            received: vec![],
+            inbox: VecDeque::new(),
+        }
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) {
+        match *message {
+            ClientMessage::Join {
+                ref name,
+                role,
+                ref setup,
+                tick_ms,
+                ..
+            } => {
+                let mut name: String = name.trim().chars().take(MAX_NAME_LENGTH).collect();
+                if name.is_empty() {
+                    name = "player".to_owned();
+                }
+
+                self.join = Some((setup.clone(), tick_ms));
+                // spectators have nothing to get ready for
+                self.member = Some(Member {
+                    name,
+                    role,
+                    ready: role == Role::Spectator,
+                });
+                self.introduce(None);
+            }
+            ClientMessage::Ready(ready) => {
+                if let Some(member) = self.member.as_mut().filter(|m| m.role == Role::Player) {
+                    member.ready = ready;
+                }
+                self.introduce(None);
+            }
+            ClientMessage::Input { tick, direction } => {
+                if let Some(lockstep) = self.lockstep.as_mut() {
+                    lockstep.pending.push((tick, direction));
+                }
+            }
+        }
+    }
+
+    pub fn receive(&mut self) -> Result<Option<ServerMessage>, String> {
+        for (peer, state) in self.socket.update_peers() {
+            let playing = self
+                .lockstep
+                .as_ref()
+                .is_some_and(|l| l.order.contains(&peer));
+
+            if state == PeerState::Disconnected && playing {
+                return Err("a player left the match".to_owned());
+            }
+
+            if self.lockstep.is_none() {
+                match state {
+                    PeerState::Connected => self.introduce(Some(peer)),
+                    PeerState::Disconnected => {
+                        self.members.remove(&peer);
+                        self.show_room();
+                    }
+                }
+            }
+        }
+
+        for (peer, packet) in self.socket.receive() {
+            let Some(message) = std::str::from_utf8(&packet)
+                .ok()
+                .and_then(decode::<PeerMessage>)
+            else {
+                continue;
+            };
+
+            match message {
+                PeerMessage::Member(member) => {
+                    self.members.insert(peer, member);
+                    self.show_room();
+                }
+                PeerMessage::Hello {
+                    seed,
+                    setup,
+                    tick_ms,
+                } => {
+                    self.hellos.insert(peer, (seed, setup, tick_ms));
+                }
+                PeerMessage::Turn { tick, direction } => {
+                    self.received.push((peer, tick, direction))
+                }
+            }
+        }
+
+        if self.lockstep.is_none() {
+            self.start();
+        }
+
+        if let Some(lockstep) = self.lockstep.as_mut() {
+            for (peer, tick, direction) in self.received.drain(..) {
+                if let Some(player) = lockstep.order.iter().position(|&p| p == peer) {
+                    lockstep.add(player, tick, direction);
+                }
+            }
+
+            self.close_ticks();
+            self.complete_frames();
+        }
+
+        Ok(self.inbox.pop_front())
+    }
+
+    // everyone in the room, this peer included, with the players first and
+    // in player order, once it knows its own id
+    fn everyone(&mut self) -> Option<Vec<(PeerId, Member)>> {
+        let id = self.socket.id()?;
+        let member = self.member.clone()?;
+
+        let mut everyone: Vec<_> = self
+            .members
+            .iter()
+            .map(|(&peer, member)| (peer, member.clone()))
+            .collect();
+        everyone.push((id, member));
+        everyone.sort_by_key(|(peer, member)| (member.role, *peer));
+        Some(everyone)
+    }
+
+    // tells one peer, or all of them, who this one is and whether it's ready
+    fn introduce(&mut self, to: Option<PeerId>) {
+        let Some(member) = self.member.clone() else {
+            return;
+        };
+
+        let message = encode(&PeerMessage::Member(member));
+        let peers: Vec<_> = match to {
+            Some(peer) => vec![peer],
+            None => self.socket.connected_peers().collect(),
+        };
+
+        for peer in peers {
+            self.socket
+                .send(message.clone().into_bytes().into_boxed_slice(), peer);
+        }
+
+        self.show_room();
+    }
+
+    // the room as the relay server would describe it
+    fn show_room(&mut self) {
+        let Some(everyone) = self.everyone() else {
+            return;
+        };
+
+        let members = everyone.into_iter().map(|(_, member)| member).collect();
+
+        self.inbox.push_back(ServerMessage::Room {
+            code: self.code.clone(),
+            members,
+        });
+    }
+
+    // once the players in the room are all ready, the first of them picks the
+    // game and everyone starts on it. spectators have to be in the room by
+    // then, as there's nobody to catch them up on what they missed
+    fn start(&mut self) {
+        let (Some(everyone), Some(id)) = (self.everyone(), self.socket.id()) else {
+            return;
+        };
+
+        // peers that haven't said who they are yet hold the match back too
+        let everyone_ready = everyone.iter().all(|(_, m)| m.ready)
+            && self.socket.connected_peers().count() == self.members.len();
+
+        let order: Vec<_> = everyone
+            .iter()
+            .filter(|(_, m)| m.role == Role::Player)
+            .map(|&(peer, _)| peer)
+            .collect();
+
+        if order.len() < 2 || order.len() > MAX_PLAYERS || !everyone_ready {
+            return;
+        }
+
+        let peers: Vec<_> = self.members.keys().copied().collect();
+
+        if order[0] == id && !self.hellos.contains_key(&id) {
+            let Some((setup, tick_ms)) = self.join.clone() else {
+                return;
+            };
+
+            let seed = rand::random();
+            let hello = PeerMessage::Hello {
+                seed,
+                setup: setup.clone(),
+                tick_ms,
+            };
+
+            for &peer in peers.iter() {
+                self.socket
+                    .send(encode(&hello).into_bytes().into_boxed_slice(), peer);
+            }
+
+            self.hellos.insert(id, (seed, setup, tick_ms));
+        }
+
+        let Some((seed, setup, tick_ms)) = self.hellos.remove(&order[0]) else {
+            return;
+        };
+
+        let player = order.iter().position(|&p| p == id);
+
+        self.inbox.push_back(ServerMessage::Start {
+            seed,
+            players: order.len(),
+            player,
+            setup,
+        });
+
+        self.lockstep = Some(Lockstep {
+            order,
+            player,
+            // a tick rate of nothing would close ticks without end
+            interval: Duration::from_millis(tick_ms.clamp(MIN_TICK_MS, MAX_TICK_MS)),
+            deadline: Instant::now() + Duration::from_millis(START_DELAY_MS),
+            closing: 0,
+            pending: vec![],
+            turns: HashMap::new(),
+            next_frame: 0,
+        });
+    }
+
+    // every tick that's due gets this peer's turn, or the lack of one, sent
+    // to the others
+    fn close_ticks(&mut self) {
+        let peers: Vec<_> = self.socket.connected_peers().collect();
+
+        let Some(lockstep) = self.lockstep.as_mut() else {
+            return;
+        };
+        let Some(player) = lockstep.player else {
+            return;
+        };
+
+        while Instant::now() >= lockstep.deadline {
+            let tick = lockstep.closing;
+
+            let direction = lockstep
+                .pending
+                .iter()
+                .position(|&(wanted, _)| wanted <= tick)
+                .map(|i| lockstep.pending.remove(i).1);
+
+            // spectators get the turns as well
+            let message = encode(&PeerMessage::Turn { tick, direction });
+            for &peer in peers.iter() {
+                self.socket
+                    .send(message.clone().into_bytes().into_boxed_slice(), peer);
+            }
+
+            lockstep.add(player, tick, direction);
+
+            lockstep.closing += 1;
+            lockstep.deadline += lockstep.interval;
+        }
+    }
+
+    fn complete_frames(&mut self) {
+        let Some(lockstep) = self.lockstep.as_mut() else {
+            return;
+        };
+
+        loop {
+            let tick = lockstep.next_frame;
+            let complete = lockstep
+                .turns
+                .get(&tick)
+                .is_some_and(|turns| turns.iter().all(Option::is_some));
+
+            if !complete {
+                return;
+            }
+
+            let turns = lockstep
+                .turns
+                .remove(&tick)
+                .unwrap_or_default()
+                .into_iter()
+                .enumerate()
+                .filter_map(|(player, turn)| turn.flatten().map(|d| (player as u8, d)))
+                .collect();
+
+            self.inbox.push_back(ServerMessage::Frame { tick, turns });
+            lockstep.next_frame += 1;
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+
+use crate::{settings::Settings, Controller, Game, Position, Spawner};
+
+// how faint the path an ai snake is following is drawn, as a shade of the
+// opponents
+pub const PLAN_ALPHA: f32 = 0.25;
+const PLAN_Z: f32 = -0.4;
+
+#[derive(Component)]
+pub struct PlanMarker;
+
+// the cells each ai snake meant to take to its food when it last steered,
+// redrawn every tick, to show how it thinks. markers are moved rather than
+// respawned, and only added or removed as the paths grow or shrink
+pub fn draw(
+    mut cmd: Commands,
+    spawner: Res<Spawner>,
+    settings: Res<Settings>,
+    game: Option<Res<Game>>,
+    mut markers: Query<(Entity, &mut Transform), With<PlanMarker>>,
+    mut drawn: Local<Option<u32>>,
+) {
+    let tick = game
+        .as_ref()
+        .filter(|_| settings.show_ai_plans)
+        .map(|g| g.replay.ticks);
+
+    if *drawn == tick && !settings.is_changed() {
+        return;
+    }
+
+    *drawn = tick;
+
+    let mut cells = game
+        .iter()
+        .filter(|_| tick.is_some())
+        .flat_map(|g| g.snakes.iter())
+        .filter(|s| s.controller == Controller::Ai && s.alive)
+        .flat_map(|s| s.plan.iter().copied());
+
+    for (entity, mut transform) in markers.iter_mut() {
+        match cells.next() {
+            Some(position) => *transform = marker_transform(&spawner, position),
+            None => cmd.entity(entity).despawn(),
+        }
+    }
+
+    for position in cells {
+        cmd.spawn((
+            PlanMarker,
+            MaterialMesh2dBundle {
+                mesh: spawner.mesh.clone(),
+                material: spawner.plan_material.clone(),
+                transform: marker_transform(&spawner, position),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn marker_transform(spawner: &Spawner, position: Position) -> Transform {
+    let mut transform = spawner.transform(position);
+    transform.translation.z = PLAN_Z;
+    transform
+}
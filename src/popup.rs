@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+
+use crate::{settings::Settings, theme, FoodEaten, GameConfig, Spawner, GRID_SCALE};
+
+const LIFETIME: f32 = 0.5;
+// how far a popup floats up over its lifetime
+const RISE: f32 = GRID_SCALE * 1.5;
+const FONT_SIZE: f32 = 10.0;
+
+#[derive(Component)]
+pub struct Popup {
+    timer: Timer,
+    start: Vec3,
+}
+
+// the points food was worth, shown where it was eaten
+pub fn spawn(
+    mut cmd: Commands,
+    spawner: Res<Spawner>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    mut eaten: EventReader<FoodEaten>,
+) {
+    let color = theme::current(&config, &settings).food;
+
+    for food in eaten.read() {
+        // above the board and everything on it
+        let start = spawner.transform(food.position).translation + Vec3::Z;
+
+        cmd.spawn((
+            Popup {
+                timer: Timer::from_seconds(LIFETIME, TimerMode::Once),
+                start,
+            },
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("+{}", food.points),
+                    TextStyle {
+                        font_size: FONT_SIZE,
+                        color,
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_translation(start),
+                ..default()
+            },
+        ));
+    }
+}
+
+// popups fade out as they float up, or only fade with reduced motion
+pub fn float(
+    mut cmd: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut popups: Query<(Entity, &mut Popup, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut popup, mut transform, mut text) in popups.iter_mut() {
+        if popup.timer.tick(time.delta()).finished() {
+            cmd.entity(entity).despawn();
+            continue;
+        }
+
+        let progress = popup.timer.fraction();
+
+        let rise = if settings.reduced_motion {
+            0.0
+        } else {
+            RISE * progress
+        };
+
+        transform.translation = popup.start + Vec3::Y * rise;
+        text.sections[0].style.color.set_a(1.0 - progress);
+    }
+}
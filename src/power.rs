@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    winit::{UpdateMode, WinitSettings},
+};
+
+use crate::{settings::Settings, AppState, PauseState};
+
+// menus and the pause screen are only drawn a few times a second, enough to
+// animate toasts, on top of waking up for input
+const IDLE_FRAME: Duration = Duration::from_millis(100);
+
+// in low-power mode the window stops redrawing as fast as it can when nothing
+// is moving. a round runs at most one tick a frame, so it always gets every
+// frame it can, or the faster difficulties would quietly slow down
+pub fn apply(
+    settings: Res<Settings>,
+    state: Res<State<AppState>>,
+    pause: Res<State<PauseState>>,
+    mut winit: ResMut<WinitSettings>,
+) {
+    let idle = *pause.get() != PauseState::Running
+        || matches!(
+            state.get(),
+            AppState::Menu
+                | AppState::Settings
+                | AppState::Bindings
+                | AppState::Skins
+                | AppState::Leaderboard
+                | AppState::Editor
+        );
+
+    let reactive = settings.low_power && idle;
+
+    if matches!(winit.focused_mode, UpdateMode::Reactive { .. }) == reactive {
+        return;
+    }
+
+    let mode = if reactive {
+        UpdateMode::Reactive { wait: IDLE_FRAME }
+    } else {
+        UpdateMode::Continuous
+    };
+
+    winit.focused_mode = mode;
+    winit.unfocused_mode = mode;
+}
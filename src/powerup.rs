@@ -0,0 +1,255 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    advance, rewind::Checkpoint, undo_move, Game, GameConfig, Position, Spawner, START_LENGTH,
+};
+
+// chance that eating regular food also drops a power-up, when none is out
+const DROP_CHANCE: f64 = 0.2;
+// chance that a drop is a rewind rather than one of the common kinds
+const REWIND_CHANCE: f64 = 0.05;
+
+const SHRINK_SEGMENTS: usize = 3;
+const SHRINK_POINTS: u32 = 5;
+
+pub const MAGNET_TICKS: u32 = 150;
+
+const SHIELD_OUTLINE_SCALE: f32 = 1.4;
+const BURST_SECONDS: f32 = 0.3;
+const BURST_SCALE: f32 = 2.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerUp {
+    Shrink,
+    Magnet,
+    Shield,
+    // if the player dies soon after, the game goes back to when it was picked
+    // up, see `rewind`
+    Rewind,
+}
+
+impl PowerUp {
+    pub const COMMON: [PowerUp; 3] = [PowerUp::Shrink, PowerUp::Magnet, PowerUp::Shield];
+}
+
+pub struct Pickup {
+    pub entity: Entity,
+    pub position: Position,
+    pub kind: PowerUp,
+}
+
+pub fn drop_rng(seed: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(1);
+    rng
+}
+
+// whether a drop is a rewind is rolled on a third stream, so drops in replays
+// recorded before rewinds existed keep their kinds and places
+pub fn rewind_rng(seed: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(3);
+    rng
+}
+
+pub fn try_drop(cmd: &mut Commands, spawner: &Spawner, config: &GameConfig, game: &mut Game) {
+    if !game.pickups.is_empty() || !game.drop_rng.gen_bool(DROP_CHANCE) {
+        return;
+    }
+
+    let common = PowerUp::COMMON[game.drop_rng.gen_range(0..PowerUp::COMMON.len())];
+
+    let kind = if game.rewind_rng.gen_bool(REWIND_CHANCE) {
+        PowerUp::Rewind
+    } else {
+        common
+    };
+
+    let position = loop {
+        let x = game
+            .drop_rng
+            .gen_range(game.margin..config.width - game.margin);
+        let y = game
+            .drop_rng
+            .gen_range(game.margin..config.height - game.margin);
+        let position = Position::new(x, y);
+
+        if !game.is_occupied(position) {
+            break position;
+        }
+    };
+
+    game.pickups.push(Pickup {
+        entity: spawner.new_pickup(cmd, position, kind),
+        position,
+        kind,
+    });
+}
+
+// apply a picked up power-up to the snake at `index`, which has already
+// moved onto it
+pub fn collect(
+    cmd: &mut Commands,
+    config: &GameConfig,
+    game: &mut Game,
+    index: usize,
+    pickup: usize,
+) {
+    let pickup = game.pickups.swap_remove(pickup);
+    cmd.entity(pickup.entity).despawn();
+
+    match pickup.kind {
+        PowerUp::Shrink => {
+            let snake = &mut game.snakes[index];
+            let count = SHRINK_SEGMENTS.min(snake.nodes.len().saturating_sub(START_LENGTH));
+
+            for node in snake.nodes.drain(..count) {
+                cmd.entity(node.entity).despawn();
+            }
+
+            game.score += SHRINK_POINTS * config.score_multiplier;
+        }
+        PowerUp::Magnet => {
+            game.magnet = MAGNET_TICKS;
+        }
+        PowerUp::Shield => {
+            game.snakes[index].shielded = true;
+        }
+        PowerUp::Rewind => {
+            game.checkpoint = Some(Checkpoint::default());
+        }
+    }
+}
+
+// spend the shield of the snake at `index` instead of killing it: undo its
+// last move so it sits still for this tick
+pub fn absorb(
+    cmd: &mut Commands,
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    game: &mut Game,
+    index: usize,
+) {
+    game.snakes[index].shielded = false;
+
+    undo_move(cmd, transforms, spawner, game, index);
+
+    if let Some(head) = game.snakes[index].nodes.last() {
+        let mut transform = spawner.transform(head.position);
+        transform.translation.z = -0.5;
+
+        cmd.spawn((
+            ShieldBurst(Timer::from_seconds(BURST_SECONDS, TimerMode::Once)),
+            MaterialMesh2dBundle {
+                mesh: spawner.mesh.clone(),
+                material: spawner.shield_material.clone(),
+                transform,
+                ..default()
+            },
+        ));
+    }
+}
+
+#[derive(Component)]
+pub struct ShieldOutline;
+
+#[derive(Component)]
+pub struct ShieldBurst(Timer);
+
+// a slightly larger cell in the shield color sits behind the head of every
+// shielded snake
+pub fn show_shield(
+    mut cmd: Commands,
+    game: Option<Res<Game>>,
+    spawner: Res<Spawner>,
+    mut outlines: Query<(Entity, &mut Transform), With<ShieldOutline>>,
+) {
+    let mut heads = game
+        .iter()
+        .flat_map(|g| g.snakes.iter())
+        .filter(|s| s.shielded && s.alive)
+        .filter_map(|s| s.nodes.last())
+        .map(|node| {
+            let mut transform = spawner.transform(node.position);
+            transform.translation.z = -0.5;
+            transform.with_scale(Vec3::splat(SHIELD_OUTLINE_SCALE))
+        });
+
+    for (entity, mut transform) in outlines.iter_mut() {
+        match heads.next() {
+            Some(head) => *transform = head,
+            None => cmd.entity(entity).despawn(),
+        }
+    }
+
+    for transform in heads {
+        cmd.spawn((
+            ShieldOutline,
+            MaterialMesh2dBundle {
+                mesh: spawner.mesh.clone(),
+                material: spawner.shield_material.clone(),
+                transform,
+                ..default()
+            },
+        ));
+    }
+}
+
+pub fn animate_bursts(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut bursts: Query<(Entity, &mut ShieldBurst, &mut Transform)>,
+) {
+    for (entity, mut burst, mut transform) in bursts.iter_mut() {
+        if burst.0.tick(time.delta()).finished() {
+            cmd.entity(entity).despawn();
+            continue;
+        }
+
+        let scale = 1.0 + (BURST_SCALE - 1.0) * burst.0.fraction();
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+// while a magnet is active, pull the food one free cell closer to the
+// player's head every tick
+pub fn pull_food(
+    transforms: &mut Query<&mut Transform>,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+) {
+    if game.magnet == 0 {
+        return;
+    }
+
+    game.magnet -= 1;
+
+    let (Some(food), Some(head)) = (game.food.as_ref(), game.player().nodes.last()) else {
+        return;
+    };
+
+    let topology = config.topology.get();
+    let target = topology.to_world(head.position);
+    let distance = |p: Position| topology.to_world(p).distance_squared(target);
+
+    let next = topology
+        .directions()
+        .iter()
+        .map(|&d| advance(config, food.position, d))
+        .filter(|&p| game.is_live(config, p) && !game.is_occupied(p))
+        .filter(|&p| distance(p) < distance(food.position))
+        .min_by(|&a, &b| distance(a).total_cmp(&distance(b)));
+
+    if let Some(position) = next {
+        let food = game.food.as_mut().unwrap();
+        food.position = position;
+
+        if let Ok(mut transform) = transforms.get_mut(food.entity) {
+            *transform = spawner.transform(position);
+        }
+    }
+}
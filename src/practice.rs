@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{is_out_of_bounds, Game, GameConfig, Position, Spawner};
+
+// the seed practice starts on until another is typed in the menu
+pub const DEFAULT_SEED: u64 = 1;
+// how faint the upcoming food is drawn, as a shade of the food
+pub const PREVIEW_ALPHA: f32 = 0.3;
+
+const PREVIEW: usize = 3;
+const PREVIEW_Z: f32 = -0.5;
+// enough digits for any seed typed in the menu to fit in a u64
+const MAX_SEED_DIGITS: u32 = 18;
+
+// food in practice comes off its own stream, rolled ahead of time, so every
+// run on a seed gets the same food in the same order however it's played
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preview {
+    rng: ChaCha8Rng,
+    upcoming: VecDeque<Position>,
+    #[serde(skip)]
+    markers: Vec<Entity>,
+}
+
+impl Preview {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(2);
+
+        Self {
+            rng,
+            upcoming: VecDeque::new(),
+            markers: vec![],
+        }
+    }
+
+    // only walls rule a cell out when it's rolled, so where the snakes happen
+    // to be doesn't change the sequence
+    fn fill(&mut self, config: &GameConfig, game: &Game) {
+        while self.upcoming.len() < PREVIEW {
+            let x = self.rng.gen_range(game.margin..config.width - game.margin);
+            let y = self.rng.gen_range(game.margin..config.height - game.margin);
+            let position = Position::new(x, y);
+
+            if !is_out_of_bounds(config, position)
+                && !game.walls.iter().any(|w| w.position == position)
+                && !self.upcoming.contains(&position)
+            {
+                self.upcoming.push_back(position);
+            }
+        }
+    }
+
+    pub fn redraw(&mut self, cmd: &mut Commands, spawner: &Spawner) {
+        self.despawn(cmd);
+
+        self.markers = self
+            .upcoming
+            .iter()
+            .map(|&position| {
+                let mut transform = spawner.transform(position);
+                transform.translation.z = PREVIEW_Z;
+
+                cmd.spawn(MaterialMesh2dBundle {
+                    mesh: spawner.mesh.clone(),
+                    material: spawner.preview_material.clone(),
+                    transform,
+                    ..default()
+                })
+                .id()
+            })
+            .collect();
+    }
+
+    pub fn despawn(&self, cmd: &mut Commands) {
+        for &entity in self.markers.iter() {
+            cmd.entity(entity).despawn();
+        }
+    }
+}
+
+// the next food in the sequence that's free right now; a cell something is
+// sitting on keeps its place in the queue for later
+pub fn food_cell(config: &GameConfig, game: &mut Game) -> Option<Position> {
+    let mut preview = game.preview.take()?;
+
+    preview.fill(config, game);
+
+    let free = preview.upcoming.iter().position(|&p| !game.is_occupied(p));
+    let position = match free.and_then(|i| preview.upcoming.remove(i)) {
+        Some(position) => position,
+        None => game.random_free_cell(config),
+    };
+
+    preview.fill(config, game);
+    game.preview = Some(preview);
+
+    Some(position)
+}
+
+// typing digits in the menu appends them to the seed, backspace drops the
+// last one
+pub fn edit_seed(seed: u64, input: &ButtonInput<KeyCode>) -> u64 {
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+
+    let mut seed = seed;
+
+    for (digit, &key) in DIGITS.iter().enumerate() {
+        if input.just_pressed(key) && seed < 10u64.pow(MAX_SEED_DIGITS - 1) {
+            seed = seed * 10 + digit as u64;
+        }
+    }
+
+    if input.just_pressed(KeyCode::Backspace) {
+        seed /= 10;
+    }
+
+    seed
+}
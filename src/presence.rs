@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use bevy::{app::AppExit, prelude::*};
+use discord_rich_presence::{activity::Activity, DiscordIpc, DiscordIpcClient};
+
+use crate::{AppState, Game, GameConfig};
+
+// the discord application the presence shows up under, baked in at build
+// time
+const CLIENT_ID: Option<&str> = option_env!("SNAKE_DISCORD_CLIENT_ID");
+
+// discord throttles activity updates, so there's no point sending them more
+// often than this
+const UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+// shows what's being played on the player's discord profile
+#[derive(Resource)]
+pub struct Presence {
+    client: DiscordIpcClient,
+    // what discord was last told, and when
+    shown: Option<(String, String)>,
+    last_update: Option<Duration>,
+    closed: bool,
+}
+
+impl Presence {
+    // `None` when discord isn't running, which is nothing to complain about
+    pub fn connect() -> Option<Self> {
+        let mut client = DiscordIpcClient::new(CLIENT_ID?).ok()?;
+        client.connect().ok()?;
+
+        Some(Self {
+            client,
+            shown: None,
+            last_update: None,
+            closed: false,
+        })
+    }
+
+    fn close(&mut self) {
+        if !self.closed {
+            self.closed = true;
+            let _ = self.client.clear_activity();
+            let _ = self.client.close();
+        }
+    }
+}
+
+impl Drop for Presence {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+pub fn update(
+    state: Res<State<AppState>>,
+    config: Res<GameConfig>,
+    game: Option<Res<Game>>,
+    time: Res<Time<Real>>,
+    mut presence: ResMut<Presence>,
+    mut exit: EventReader<AppExit>,
+) {
+    if exit.read().count() > 0 {
+        presence.close();
+    }
+
+    if presence.closed {
+        return;
+    }
+
+    let shown = match (state.get(), game) {
+        (AppState::Playing, Some(game)) => (
+            config.mode.name().to_owned(),
+            format!("score {}, length {}", game.score, game.player().nodes.len()),
+        ),
+        (AppState::Watching, _) => ("watching a replay".to_owned(), String::new()),
+        (AppState::Editor, _) => ("editing a level".to_owned(), String::new()),
+        (AppState::Tutorial, _) => ("learning to play".to_owned(), String::new()),
+        _ => ("in the menus".to_owned(), String::new()),
+    };
+
+    let now = time.elapsed();
+    let due = presence
+        .last_update
+        .map_or(true, |last| now - last >= UPDATE_INTERVAL);
+
+    if !due || presence.shown.as_ref() == Some(&shown) {
+        return;
+    }
+
+    let (details, status) = &shown;
+    let mut activity = Activity::new().details(details);
+    if !status.is_empty() {
+        activity = activity.state(status);
+    }
+
+    if let Err(e) = presence.client.set_activity(activity) {
+        warn!("failed to update discord presence: {e}");
+    }
+
+    presence.shown = Some(shown);
+    presence.last_update = Some(now);
+}
@@ -0,0 +1,127 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+};
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use crate::{
+    cleanup_game, hud::Toasts, launch::Overrides, level::Level, settings::Settings, setup_game,
+    storage, AppState, Game, GameConfig,
+};
+
+// picks up edits to the settings file, and the level given with --level, while
+// the game runs. new settings take effect at once, and a new level restarts
+// the round being played on it
+#[derive(Resource)]
+pub struct Watcher {
+    // dropping the watcher stops it
+    _watcher: RecommendedWatcher,
+    changed: Mutex<Receiver<PathBuf>>,
+    settings: Option<PathBuf>,
+    level: Option<PathBuf>,
+}
+
+impl Watcher {
+    pub fn new(level: Option<&Path>) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::channel();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+
+                if event.kind.is_modify() || event.kind.is_create() {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+            })
+            .map_err(|e| format!("failed to watch for changes: {e}"))?;
+
+        let settings = storage::path("settings");
+        let level = level.and_then(|p| std::fs::canonicalize(p).ok());
+
+        // editors often save by replacing the file, which only its directory
+        // sees
+        for path in settings.iter().chain(level.iter()) {
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+
+                watcher
+                    .watch(dir, RecursiveMode::NonRecursive)
+                    .map_err(|e| format!("failed to watch {}: {e}", dir.display()))?;
+            }
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            changed: Mutex::new(receiver),
+            settings,
+            level,
+        })
+    }
+}
+
+// saving by replacing the file can report it under another path to the same
+// directory, so only the name is compared
+fn matches(watched: &Option<PathBuf>, path: &Path) -> bool {
+    watched
+        .as_ref()
+        .is_some_and(|w| w.file_name() == path.file_name())
+}
+
+pub fn apply(
+    mut cmd: Commands,
+    watcher: Res<Watcher>,
+    state: Res<State<AppState>>,
+    game: Option<Res<Game>>,
+    mut settings: ResMut<Settings>,
+    mut overrides: ResMut<Overrides>,
+    mut config: ResMut<GameConfig>,
+    mut toast: Toasts,
+) {
+    let changed: Vec<_> = watcher.changed.lock().unwrap().try_iter().collect();
+
+    let settings_changed = changed.iter().any(|p| matches(&watcher.settings, p));
+    let level_changed = changed.iter().any(|p| matches(&watcher.level, p));
+
+    // a file half way through being written doesn't parse, and the write that
+    // finishes it comes along as another change
+    if settings_changed {
+        if let Some(loaded) = Settings::read().filter(|s| *s != *settings) {
+            *settings = loaded;
+            toast.show("settings reloaded");
+        }
+    }
+
+    let Some(path) = watcher.level.as_ref().filter(|_| level_changed) else {
+        return;
+    };
+
+    let level = match Level::from_file(path) {
+        Ok(level) => level,
+        Err(e) => {
+            warn!("{e}");
+            return;
+        }
+    };
+
+    if config.level.as_ref() == Some(&level) {
+        return;
+    }
+
+    overrides.set_level(level.clone());
+    level.apply(&mut config);
+    toast.show("level reloaded");
+
+    if let Some(game) = game.filter(|_| *state.get() == AppState::Playing) {
+        cleanup_game(&mut cmd, &game);
+        cmd.add(|world: &mut World| world.run_system_once(setup_game));
+    }
+}
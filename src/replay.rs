@@ -0,0 +1,181 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::{self, AiLevel, Weights},
+    confusion::Confusion,
+    difficulty::Difficulty,
+    level::Level,
+    mask::Mask,
+    mode::GameMode,
+    share, AppState, FriendlyCollision, GameConfig, Offset,
+};
+
+// everything needed to re-run a round deterministically: the config is
+// rebuilt from difficulty and mode, and only changes of player facing are
+// stored, keyed by the tick they took effect on
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+    pub(crate) friendly_collision: FriendlyCollision,
+    pub seed: u64,
+    pub ticks: u32,
+    pub turns: Vec<(u32, u8, u8)>,
+    pub shrinks: Vec<u32>,
+    #[serde(default)]
+    pub escalations: Vec<u32>,
+    // ticks on which control passed to the next snake in swap mode
+    #[serde(default)]
+    pub swaps: Vec<u32>,
+    // ticks whose rewind checkpoint the player went back to. the death that
+    // spent it isn't in the replay, so playback spends it there instead
+    #[serde(default)]
+    pub rewinds: Vec<u32>,
+    #[serde(default)]
+    pub level: Option<Level>,
+    #[serde(default)]
+    pub mask: Option<Mask>,
+    // older replays always played on the size their difficulty and mode set
+    #[serde(default)]
+    pub size: Option<(usize, usize)>,
+    // replays from before lives were added always had a single life
+    #[serde(default = "default_lives")]
+    pub lives: u32,
+    // only kept so a resumed save plays with the same controls, turns are
+    // recorded after confusion is applied
+    #[serde(default)]
+    pub confusion: Confusion,
+    #[serde(default)]
+    pub ai_levels: Vec<AiLevel>,
+    #[serde(default)]
+    pub evolved: Weights,
+}
+
+fn default_lives() -> u32 {
+    1
+}
+
+impl Replay {
+    pub(crate) fn new(config: &GameConfig, seed: u64) -> Self {
+        Self {
+            difficulty: config.difficulty,
+            mode: config.mode,
+            friendly_collision: config.friendly_collision,
+            seed,
+            level: config.level.clone(),
+            mask: config.mask.clone(),
+            size: Some((config.width, config.height)),
+            lives: config.lives,
+            confusion: config.confusion,
+            ai_levels: ai::levels(config),
+            evolved: config.evolved,
+            ..default()
+        }
+    }
+
+    pub(crate) fn config(&self) -> GameConfig {
+        let mut config = GameConfig::sized(self.difficulty, self.mode, self.size);
+        config.friendly_collision = self.friendly_collision;
+        config.seed = Some(self.seed);
+        config.lives = self.lives;
+        config.confusion = self.confusion;
+        // replays from before ai levels were recorded have none, and their
+        // opponents have to steer the way they did back then
+        config.ai_levels = if self.ai_levels.is_empty() {
+            vec![AiLevel::Legacy; config.opponents]
+        } else {
+            self.ai_levels.clone()
+        };
+        config.evolved = self.evolved;
+
+        if let Some(level) = self.level.clone() {
+            level.apply(&mut config);
+        }
+
+        if let Some(mask) = self.mask.clone() {
+            mask.apply(&mut config);
+        }
+
+        config
+    }
+
+    pub(crate) fn record_turn(
+        &mut self,
+        config: &GameConfig,
+        tick: u32,
+        player: usize,
+        facing: Offset,
+    ) {
+        let directions = config.topology.get().directions();
+
+        if let Some(direction) = directions.iter().position(|&d| d == facing) {
+            self.turns.push((tick, player as u8, direction as u8));
+        }
+    }
+
+    pub(crate) fn turn(&self, config: &GameConfig, tick: u32, player: usize) -> Option<Offset> {
+        let directions = config.topology.get().directions();
+
+        self.turns
+            .iter()
+            .find(|&&(t, p, _)| t == tick && p as usize == player)
+            .and_then(|&(_, _, direction)| directions.get(direction as usize).copied())
+    }
+
+    pub fn encode(&self) -> Option<String> {
+        let text = ron::to_string(self).ok()?;
+
+        Some(URL_SAFE_NO_PAD.encode(text))
+    }
+
+    pub fn decode(code: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(code).ok()?;
+
+        ron::from_str(std::str::from_utf8(&bytes).ok()?).ok()
+    }
+}
+
+#[derive(Resource)]
+pub struct Playback(pub Replay);
+
+pub fn watch(cmd: &mut Commands, replay: Replay, next_state: &mut NextState<AppState>) {
+    cmd.insert_resource(replay.config());
+    cmd.insert_resource(Playback(replay));
+    next_state.set(AppState::Watching);
+}
+
+pub fn watch_input(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
+    if input.any_just_pressed([KeyCode::Escape, KeyCode::KeyM]) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+pub fn cleanup(mut cmd: Commands, mut config: ResMut<GameConfig>) {
+    cmd.remove_resource::<Playback>();
+
+    // drop the replay's seed so the next round played from the menu is fresh
+    config.seed = GameConfig::new(config.difficulty, config.mode).seed;
+}
+
+pub fn export(replay: &Replay) -> bool {
+    replay
+        .encode()
+        .is_some_and(|code| share::export("replay", code))
+}
+
+pub fn import() -> Option<Replay> {
+    Replay::decode(&share::import("replay")?)
+}
+
+pub fn open_link(mut cmd: Commands, mut next_state: ResMut<NextState<AppState>>) {
+    let Some(code) = share::link_param("replay") else {
+        return;
+    };
+
+    match Replay::decode(&code) {
+        Some(replay) => watch(&mut cmd, replay, &mut next_state),
+        None => warn!("failed to read replay from link"),
+    }
+}
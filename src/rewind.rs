@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{savegame::SavedGame, Game, GameConfig, Spawner};
+
+// ticks of history practice keeps, and how far back each press of z goes
+const HISTORY: usize = 30;
+const UNDO_TICKS: usize = 5;
+
+// how long after picking up a rewind a death still sends the player back, in
+// seconds of game time
+const CHECKPOINT_SECONDS: f32 = 10.0;
+
+// a snapshot of the whole game after every tick in practice, reusing the
+// savegame format so a rewind restores exactly what a continued save would
+pub fn record(config: Res<GameConfig>, mut game: ResMut<Game>) {
+    if !config.practice || game.is_over() {
+        return;
+    }
+
+    let ticks = game.replay.ticks;
+
+    if game.history.back().is_some_and(|&(t, _)| t == ticks) {
+        return;
+    }
+
+    let snapshot = game.snapshot();
+
+    if game.history.len() == HISTORY {
+        game.history.pop_front();
+    }
+    game.history.push_back((ticks, snapshot));
+}
+
+// back a few ticks, or to the oldest snapshot kept
+pub fn undo(cmd: &mut Commands, spawner: &Spawner, config: &GameConfig, game: &mut Game) {
+    let mut history = std::mem::take(&mut game.history);
+    history.truncate(history.len().saturating_sub(UNDO_TICKS).max(1));
+
+    let Some((_, snapshot)) = history.back() else {
+        return;
+    };
+
+    game.restore(cmd, spawner, config, snapshot.clone());
+    game.history = history;
+}
+
+// the state a rewind power-up goes back to. it's taken once the tick it was
+// picked up on has finished, so the game resumes from a whole tick
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    left: f32,
+    snapshot: Option<Box<SavedGame>>,
+}
+
+impl Default for Checkpoint {
+    fn default() -> Self {
+        Self {
+            left: CHECKPOINT_SECONDS,
+            snapshot: None,
+        }
+    }
+}
+
+// at the end of every tick, snapshot a fresh pickup or count down the one
+// already taken
+pub fn update_checkpoint(game: &mut Game) {
+    let Some(mut checkpoint) = game.checkpoint.take() else {
+        return;
+    };
+
+    let ticks = game.replay.ticks;
+
+    if checkpoint.snapshot.is_none() {
+        // in playback the pickup was already spent going back to this tick
+        if game
+            .playback
+            .as_ref()
+            .is_some_and(|p| p.rewinds.contains(&ticks))
+        {
+            return;
+        }
+
+        checkpoint.snapshot = Some(Box::new(game.snapshot()));
+    } else {
+        checkpoint.left -= game.tick_timer.duration().as_secs_f32();
+
+        if checkpoint.left <= 0.0 {
+            return;
+        }
+    }
+
+    game.checkpoint = Some(checkpoint);
+}
+
+// when a player has died, go back to the checkpoint instead, spending it.
+// false if there's none to go back to
+pub fn to_checkpoint(
+    cmd: &mut Commands,
+    spawner: &Spawner,
+    config: &GameConfig,
+    game: &mut Game,
+) -> bool {
+    let Some(snapshot) = game.checkpoint.as_ref().and_then(|c| c.snapshot.clone()) else {
+        return false;
+    };
+
+    let history = std::mem::take(&mut game.history);
+
+    game.restore(cmd, spawner, config, *snapshot);
+
+    let ticks = game.replay.ticks;
+    game.replay.rewinds.push(ticks);
+    game.history = history;
+
+    true
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{Game, GameConfig, Position};
+
+// custom rules for crates embedding the game, registered with
+// `SnakePlugin::with_rule`. every callback has a default that keeps the
+// standard rules, so a rule only implements what it changes
+pub trait GameRule: Send + Sync + 'static {
+    // relative chance of food being placed on the free cell at x, y. weights
+    // of several rules multiply, and a weight of 0 keeps food off the cell
+    fn spawn_weight(&self, _x: usize, _y: usize) -> f32 {
+        1.0
+    }
+
+    // what happens to a snake about to die. the first rule not returning
+    // `Outcome::Default` decides
+    fn collision(&self, _collision: &Collision) -> Outcome {
+        Outcome::Default
+    }
+
+    // points for a piece of food before the score multiplier, with the
+    // points the previous rule settled on
+    fn score(&self, points: u32, _food: &FoodEaten) -> u32 {
+        points
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionKind {
+    OutOfBounds,
+    Wall,
+    Snake,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Collision {
+    pub kind: CollisionKind,
+    pub x: usize,
+    pub y: usize,
+    pub player: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    // shields absorb the hit, otherwise the snake dies
+    Default,
+    Die,
+    // the snake's move is undone and it sits still for the tick
+    Survive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoodEaten {
+    pub x: usize,
+    pub y: usize,
+    pub pellet: bool,
+    pub player: bool,
+    pub length: usize,
+}
+
+#[derive(Resource, Clone, Default)]
+pub(crate) struct Rules(Vec<Arc<dyn GameRule>>);
+
+impl Rules {
+    pub(crate) fn push(&mut self, rule: impl GameRule) {
+        self.0.push(Arc::new(rule));
+    }
+
+    pub(crate) fn collision(&self, collision: &Collision) -> Outcome {
+        self.0
+            .iter()
+            .map(|r| r.collision(collision))
+            .find(|&o| o != Outcome::Default)
+            .unwrap_or(Outcome::Default)
+    }
+
+    pub(crate) fn score(&self, points: u32, food: &FoodEaten) -> u32 {
+        self.0
+            .iter()
+            .fold(points, |points, r| r.score(points, food))
+    }
+
+    // a free live cell picked by the rules' spawn weights, or `None` when
+    // there are no spawn rules or they rule out every cell
+    pub(crate) fn food_cell(&self, config: &GameConfig, game: &mut Game) -> Option<Position> {
+        if self.0.is_empty() {
+            return None;
+        }
+
+        let cells: Vec<_> = (game.margin..config.height - game.margin)
+            .flat_map(|y| {
+                (game.margin..config.width - game.margin).map(move |x| Position::new(x, y))
+            })
+            .filter(|&p| !game.is_occupied(p))
+            .map(|p| {
+                let weight = self
+                    .0
+                    .iter()
+                    .map(|r| r.spawn_weight(p.x, p.y).max(0.0))
+                    .product::<f32>();
+
+                (p, weight)
+            })
+            .filter(|&(_, w)| w > 0.0)
+            .collect();
+
+        let total: f32 = cells.iter().map(|&(_, w)| w).sum();
+
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut pick = game.rng.gen_range(0.0..total);
+
+        for &(position, weight) in cells.iter() {
+            if pick < weight {
+                return Some(position);
+            }
+            pick -= weight;
+        }
+
+        cells.last().map(|&(p, _)| p)
+    }
+}
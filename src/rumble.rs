@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use bevy::{
+    input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest},
+    prelude::*,
+};
+
+use crate::{settings::Settings, Game, SnakeDied};
+
+const EAT_SECONDS: f32 = 0.1;
+const DEATH_SECONDS: f32 = 0.4;
+
+// a short buzz on the light motor for food and a longer one on the heavy
+// motor for a death, on every connected gamepad
+pub fn feedback(
+    settings: Res<Settings>,
+    game: Res<Game>,
+    gamepads: Res<Gamepads>,
+    mut died: EventReader<SnakeDied>,
+    mut requests: EventWriter<GamepadRumbleRequest>,
+    mut eaten: Local<u32>,
+) {
+    let dead = died.read().count() > 0;
+
+    // a new round starts counting from nothing again
+    let ate = game.food_eaten > *eaten && !game.is_added();
+    *eaten = game.food_eaten;
+
+    let scale = settings.rumble.scale();
+
+    let (intensity, seconds) = if scale == 0.0 {
+        return;
+    } else if dead {
+        (GamepadRumbleIntensity::strong_motor(scale), DEATH_SECONDS)
+    } else if ate {
+        (GamepadRumbleIntensity::weak_motor(scale * 0.5), EAT_SECONDS)
+    } else {
+        return;
+    };
+
+    for gamepad in gamepads.iter() {
+        requests.send(GamepadRumbleRequest::Add {
+            gamepad,
+            duration: Duration::from_secs_f32(seconds),
+            intensity,
+        });
+    }
+}
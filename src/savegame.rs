@@ -0,0 +1,351 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    adaptive::Tracker,
+    confusion::Controls,
+    enemy::{self, Route},
+    keys::{self, KeyColor},
+    powerup::{self, Pickup, PowerUp},
+    practice::Preview,
+    replay::Replay,
+    rewind::Checkpoint,
+    storage, terrain, Controller, Game, GameConfig, Offset, Position, Snake, SnakeWall, Spawner,
+    FOOD_POINTS, RESUME_COUNTDOWN,
+};
+
+const STORAGE_KEY: &str = "savegame";
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SavedSnake {
+    nodes: Vec<Position>,
+    facing: Offset,
+    controller: Controller,
+    alive: bool,
+    #[serde(default)]
+    shielded: bool,
+    #[serde(default)]
+    input_queue: VecDeque<Offset>,
+    #[serde(default)]
+    last_tail: Option<Position>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    replay: Replay,
+    ranked: bool,
+    speed: f32,
+    margin: usize,
+    score: u32,
+    #[serde(default)]
+    played: f32,
+    #[serde(default)]
+    food_eaten: u32,
+    #[serde(default = "default_lives")]
+    lives: u32,
+    #[serde(default)]
+    invulnerable: u32,
+    #[serde(default)]
+    slow_motion: u32,
+    #[serde(default)]
+    near_miss: bool,
+    tick_interval: f32,
+    time_elapsed: Option<f32>,
+    shrink_elapsed: Option<f32>,
+    #[serde(default)]
+    escalation_elapsed: Option<f32>,
+    food: Option<Position>,
+    #[serde(default = "default_food_value")]
+    food_value: f32,
+    pellets: Vec<Position>,
+    #[serde(default)]
+    pickups: Vec<(Position, PowerUp)>,
+    #[serde(default)]
+    magnet: u32,
+    walls: Vec<Position>,
+    #[serde(default)]
+    enemies: Vec<Route>,
+    #[serde(default)]
+    keys: Vec<(Position, KeyColor)>,
+    #[serde(default)]
+    doors: Vec<(Position, KeyColor)>,
+    snakes: Vec<SavedSnake>,
+    rng: ChaCha8Rng,
+    #[serde(default)]
+    drop_rng: Option<ChaCha8Rng>,
+    #[serde(default)]
+    rewind_rng: Option<ChaCha8Rng>,
+    // how far rotating controls have turned, and how long until they turn
+    #[serde(default)]
+    controls: Controls,
+    #[serde(default)]
+    chunks: Vec<(usize, usize)>,
+    #[serde(default)]
+    adaptive: Option<Tracker>,
+    #[serde(default)]
+    preview: Option<Preview>,
+    #[serde(default)]
+    checkpoint: Option<Checkpoint>,
+    #[serde(default)]
+    touched_wall: Option<Position>,
+}
+
+fn default_lives() -> u32 {
+    1
+}
+
+fn default_food_value() -> f32 {
+    FOOD_POINTS as f32
+}
+
+impl SavedGame {
+    pub(crate) fn new(game: &Game) -> Self {
+        Self {
+            replay: game.replay.clone(),
+            ranked: game.ranked,
+            speed: game.speed,
+            margin: game.margin,
+            score: game.score,
+            played: game.played,
+            food_eaten: game.food_eaten,
+            lives: game.lives,
+            invulnerable: game.invulnerable,
+            slow_motion: game.slow_motion,
+            near_miss: game.near_miss,
+            tick_interval: game.tick_timer.duration().as_secs_f32(),
+            time_elapsed: game.time_limit.as_ref().map(|t| t.elapsed_secs()),
+            shrink_elapsed: game.shrink_timer.as_ref().map(|t| t.elapsed_secs()),
+            escalation_elapsed: game.escalation_timer.as_ref().map(|t| t.elapsed_secs()),
+            food: game.food.as_ref().map(|f| f.position),
+            food_value: game.food_value,
+            pellets: game.pellets.iter().map(|p| p.position).collect(),
+            pickups: game.pickups.iter().map(|p| (p.position, p.kind)).collect(),
+            magnet: game.magnet,
+            walls: game.walls.iter().map(|w| w.position).collect(),
+            enemies: game.enemies.iter().map(|e| e.route.clone()).collect(),
+            keys: game.keys.iter().map(|k| (k.position, k.color)).collect(),
+            doors: game.doors.clone(),
+            snakes: game
+                .snakes
+                .iter()
+                .map(|s| SavedSnake {
+                    nodes: s.nodes.iter().map(|n| n.position).collect(),
+                    facing: s.facing,
+                    controller: s.controller,
+                    alive: s.alive,
+                    shielded: s.shielded,
+                    input_queue: s.input_queue.clone(),
+                    last_tail: s.last_tail,
+                })
+                .collect(),
+            rng: game.rng.clone(),
+            drop_rng: Some(game.drop_rng.clone()),
+            rewind_rng: Some(game.rewind_rng.clone()),
+            controls: game.controls.clone(),
+            chunks: game.chunks.iter().copied().collect(),
+            adaptive: Some(game.adaptive.clone()),
+            preview: game.preview.clone(),
+            checkpoint: game.checkpoint.clone(),
+            touched_wall: game.touched_wall,
+        }
+    }
+
+    pub(crate) fn config(&self) -> GameConfig {
+        let mut config = self.replay.config();
+
+        // practice keeps its seed for the rounds after this one
+        if !config.practice {
+            config.seed = GameConfig::new(config.difficulty, config.mode).seed;
+        }
+
+        config
+    }
+
+    pub(crate) fn restore(
+        self,
+        cmd: &mut Commands,
+        spawner: &Spawner,
+        config: &GameConfig,
+    ) -> Game {
+        let timer = |duration: Option<f32>, elapsed: Option<f32>, mode| {
+            let mut timer = Timer::from_seconds(duration?, mode);
+            timer.set_elapsed(std::time::Duration::from_secs_f32(elapsed.unwrap_or(0.0)));
+            Some(timer)
+        };
+
+        let walls = self
+            .walls
+            .into_iter()
+            .map(|position| {
+                let ring = position.x < self.margin
+                    || position.y < self.margin
+                    || position.x >= config.width - self.margin
+                    || position.y >= config.height - self.margin;
+
+                let door = self.doors.iter().find(|&&(p, _)| p == position);
+
+                if let Some(&(_, color)) = door {
+                    let entity = keys::new_door(cmd, spawner, position, color);
+                    SnakeWall { entity, position }
+                } else if ring {
+                    spawner.new_ring(cmd, position)
+                } else {
+                    spawner.new_wall(cmd, position)
+                }
+            })
+            .collect();
+
+        let snakes = self
+            .snakes
+            .into_iter()
+            .map(|saved| {
+                let mut snake = Snake::new(saved.controller);
+                snake.facing = saved.facing;
+                snake.alive = saved.alive;
+                snake.shielded = saved.shielded;
+                snake.input_queue = saved.input_queue;
+                snake.last_tail = saved.last_tail;
+                snake.nodes = saved
+                    .nodes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, p)| spawner.new_node(cmd, p, saved.controller, i))
+                    .collect();
+                snake
+            })
+            .collect();
+
+        let (terrain, tiles) = terrain::spawn_level(cmd, spawner, config);
+
+        let drop_rng = self
+            .drop_rng
+            .unwrap_or_else(|| powerup::drop_rng(self.replay.seed));
+        let rewind_rng = self
+            .rewind_rng
+            .unwrap_or_else(|| powerup::rewind_rng(self.replay.seed));
+
+        let active = self.replay.swaps.len() % config.players.max(1);
+
+        let mut preview = self.preview;
+        if let Some(preview) = preview.as_mut() {
+            preview.redraw(cmd, spawner);
+        }
+
+        Game {
+            dead: false,
+            finished: false,
+            ranked: self.ranked,
+            paused: false,
+            replay: self.replay,
+            playback: None,
+            speed: self.speed,
+            played: self.played,
+            countdown: Some(Timer::from_seconds(RESUME_COUNTDOWN, TimerMode::Once)),
+            time_limit: timer(
+                crate::objectives::time_limit(config),
+                self.time_elapsed,
+                TimerMode::Once,
+            ),
+            shrink_timer: timer(
+                config.shrink_interval,
+                self.shrink_elapsed,
+                TimerMode::Repeating,
+            ),
+            escalation_timer: timer(
+                config.escalation_interval,
+                self.escalation_elapsed,
+                TimerMode::Repeating,
+            ),
+            margin: self.margin,
+            score: self.score,
+            food_eaten: self.food_eaten,
+            lives: self.lives,
+            invulnerable: self.invulnerable,
+            slow_motion: self.slow_motion,
+            near_miss: self.near_miss,
+            food: self
+                .food
+                .map(|p| spawner.new_food(cmd, p, crate::FoodKind::Regular)),
+            food_value: self.food_value,
+            pellets: self
+                .pellets
+                .into_iter()
+                .map(|p| spawner.new_food(cmd, p, crate::FoodKind::Pellet))
+                .collect(),
+            pickups: self
+                .pickups
+                .into_iter()
+                .map(|(position, kind)| Pickup {
+                    entity: spawner.new_pickup(cmd, position, kind),
+                    position,
+                    kind,
+                })
+                .collect(),
+            magnet: self.magnet,
+            walls,
+            terrain,
+            tiles,
+            keys: self
+                .keys
+                .into_iter()
+                .map(|(position, color)| keys::new_key(cmd, spawner, position, color))
+                .collect(),
+            doors: self.doors,
+            enemies: self
+                .enemies
+                .into_iter()
+                .map(|route| enemy::spawn(cmd, spawner, route))
+                .collect(),
+            snakes,
+            tick_timer: Timer::from_seconds(self.tick_interval, TimerMode::Repeating),
+            rng: self.rng,
+            drop_rng,
+            rewind_rng,
+            controls: self.controls,
+            rules: default(),
+            active,
+            chunks: self.chunks.into_iter().collect(),
+            adaptive: self.adaptive.unwrap_or_else(|| Tracker::new(self.lives)),
+            preview,
+            history: VecDeque::new(),
+            checkpoint: self.checkpoint,
+            inputs: VecDeque::new(),
+            eaten: vec![],
+            death: None,
+            touched_wall: self.touched_wall,
+        }
+    }
+}
+
+// the single save slot; a saved game is consumed when it is continued so a
+// run can't be retried from the same point
+#[derive(Resource, Default)]
+pub struct SaveSlot(Option<SavedGame>);
+
+impl SaveSlot {
+    pub fn load() -> Self {
+        Self(storage::load(STORAGE_KEY))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    pub fn store(&mut self, game: &Game) {
+        let saved = SavedGame::new(game);
+        storage::save(STORAGE_KEY, &saved);
+        self.0 = Some(saved);
+    }
+
+    pub fn take(&mut self) -> Option<SavedGame> {
+        storage::remove(STORAGE_KEY);
+        self.0.take()
+    }
+}
+
+// handed from the menu to `setup_game`, which takes the saved game out of it
+#[derive(Resource)]
+pub struct Resume(pub Option<SavedGame>);
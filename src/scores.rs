@@ -0,0 +1,234 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    daily, difficulty::Difficulty, hud, mode::GameMode, replay::Replay, settings::Settings,
+    storage, Death, Game, GameConfig, RoundOver,
+};
+
+const STORAGE_KEY: &str = "scores";
+const MAX_ENTRIES: usize = 10;
+// initials, arcade style
+pub const NAME_LENGTH: usize = 3;
+const LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+// every table is saved together, and in the web build has to share a few
+// megabytes of local storage with everything else, so longer runs are kept
+// without their replay
+const MAX_REPLAY_SIZE: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    #[serde(default)]
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    pub score: u32,
+    pub length: usize,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    // empty for scores set before names were asked for
+    #[serde(default)]
+    pub name: String,
+    // seconds of play, when the score was set in milliseconds since the
+    // epoch, and the run itself, none of which older scores kept
+    #[serde(default)]
+    pub seconds: f32,
+    #[serde(default)]
+    pub date: u64,
+    #[serde(default)]
+    pub replay: Option<Replay>,
+    // what ended the run, none if it was won
+    #[serde(default)]
+    pub death: Option<Death>,
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn best(&self, mode: GameMode, difficulty: Difficulty) -> Option<&HighScore> {
+        self.entries
+            .iter()
+            .filter(|e| e.mode == mode && e.difficulty == difficulty)
+            .max_by_key(|e| e.score)
+    }
+
+    // whether a score would make it onto its table
+    pub fn qualifies(&self, mode: GameMode, difficulty: Difficulty, score: u32) -> bool {
+        let table = self.table(mode, difficulty);
+
+        score > 0 && (table.len() < MAX_ENTRIES || table.iter().any(|e| score > e.score))
+    }
+
+    // the table for a mode and difficulty, best first
+    pub fn table(&self, mode: GameMode, difficulty: Difficulty) -> Vec<&HighScore> {
+        self.entries
+            .iter()
+            .filter(|e| e.mode == mode && e.difficulty == difficulty)
+            .collect()
+    }
+
+    pub fn insert(&mut self, entry: HighScore) {
+        self.entries.push(entry);
+        self.entries.sort_by_key(|e| Reverse(e.score));
+
+        let mut counts = HashMap::new();
+        self.entries.retain(|e| {
+            let count = counts.entry((e.mode, e.difficulty)).or_insert(0);
+            *count += 1;
+            *count <= MAX_ENTRIES
+        });
+    }
+}
+
+// a run that made the table, held back from it until the player has put
+// their initials to it
+#[derive(Resource)]
+pub struct NameEntry {
+    entry: HighScore,
+    // indices into the alphabet
+    letters: [usize; NAME_LENGTH],
+    cursor: usize,
+}
+
+impl NameEntry {
+    // starts from the player's online nickname, if they have one
+    fn new(entry: HighScore, nickname: &str) -> Self {
+        let mut letters = [0; NAME_LENGTH];
+
+        for (letter, c) in letters.iter_mut().zip(nickname.chars()) {
+            if let Some(index) = LETTERS
+                .iter()
+                .position(|&l| l == c.to_ascii_uppercase() as u8)
+            {
+                *letter = index;
+            }
+        }
+
+        Self {
+            entry,
+            letters,
+            cursor: 0,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.letters.iter().map(|&l| LETTERS[l] as char).collect()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.entry.score
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+}
+
+pub fn record(
+    mut cmd: Commands,
+    mut events: EventReader<RoundOver>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    game: Res<Game>,
+    scores: Res<HighScores>,
+    mut toast: hud::Toasts,
+) {
+    for event in events.read() {
+        if !game.ranked || !scores.qualifies(config.mode, config.difficulty, event.score) {
+            continue;
+        }
+
+        let best = scores
+            .best(config.mode, config.difficulty)
+            .is_none_or(|b| event.score > b.score);
+        toast.show(if best {
+            "new best score!"
+        } else {
+            "new high score!"
+        });
+
+        let entry = HighScore {
+            mode: config.mode,
+            difficulty: config.difficulty,
+            score: event.score,
+            length: event.length,
+            speed: game.speed,
+            name: String::new(),
+            seconds: game.played,
+            date: daily::now_millis(),
+            replay: ron::to_string(&game.replay)
+                .is_ok_and(|r| r.len() <= MAX_REPLAY_SIZE)
+                .then(|| game.replay.clone()),
+            death: event.death,
+        };
+
+        cmd.insert_resource(NameEntry::new(entry, &settings.nickname));
+    }
+}
+
+// up and down pick each letter, or it can be typed, and enter saves
+pub fn enter_name(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    mut entry: ResMut<NameEntry>,
+    mut scores: ResMut<HighScores>,
+) {
+    let cursor = entry.cursor;
+
+    if input.just_pressed(KeyCode::ArrowUp) {
+        entry.letters[cursor] = (entry.letters[cursor] + 1) % LETTERS.len();
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        entry.letters[cursor] = (entry.letters[cursor] + LETTERS.len() - 1) % LETTERS.len();
+    }
+
+    for c in hud::typed(&input) {
+        let cursor = entry.cursor;
+
+        if let Some(index) = LETTERS
+            .iter()
+            .position(|&l| l == c.to_ascii_uppercase() as u8)
+        {
+            entry.letters[cursor] = index;
+            entry.cursor = (cursor + 1).min(NAME_LENGTH - 1);
+        }
+    }
+
+    if input.just_pressed(KeyCode::ArrowLeft) || input.just_pressed(KeyCode::Backspace) {
+        entry.cursor = entry.cursor.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::ArrowRight) {
+        entry.cursor = (entry.cursor + 1).min(NAME_LENGTH - 1);
+    }
+
+    if input.just_pressed(KeyCode::Enter) {
+        let mut score = entry.entry.clone();
+        score.name = entry.name();
+
+        save(&mut scores, score);
+        cmd.remove_resource::<NameEntry>();
+    }
+}
+
+// leaving the game before the name is in still keeps the score
+pub fn skip_name(mut cmd: Commands, entry: Res<NameEntry>, mut scores: ResMut<HighScores>) {
+    save(&mut scores, entry.entry.clone());
+    cmd.remove_resource::<NameEntry>();
+}
+
+fn save(scores: &mut HighScores, entry: HighScore) {
+    scores.insert(entry);
+    storage::save(STORAGE_KEY, &*scores);
+}
@@ -0,0 +1,48 @@
+use bevy::{prelude::*, render::view::screenshot::ScreenshotManager, window::PrimaryWindow};
+
+use crate::{daily, hud::Toasts};
+
+pub fn capture(
+    input: Res<ButtonInput<KeyCode>>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    mut toast: Toasts,
+) {
+    if !input.any_just_pressed([KeyCode::F12, KeyCode::PrintScreen]) {
+        return;
+    }
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let Some(path) = path(&format!("snake-{}.png", daily::now_millis())) else {
+        return;
+    };
+
+    match screenshots.save_screenshot_to_disk(window, &path) {
+        Ok(()) => toast.show(format!("saved {}", path.display())),
+        Err(e) => warn!("failed to take screenshot: {e}"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn path(name: &str) -> Option<std::path::PathBuf> {
+    let dir = match directories::UserDirs::new().and_then(|d| d.picture_dir().map(Into::into)) {
+        Some(dir) => dir,
+        None => directories::ProjectDirs::from("", "mfro", "snake")?
+            .data_dir()
+            .join("screenshots"),
+    };
+
+    let _ = std::fs::create_dir_all(&dir);
+
+    Some(dir.join(name))
+}
+
+// on the web bevy hands the capture to the browser as a download, so only
+// the file name matters
+#[cfg(target_arch = "wasm32")]
+pub fn path(name: &str) -> Option<std::path::PathBuf> {
+    Some(name.into())
+}
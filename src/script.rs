@@ -0,0 +1,176 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+use crate::{FoodEaten, FoodKind, Game, GameConfig, Position, SnakeDied, Spawner};
+
+const HOOKS: [&str; 3] = ["on_tick", "on_food_eaten", "on_death"];
+
+// what scripts ask for through the api, applied to the game after each hook
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    SpawnFood(i64, i64),
+    SpawnWall(i64, i64),
+    SetSpeed(f64),
+}
+
+// a user script with custom rules, loaded with --script. it can define any of
+// the hooks
+//
+//     fn on_tick(game) {}
+//     fn on_food_eaten(game) {}
+//     fn on_death(game) {}
+//
+// which get the tick, score, length, head_x, head_y, width and height in
+// `game`, and can call spawn_food(x, y), spawn_wall(x, y) and set_speed(x)
+#[derive(Resource)]
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    actions: Arc<Mutex<Vec<Action>>>,
+    // the tick on_tick last ran on
+    tick: u32,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+
+        let actions = Arc::new(Mutex::new(vec![]));
+        let mut engine = Engine::new();
+
+        let queue = actions.clone();
+        engine.register_fn("spawn_food", move |x: i64, y: i64| {
+            queue.lock().unwrap().push(Action::SpawnFood(x, y));
+        });
+
+        let queue = actions.clone();
+        engine.register_fn("spawn_wall", move |x: i64, y: i64| {
+            queue.lock().unwrap().push(Action::SpawnWall(x, y));
+        });
+
+        let queue = actions.clone();
+        engine.register_fn("set_speed", move |speed: f64| {
+            queue.lock().unwrap().push(Action::SetSpeed(speed));
+        });
+
+        let ast = engine
+            .compile(source)
+            .map_err(|e| format!("failed to compile {}: {e}", path.display()))?;
+
+        Ok(Self {
+            engine,
+            ast,
+            scope: Scope::new(),
+            actions,
+            tick: 0,
+        })
+    }
+
+    // the rules a script adds aren't part of replays, so its scores can't be
+    // trusted
+    pub fn start_round(&mut self, game: &mut Game) {
+        game.ranked = false;
+        self.tick = game.replay.ticks;
+        self.actions.lock().unwrap().clear();
+    }
+
+    // runs a hook on the game as it is now, then applies what it asked for
+    fn hook(
+        &mut self,
+        hook: &str,
+        cmd: &mut Commands,
+        spawner: &Spawner,
+        config: &GameConfig,
+        game: &mut Game,
+    ) {
+        if !self.ast.iter_functions().any(|f| f.name == hook) {
+            return;
+        }
+
+        if let Err(e) =
+            self.engine
+                .call_fn::<Dynamic>(&mut self.scope, &self.ast, hook, (state(config, game),))
+        {
+            warn!("script {hook} failed: {e}");
+        }
+
+        let actions: Vec<_> = self.actions.lock().unwrap().drain(..).collect();
+
+        for action in actions {
+            match action {
+                Action::SpawnFood(x, y) => {
+                    if let Some(position) = free_cell(config, game, x, y) {
+                        game.pellets
+                            .push(spawner.new_food(cmd, position, FoodKind::Pellet));
+                    }
+                }
+                Action::SpawnWall(x, y) => {
+                    if let Some(position) = free_cell(config, game, x, y) {
+                        game.walls.push(spawner.new_wall(cmd, position));
+                    }
+                }
+                Action::SetSpeed(speed) if speed > 0.0 => {
+                    let interval = config.tick_interval / speed as f32;
+                    game.tick_timer
+                        .set_duration(Duration::from_secs_f32(interval));
+                }
+                Action::SetSpeed(speed) => warn!("script set an invalid speed {speed}"),
+            }
+        }
+    }
+}
+
+pub fn run(
+    mut cmd: Commands,
+    spawner: Res<Spawner>,
+    config: Res<GameConfig>,
+    mut game: ResMut<Game>,
+    mut script: ResMut<Script>,
+    mut eaten: EventReader<FoodEaten>,
+    mut died: EventReader<SnakeDied>,
+) {
+    // a frame runs at most one tick, so the hook sees the board it left
+    if script.tick != game.replay.ticks {
+        script.tick = game.replay.ticks;
+        script.hook(HOOKS[0], &mut cmd, &spawner, &config, &mut game);
+    }
+
+    for _ in eaten.read() {
+        script.hook(HOOKS[1], &mut cmd, &spawner, &config, &mut game);
+    }
+
+    if died.read().count() > 0 {
+        script.hook(HOOKS[2], &mut cmd, &spawner, &config, &mut game);
+    }
+}
+
+fn state(config: &GameConfig, game: &Game) -> Map {
+    let player = game.player();
+    let head = player.nodes.last().map(|n| n.position).unwrap_or_default();
+
+    let mut state = Map::new();
+    state.insert("tick".into(), i64::from(game.replay.ticks).into());
+    state.insert("score".into(), i64::from(game.score).into());
+    state.insert("length".into(), (player.nodes.len() as i64).into());
+    state.insert("head_x".into(), (head.x as i64).into());
+    state.insert("head_y".into(), (head.y as i64).into());
+    state.insert("width".into(), (config.width as i64).into());
+    state.insert("height".into(), (config.height as i64).into());
+    state
+}
+
+pub fn free_cell(config: &GameConfig, game: &Game, x: i64, y: i64) -> Option<Position> {
+    let x = usize::try_from(x).ok().filter(|&x| x < config.width)?;
+    let y = usize::try_from(y).ok().filter(|&y| y < config.height)?;
+    let position = Position::new(x, y);
+
+    (!game.is_occupied(position)).then_some(position)
+}
@@ -0,0 +1,170 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+    sprite::Mesh2dHandle,
+};
+
+use crate::{
+    topology::Topology, Game, GameConfig, Offset, Position, SnakeNode, Spawner, GRID_SCALE,
+};
+
+// the edge of a cell that isn't joined to anything, leaving the same gap
+// between cells as the board's own squares
+const EDGE: f32 = GRID_SCALE / 2.0 - 0.5;
+// how much of the outside of a bend is cut away
+const BEND: f32 = GRID_SCALE * 0.4;
+// half the width the tail narrows down to
+const TIP: f32 = GRID_SCALE * 0.15;
+
+// every piece is joined to the cell to its right. a bend is also joined to the
+// cell below it, and they're rotated into place from there
+pub fn straight_mesh() -> Mesh {
+    let half = GRID_SCALE / 2.0;
+
+    polygon(&[
+        Vec2::new(-half, -EDGE),
+        Vec2::new(half, -EDGE),
+        Vec2::new(half, EDGE),
+        Vec2::new(-half, EDGE),
+    ])
+}
+
+pub fn bend_mesh() -> Mesh {
+    let half = GRID_SCALE / 2.0;
+
+    polygon(&[
+        Vec2::new(-EDGE, -half),
+        Vec2::new(half, -half),
+        Vec2::new(half, EDGE),
+        Vec2::new(-EDGE + BEND, EDGE),
+        Vec2::new(-EDGE, EDGE - BEND),
+    ])
+}
+
+pub fn tail_mesh() -> Mesh {
+    let half = GRID_SCALE / 2.0;
+
+    polygon(&[
+        Vec2::new(-EDGE, -TIP),
+        Vec2::new(half, -EDGE),
+        Vec2::new(half, EDGE),
+        Vec2::new(-EDGE, TIP),
+    ])
+}
+
+// the corners have to go counter-clockwise around a convex shape, so a fan
+// from the first one covers it
+fn polygon(corners: &[Vec2]) -> Mesh {
+    let positions: Vec<[f32; 3]> = corners.iter().map(|c| [c.x, c.y, 0.0]).collect();
+    let normals = vec![[0.0, 0.0, 1.0]; corners.len()];
+    let uvs: Vec<[f32; 2]> = corners
+        .iter()
+        .map(|c| [c.x / GRID_SCALE + 0.5, 0.5 - c.y / GRID_SCALE])
+        .collect();
+    let indices = (1..corners.len() as u32 - 1)
+        .flat_map(|i| [0, i, i + 1])
+        .collect();
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+// each segment of a snake is drawn joined up with the ones before and after
+// it, so the snake reads as one body with bends and a tapering tail. the head,
+// and every cell on hex and pixelated boards, stays a plain cell
+pub fn shape(
+    config: Res<GameConfig>,
+    spawner: Res<Spawner>,
+    game: Res<Game>,
+    mut nodes: Query<(&mut Mesh2dHandle, &mut Transform)>,
+) {
+    let joined = config.topology == Topology::Square && !config.pixelated;
+
+    for snake in game.snakes.iter() {
+        for (index, node) in snake.nodes.iter().enumerate() {
+            let Ok((mut mesh, mut transform)) = nodes.get_mut(node.entity) else {
+                continue;
+            };
+
+            let (piece, angle) = if joined {
+                piece(&spawner, &config, &snake.nodes, index)
+            } else {
+                None
+            }
+            .unwrap_or((&spawner.mesh, 0.0));
+
+            if *mesh != *piece {
+                *mesh = piece.clone();
+            }
+
+            let rotation = Quat::from_rotation_z(angle);
+            if transform.rotation != rotation {
+                transform.rotation = rotation;
+            }
+        }
+    }
+}
+
+// the piece for a segment and how far it's turned, from the cells it's joined
+// to. nodes run from the tail to the head
+fn piece<'a>(
+    spawner: &'a Spawner,
+    config: &GameConfig,
+    nodes: &[SnakeNode],
+    index: usize,
+) -> Option<(&'a Mesh2dHandle, f32)> {
+    let position = nodes[index].position;
+    let towards = |other: usize| {
+        nodes
+            .get(other)
+            .and_then(|node| step(config, position, node.position))
+    };
+
+    let ahead = towards(index + 1)?;
+    let behind = index.checked_sub(1).and_then(towards);
+
+    match behind {
+        // the tail, or a segment the one behind it teleported away from
+        None => Some((&spawner.tail_mesh, angle(ahead))),
+        Some(behind) if behind == -ahead => Some((&spawner.straight_mesh, angle(ahead))),
+        // the bend joins right and down, so it's turned to whichever side has
+        // the other one a quarter turn clockwise from it
+        Some(behind) if behind == Offset::new(-ahead.y, ahead.x) => {
+            Some((&spawner.bend_mesh, angle(ahead)))
+        }
+        Some(behind) => Some((&spawner.bend_mesh, angle(behind))),
+    }
+}
+
+// the direction from one cell to the next, across the seam on boards that
+// wrap around
+fn step(config: &GameConfig, from: Position, to: Position) -> Option<Offset> {
+    let wrapped = |delta: isize, size: usize| {
+        if config.wrap && delta.abs() == size as isize - 1 {
+            -delta.signum()
+        } else {
+            delta
+        }
+    };
+
+    let offset = Offset::new(
+        wrapped(to.x as isize - from.x as isize, config.width),
+        wrapped(to.y as isize - from.y as isize, config.height),
+    );
+
+    Offset::DIRECTIONS.contains(&offset).then_some(offset)
+}
+
+// offsets count downwards, world space upwards
+fn angle(offset: Offset) -> f32 {
+    (-offset.y as f32).atan2(offset.x as f32)
+}
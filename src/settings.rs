@@ -0,0 +1,728 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    launch::Overrides,
+    locale::{Language, Localized},
+    mode::GameMode,
+    storage,
+    theme::{Palette, Theme},
+    AppState, GameConfig, Offset, PauseState,
+};
+
+const STORAGE_KEY: &str = "settings";
+
+pub const MIN_SPEED: f32 = 0.5;
+pub const MAX_SPEED: f32 = 3.0;
+const SPEED_STEP: f32 = 0.25;
+
+const SELECTED: Color = Color::BLACK;
+const UNSELECTED: Color = Color::GRAY;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoardSize {
+    // whatever the difficulty plays on
+    #[default]
+    Difficulty,
+    Small,
+    Medium,
+    Large,
+    Huge,
+}
+
+impl BoardSize {
+    pub const ALL: [BoardSize; 5] = [
+        BoardSize::Difficulty,
+        BoardSize::Small,
+        BoardSize::Medium,
+        BoardSize::Large,
+        BoardSize::Huge,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BoardSize::Difficulty => "by difficulty",
+            BoardSize::Small => "small (30x24)",
+            BoardSize::Medium => "medium (50x40)",
+            BoardSize::Large => "large (70x56)",
+            BoardSize::Huge => "huge (100x80)",
+        }
+    }
+
+    pub fn cells(self) -> Option<(usize, usize)> {
+        match self {
+            BoardSize::Difficulty => None,
+            BoardSize::Small => Some((30, 24)),
+            BoardSize::Medium => Some((50, 40)),
+            BoardSize::Large => Some((70, 56)),
+            BoardSize::Huge => Some((100, 80)),
+        }
+    }
+}
+
+// the colors to play in, unless high contrast is on
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeChoice {
+    // whatever the mode plays in
+    #[default]
+    Mode,
+    Classic,
+    Zen,
+    Nokia,
+}
+
+impl ThemeChoice {
+    pub const ALL: [ThemeChoice; 4] = [
+        ThemeChoice::Mode,
+        ThemeChoice::Classic,
+        ThemeChoice::Zen,
+        ThemeChoice::Nokia,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeChoice::Mode => "by mode",
+            ThemeChoice::Classic => "classic",
+            ThemeChoice::Zen => "zen",
+            ThemeChoice::Nokia => "nokia",
+        }
+    }
+
+    pub fn theme(self) -> Option<Theme> {
+        match self {
+            ThemeChoice::Mode => None,
+            ThemeChoice::Classic => Some(Theme::CLASSIC),
+            ThemeChoice::Zen => Some(Theme::ZEN),
+            ThemeChoice::Nokia => Some(Theme::NOKIA),
+        }
+    }
+}
+
+// the ways to steer, in the order each player's keys are listed in
+pub const DIRECTIONS: [(Offset, &str); 4] = [
+    (Offset::new(0, -1), "up"),
+    (Offset::new(0, 1), "down"),
+    (Offset::new(1, 0), "right"),
+    (Offset::new(-1, 0), "left"),
+];
+
+const ARROWS: [KeyCode; 4] = [
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowRight,
+    KeyCode::ArrowLeft,
+];
+
+const WASD: [KeyCode; 4] = [KeyCode::KeyW, KeyCode::KeyS, KeyCode::KeyD, KeyCode::KeyA];
+
+const VIM: [KeyCode; 4] = [KeyCode::KeyK, KeyCode::KeyJ, KeyCode::KeyL, KeyCode::KeyH];
+
+// the keys player one steers with. player two gets whichever of the arrows
+// and wasd are left
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyLayout {
+    #[default]
+    Arrows,
+    Wasd,
+    Vim,
+}
+
+impl KeyLayout {
+    pub const ALL: [KeyLayout; 3] = [KeyLayout::Arrows, KeyLayout::Wasd, KeyLayout::Vim];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyLayout::Arrows => "arrows",
+            KeyLayout::Wasd => "wasd",
+            KeyLayout::Vim => "hjkl",
+        }
+    }
+
+    pub fn bindings(self) -> KeyBindings {
+        match self {
+            KeyLayout::Arrows => KeyBindings([ARROWS, WASD]),
+            KeyLayout::Wasd => KeyBindings([WASD, ARROWS]),
+            KeyLayout::Vim => KeyBindings([VIM, WASD]),
+        }
+    }
+}
+
+// the key each player steers in each of `DIRECTIONS` with. starts out as one
+// of the layouts, and any key can be rebound from there
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBindings(pub [[KeyCode; 4]; 2]);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyLayout::default().bindings()
+    }
+}
+
+impl KeyBindings {
+    // per player
+    pub fn controls(self) -> [[(KeyCode, Offset); 4]; 2] {
+        self.0
+            .map(|keys| std::array::from_fn(|i| (keys[i], DIRECTIONS[i].0)))
+    }
+
+    // the layout these still match, if no key has been rebound away from it
+    pub fn layout(self) -> Option<KeyLayout> {
+        KeyLayout::ALL
+            .into_iter()
+            .find(|layout| layout.bindings() == self)
+    }
+
+    // the player and direction a key is bound to
+    pub fn find(self, key: KeyCode) -> Option<(usize, usize)> {
+        self.0.iter().enumerate().find_map(|(player, keys)| {
+            keys.iter()
+                .position(|&k| k == key)
+                .map(|direction| (player, direction))
+        })
+    }
+}
+
+// how far past level the device has to be tilted before it steers
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TiltSensitivity {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+// only the web build has tilt steering to set
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+impl TiltSensitivity {
+    pub const ALL: [TiltSensitivity; 3] = [
+        TiltSensitivity::Low,
+        TiltSensitivity::Medium,
+        TiltSensitivity::High,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TiltSensitivity::Low => "low",
+            TiltSensitivity::Medium => "medium",
+            TiltSensitivity::High => "high",
+        }
+    }
+
+    pub fn threshold(self) -> f32 {
+        match self {
+            TiltSensitivity::Low => 25.0,
+            TiltSensitivity::Medium => 15.0,
+            TiltSensitivity::High => 8.0,
+        }
+    }
+}
+
+// how hard a connected gamepad rumbles when food is eaten or a snake dies
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RumbleStrength {
+    Off,
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl RumbleStrength {
+    pub const ALL: [RumbleStrength; 4] = [
+        RumbleStrength::Off,
+        RumbleStrength::Low,
+        RumbleStrength::Medium,
+        RumbleStrength::High,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RumbleStrength::Off => "off",
+            RumbleStrength::Low => "low",
+            RumbleStrength::Medium => "medium",
+            RumbleStrength::High => "high",
+        }
+    }
+
+    // the motor strength a death rumbles at, food gets half of it
+    pub fn scale(self) -> f32 {
+        match self {
+            RumbleStrength::Off => 0.0,
+            RumbleStrength::Low => 0.3,
+            RumbleStrength::Medium => 0.6,
+            RumbleStrength::High => 1.0,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub palette: Palette,
+    pub shape_coded_food: bool,
+    pub high_contrast: bool,
+    pub reduced_motion: bool,
+    pub large_cells: bool,
+    pub near_miss_slowdown: bool,
+    // the last few inputs and the queued turns, shown in a corner for
+    // viewers
+    pub input_display: bool,
+    pub speed: f32,
+    pub board_size: BoardSize,
+    pub theme: ThemeChoice,
+    pub bindings: KeyBindings,
+    // turning towards the cell under the mouse cursor, for trackpads
+    pub mouse_steering: bool,
+    pub rumble: RumbleStrength,
+    // redrawing only as often as the game needs instead of as fast as
+    // possible, to save battery
+    pub low_power: bool,
+    // cells the snake moves off fade out instead of clearing at once
+    pub motion_trail: bool,
+    // the path each ai snake is following, drawn faintly on the board
+    pub show_ai_plans: bool,
+    // cells tinted by how often the player's head has been on them this round
+    pub heatmap: bool,
+    // consent to send the score and length of each round, nothing that
+    // identifies the player, see `telemetry`
+    #[cfg(feature = "telemetry")]
+    pub telemetry: bool,
+    // where those go instead of the server the build was made with
+    #[cfg(feature = "telemetry")]
+    pub telemetry_endpoint: Option<String>,
+    // steering by tilting the device, in the web build
+    pub tilt: bool,
+    pub tilt_sensitivity: TiltSensitivity,
+    // the front-to-back and left-to-right tilt that counts as level
+    pub tilt_level: (f32, f32),
+    // the mode the game starts in, unless another is given on the command line
+    pub last_mode: GameMode,
+    // the name other players see in online matches
+    pub nickname: String,
+    // what the menus and hud are shown in, picked from the system or browser
+    // until it's changed here
+    pub language: Language,
+    // set once the tutorial has been finished or skipped, so it only opens
+    // on its own the first time
+    pub tutorial_done: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            palette: default(),
+            shape_coded_food: false,
+            high_contrast: false,
+            reduced_motion: false,
+            large_cells: false,
+            near_miss_slowdown: false,
+            input_display: false,
+            speed: 1.0,
+            board_size: default(),
+            theme: default(),
+            bindings: default(),
+            mouse_steering: false,
+            rumble: default(),
+            // laptops running the web build are the most likely to be on
+            // battery
+            low_power: cfg!(target_arch = "wasm32"),
+            motion_trail: false,
+            show_ai_plans: false,
+            heatmap: false,
+            #[cfg(feature = "telemetry")]
+            telemetry: false,
+            #[cfg(feature = "telemetry")]
+            telemetry_endpoint: None,
+            tilt: false,
+            tilt_sensitivity: default(),
+            tilt_level: (0.0, 0.0),
+            last_mode: default(),
+            nickname: String::new(),
+            language: Language::detect(),
+            tutorial_done: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        Self::read().unwrap_or_default()
+    }
+
+    // `None` when the file is missing or doesn't parse, rather than the
+    // defaults
+    pub fn read() -> Option<Self> {
+        storage::load(STORAGE_KEY)
+    }
+
+    pub fn save(&self) {
+        storage::save(STORAGE_KEY, self);
+    }
+
+    pub fn step_speed(&mut self, delta: isize) {
+        self.speed = (self.speed + SPEED_STEP * delta as f32).clamp(MIN_SPEED, MAX_SPEED);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Theme,
+    Palette,
+    ShapeCodedFood,
+    HighContrast,
+    ReducedMotion,
+    LargeCells,
+    NearMissSlowdown,
+    InputDisplay,
+    MotionTrail,
+    AiPlans,
+    Heatmap,
+    Speed,
+    BoardSize,
+    Keys,
+    Rebind,
+    MouseSteering,
+    Rumble,
+    LowPower,
+    #[cfg(feature = "telemetry")]
+    Telemetry,
+    Language,
+    #[cfg(target_arch = "wasm32")]
+    Tilt,
+    #[cfg(target_arch = "wasm32")]
+    TiltSensitivity,
+    #[cfg(target_arch = "wasm32")]
+    CalibrateTilt,
+}
+
+impl Entry {
+    const ALL: &[Entry] = &[
+        Entry::Theme,
+        Entry::Palette,
+        Entry::ShapeCodedFood,
+        Entry::HighContrast,
+        Entry::ReducedMotion,
+        Entry::LargeCells,
+        Entry::NearMissSlowdown,
+        Entry::InputDisplay,
+        Entry::MotionTrail,
+        Entry::AiPlans,
+        Entry::Heatmap,
+        Entry::Speed,
+        Entry::BoardSize,
+        Entry::Keys,
+        Entry::Rebind,
+        Entry::MouseSteering,
+        Entry::Rumble,
+        Entry::LowPower,
+        #[cfg(feature = "telemetry")]
+        Entry::Telemetry,
+        Entry::Language,
+        // only the web build reads the device's tilt
+        #[cfg(target_arch = "wasm32")]
+        Entry::Tilt,
+        #[cfg(target_arch = "wasm32")]
+        Entry::TiltSensitivity,
+        #[cfg(target_arch = "wasm32")]
+        Entry::CalibrateTilt,
+    ];
+
+    fn label(self, settings: &Settings) -> String {
+        let language = settings.language;
+        let switch = |value| language.text(on_off(value));
+        let label = |english, value: &str| language.format(english, &[&value]);
+
+        match self {
+            Entry::Theme => label("theme: {}", language.text(settings.theme.name())),
+            Entry::Palette => label("palette: {}", language.text(settings.palette.name())),
+            Entry::ShapeCodedFood => {
+                label("shape-coded food: {}", switch(settings.shape_coded_food))
+            }
+            Entry::HighContrast => label("high contrast: {}", switch(settings.high_contrast)),
+            Entry::ReducedMotion => label("reduced motion: {}", switch(settings.reduced_motion)),
+            Entry::LargeCells => label("large cells: {}", switch(settings.large_cells)),
+            Entry::NearMissSlowdown => label(
+                "near-miss slowdown: {}",
+                switch(settings.near_miss_slowdown),
+            ),
+            Entry::InputDisplay => label("input display: {}", switch(settings.input_display)),
+            Entry::MotionTrail => label("motion trail: {}", switch(settings.motion_trail)),
+            Entry::AiPlans => label("ai paths: {}", switch(settings.show_ai_plans)),
+            Entry::Heatmap => label("visit heatmap: {}", switch(settings.heatmap)),
+            Entry::Speed => label("speed: {}", &speed_label(settings.speed)),
+            Entry::BoardSize => label("board size: {}", language.text(settings.board_size.name())),
+            Entry::Keys => label(
+                "keys: {}",
+                language.text(settings.bindings.layout().map_or("custom", KeyLayout::name)),
+            ),
+            Entry::Rebind => language.text("rebind keys").to_owned(),
+            Entry::MouseSteering => label("mouse steering: {}", switch(settings.mouse_steering)),
+            Entry::Rumble => label("rumble: {}", language.text(settings.rumble.name())),
+            Entry::LowPower => label("low power mode: {}", switch(settings.low_power)),
+            #[cfg(feature = "telemetry")]
+            Entry::Telemetry => label("share anonymous stats: {}", switch(settings.telemetry)),
+            Entry::Language => label("language: {}", language.name()),
+            #[cfg(target_arch = "wasm32")]
+            Entry::Tilt => label("tilt steering: {}", switch(settings.tilt)),
+            #[cfg(target_arch = "wasm32")]
+            Entry::TiltSensitivity => label(
+                "tilt sensitivity: {}",
+                language.text(settings.tilt_sensitivity.name()),
+            ),
+            #[cfg(target_arch = "wasm32")]
+            Entry::CalibrateTilt => match crate::tilt::reading() {
+                Some(_) => language
+                    .text("calibrate tilt: hold the device level and press enter")
+                    .to_owned(),
+                None => language.text("calibrate tilt: no tilt sensor").to_owned(),
+            },
+        }
+    }
+
+    fn change(self, settings: &mut Settings, delta: isize) {
+        match self {
+            Entry::Theme => settings.theme = cycle(&ThemeChoice::ALL, settings.theme, delta),
+            Entry::Palette => settings.palette = cycle(&Palette::ALL, settings.palette, delta),
+            Entry::ShapeCodedFood => settings.shape_coded_food = !settings.shape_coded_food,
+            Entry::HighContrast => settings.high_contrast = !settings.high_contrast,
+            Entry::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            Entry::LargeCells => settings.large_cells = !settings.large_cells,
+            Entry::NearMissSlowdown => settings.near_miss_slowdown = !settings.near_miss_slowdown,
+            Entry::InputDisplay => settings.input_display = !settings.input_display,
+            Entry::MotionTrail => settings.motion_trail = !settings.motion_trail,
+            Entry::AiPlans => settings.show_ai_plans = !settings.show_ai_plans,
+            Entry::Heatmap => settings.heatmap = !settings.heatmap,
+            Entry::Speed => settings.step_speed(delta),
+            Entry::BoardSize => {
+                settings.board_size = cycle(&BoardSize::ALL, settings.board_size, delta)
+            }
+            Entry::Keys => {
+                let layout = settings.bindings.layout().unwrap_or_default();
+                settings.bindings = cycle(&KeyLayout::ALL, layout, delta).bindings();
+            }
+            // opens its own screen instead, see `navigate`
+            Entry::Rebind => {}
+            Entry::MouseSteering => settings.mouse_steering = !settings.mouse_steering,
+            Entry::Rumble => settings.rumble = cycle(&RumbleStrength::ALL, settings.rumble, delta),
+            Entry::LowPower => settings.low_power = !settings.low_power,
+            #[cfg(feature = "telemetry")]
+            Entry::Telemetry => settings.telemetry = !settings.telemetry,
+            Entry::Language => settings.language = cycle(&Language::ALL, settings.language, delta),
+            #[cfg(target_arch = "wasm32")]
+            Entry::Tilt => {
+                settings.tilt = !settings.tilt;
+
+                if settings.tilt {
+                    crate::tilt::request_permission();
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            Entry::TiltSensitivity => {
+                settings.tilt_sensitivity =
+                    cycle(&TiltSensitivity::ALL, settings.tilt_sensitivity, delta)
+            }
+            #[cfg(target_arch = "wasm32")]
+            Entry::CalibrateTilt => {
+                if let Some(level) = crate::tilt::reading() {
+                    settings.tilt_level = level;
+                }
+            }
+        }
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+pub fn speed_label(speed: f32) -> String {
+    let slider = (0..=((MAX_SPEED - MIN_SPEED) / SPEED_STEP) as usize)
+        .map(|i| {
+            if MIN_SPEED + i as f32 * SPEED_STEP <= speed {
+                '='
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+
+    format!("[{}] {}x", slider, speed)
+}
+
+fn cycle<T: Copy + PartialEq>(all: &[T], current: T, delta: isize) -> T {
+    let index = all.iter().position(|&v| v == current).unwrap_or(0) as isize;
+
+    all[(index + delta).rem_euclid(all.len() as isize) as usize]
+}
+
+// kept across trips to the key bindings so the cursor comes back where it was
+#[derive(Resource, Default)]
+pub struct SettingsMenu {
+    selected: usize,
+}
+
+#[derive(Component)]
+pub struct SettingsRoot;
+
+#[derive(Component)]
+pub struct SettingsEntry(usize);
+
+pub fn setup(mut cmd: Commands) {
+    cmd.init_resource::<SettingsMenu>();
+
+    cmd.spawn((
+        SettingsRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            Localized("settings"),
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ),
+        ));
+
+        for index in 0..Entry::ALL.len() {
+            parent.spawn((
+                SettingsEntry(index),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: UNSELECTED,
+                        ..default()
+                    },
+                ),
+            ));
+        }
+
+        parent.spawn((
+            Localized("up/down to choose, left/right to change, escape to go back"),
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: UNSELECTED,
+                    ..default()
+                },
+            ),
+        ));
+    });
+}
+
+pub fn cleanup(mut cmd: Commands, roots: Query<Entity, With<SettingsRoot>>) {
+    for entity in roots.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn navigate(
+    input: Res<ButtonInput<KeyCode>>,
+    mut menu: ResMut<SettingsMenu>,
+    mut settings: ResMut<Settings>,
+    mut config: ResMut<GameConfig>,
+    overrides: Option<Res<Overrides>>,
+    pause: Res<State<PauseState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // opened from the pause menu, with a round still on the board
+    let paused = *pause.get() == PauseState::Settings;
+
+    if input.just_pressed(KeyCode::ArrowUp) {
+        menu.selected = menu.selected.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        menu.selected = (menu.selected + 1).min(Entry::ALL.len() - 1);
+    }
+
+    let entry = Entry::ALL[menu.selected];
+
+    if entry == Entry::Rebind {
+        if input.just_pressed(KeyCode::Enter) && paused {
+            next_pause.set(PauseState::Bindings);
+        } else if input.just_pressed(KeyCode::Enter) {
+            next_state.set(AppState::Bindings);
+        }
+    } else if input.any_just_pressed([KeyCode::ArrowLeft, KeyCode::ArrowRight, KeyCode::Enter]) {
+        let delta = if input.just_pressed(KeyCode::ArrowLeft) {
+            -1
+        } else {
+            1
+        };
+
+        entry.change(&mut settings, delta);
+        settings.save();
+
+        // the round being played keeps its board, a new size takes over
+        // from the next one started in the menu
+        if entry == Entry::BoardSize && !paused {
+            resize(&mut config, &settings, overrides.as_deref());
+        }
+    }
+
+    if input.just_pressed(KeyCode::Escape) && paused {
+        next_pause.set(PauseState::Paused);
+    } else if input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+// the size the menu would build the config with, so the next round is played
+// at the new size without leaving the menu
+fn resize(config: &mut GameConfig, settings: &Settings, overrides: Option<&Overrides>) {
+    let mut sized = GameConfig::sized(config.difficulty, config.mode, settings.board_size.cells());
+
+    if let Some(overrides) = overrides {
+        overrides.apply(&mut sized);
+        crate::fit_start_room(&mut sized);
+    }
+
+    config.width = sized.width;
+    config.height = sized.height;
+}
+
+pub fn refresh(
+    menu: Res<SettingsMenu>,
+    settings: Res<Settings>,
+    mut entries: Query<(&SettingsEntry, &mut Text)>,
+) {
+    for (entry, mut text) in entries.iter_mut() {
+        let value = Entry::ALL[entry.0].label(&settings);
+
+        let color = if entry.0 == menu.selected {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// moving codes in and out of the game as text: the clipboard on native, and
+// prompts and page links on the web
+
+// reduce a pasted link to the value of its `param` query parameter, leaving
+// bare codes untouched
+fn code(text: &str, param: &str) -> String {
+    let prefix = format!("{param}=");
+
+    text.rsplit(prefix.as_str())
+        .next()
+        .unwrap_or(text)
+        .split('&')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_owned()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export(_param: &str, code: String) -> bool {
+    match arboard::Clipboard::new().and_then(|mut c| c.set_text(code)) {
+        Ok(()) => true,
+        Err(e) => {
+            bevy::log::warn!("failed to copy to the clipboard: {e}");
+            false
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn import(param: &str) -> Option<String> {
+    match arboard::Clipboard::new().and_then(|mut c| c.get_text()) {
+        Ok(text) => Some(code(&text, param)),
+        Err(e) => {
+            bevy::log::warn!("failed to read the clipboard: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn link_param(_param: &str) -> Option<String> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn export(param: &str, code: String) -> bool {
+    let Some(window) = web_sys::window() else {
+        return false;
+    };
+
+    let location = window.location();
+    let link = match (location.origin(), location.pathname()) {
+        (Ok(origin), Ok(path)) => format!("{origin}{path}?{param}={code}"),
+        _ => code,
+    };
+
+    window
+        .prompt_with_message_and_default(&format!("copy this link to share the {param}"), &link)
+        .is_ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn import(param: &str) -> Option<String> {
+    let text = web_sys::window()?
+        .prompt_with_message(&format!("paste a {param} link"))
+        .ok()??;
+
+    Some(code(&text, param))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn link_param(param: &str) -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+
+    search
+        .contains(&format!("{param}="))
+        .then(|| code(&search, param))
+}
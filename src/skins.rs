@@ -0,0 +1,365 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::ImageSampler,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hud::Toasts, settings::Settings, storage, theme, AppState, GameConfig, RoundOver, Spawner,
+};
+
+const STORAGE_KEY: &str = "skins";
+
+const RAINBOW_STEPS: usize = 12;
+const PIXEL_SIZE: u32 = 8;
+
+const SELECTED: Color = Color::BLACK;
+const UNSELECTED: Color = Color::GRAY;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Skin {
+    #[default]
+    Classic,
+    Striped,
+    Rainbow,
+    Pixel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unlock {
+    Always,
+    Score(u32),
+    Length(usize),
+}
+
+impl Skin {
+    pub const ALL: [Skin; 4] = [Skin::Classic, Skin::Striped, Skin::Rainbow, Skin::Pixel];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Skin::Classic => "classic",
+            Skin::Striped => "striped",
+            Skin::Rainbow => "rainbow",
+            Skin::Pixel => "pixel",
+        }
+    }
+
+    fn unlock(self) -> Unlock {
+        match self {
+            Skin::Classic => Unlock::Always,
+            Skin::Striped => Unlock::Score(200),
+            Skin::Rainbow => Unlock::Length(50),
+            Skin::Pixel => Unlock::Score(1000),
+        }
+    }
+
+    fn requirement(self) -> String {
+        match self.unlock() {
+            Unlock::Always => String::new(),
+            Unlock::Score(score) => format!("score {score}"),
+            Unlock::Length(length) => format!("length {length}"),
+        }
+    }
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkinRecord {
+    unlocked: Vec<Skin>,
+    pub selected: Skin,
+}
+
+impl SkinRecord {
+    pub fn load() -> Self {
+        storage::load(STORAGE_KEY).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        storage::save(STORAGE_KEY, self);
+    }
+
+    pub fn is_unlocked(&self, skin: Skin) -> bool {
+        skin.unlock() == Unlock::Always || self.unlocked.contains(&skin)
+    }
+}
+
+pub fn unlock(
+    mut events: EventReader<RoundOver>,
+    mut record: ResMut<SkinRecord>,
+    mut toast: Toasts,
+) {
+    for event in events.read() {
+        for skin in Skin::ALL {
+            let earned = match skin.unlock() {
+                Unlock::Always => false,
+                Unlock::Score(score) => event.score >= score,
+                Unlock::Length(length) => event.length >= length,
+            };
+
+            if earned && !record.is_unlocked(skin) {
+                record.unlocked.push(skin);
+                record.save();
+                toast.show(format!("unlocked the {} skin", skin.name()));
+            }
+        }
+    }
+}
+
+// rebuild the player's per-segment materials; an empty list means the plain
+// snake material
+pub fn apply(
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    record: Res<SkinRecord>,
+    mut spawner: ResMut<Spawner>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut applied: Local<Option<Skin>>,
+) {
+    let color = theme::current(&config, &settings).snake;
+    let stripes = [color, lighten(color, 0.45)];
+
+    let skin = match record.selected {
+        skin if record.is_unlocked(skin) => skin,
+        _ => Skin::Classic,
+    };
+
+    // this runs on every change to the config, so a skin that's already on
+    // only gets recolored in place rather than adding more assets
+    if *applied == Some(skin) {
+        match skin {
+            Skin::Striped => {
+                for (handle, color) in spawner.skin_materials.iter().zip(stripes) {
+                    if let Some(material) = materials.get_mut(handle) {
+                        material.color = color;
+                    }
+                }
+            }
+            Skin::Pixel => {
+                let texture = spawner
+                    .skin_materials
+                    .first()
+                    .and_then(|h| materials.get(h))
+                    .and_then(|m| m.texture.as_ref());
+
+                if let Some(texture) = texture {
+                    images.insert(texture.id(), pixel_texture(color));
+                }
+            }
+            Skin::Classic | Skin::Rainbow => {}
+        }
+
+        return;
+    }
+
+    *applied = Some(skin);
+
+    // the old handles are dropped here, and their assets go once the last
+    // node drawn with them is gone
+    spawner.skin_materials = match skin {
+        Skin::Classic => vec![],
+        Skin::Striped => stripes.map(|c| materials.add(c)).to_vec(),
+        Skin::Rainbow => (0..RAINBOW_STEPS)
+            .map(|i| {
+                let hue = i as f32 * 360.0 / RAINBOW_STEPS as f32;
+                materials.add(Color::hsl(hue, 0.8, 0.5))
+            })
+            .collect(),
+        Skin::Pixel => vec![materials.add(ColorMaterial {
+            color: Color::WHITE,
+            texture: Some(images.add(pixel_texture(color))),
+        })],
+    };
+}
+
+fn lighten(color: Color, amount: f32) -> Color {
+    let [r, g, b, a] = color.as_rgba_f32();
+
+    Color::rgba(
+        r + (1.0 - r) * amount,
+        g + (1.0 - g) * amount,
+        b + (1.0 - b) * amount,
+        a,
+    )
+}
+
+// a small bevelled tile: light top-left edge, dark bottom-right edge
+fn pixel_texture(color: Color) -> Image {
+    let light = lighten(color, 0.5).as_rgba_u8();
+    let base = lighten(color, 0.2).as_rgba_u8();
+    let dark = color.as_rgba_u8();
+
+    let mut data = Vec::with_capacity((PIXEL_SIZE * PIXEL_SIZE * 4) as usize);
+
+    for y in 0..PIXEL_SIZE {
+        for x in 0..PIXEL_SIZE {
+            let pixel = if x == 0 || y == 0 {
+                light
+            } else if x == PIXEL_SIZE - 1 || y == PIXEL_SIZE - 1 {
+                dark
+            } else {
+                base
+            };
+
+            data.extend_from_slice(&pixel);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: PIXEL_SIZE,
+            height: PIXEL_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+
+    image.sampler = ImageSampler::nearest();
+
+    image
+}
+
+#[derive(Resource, Default)]
+pub struct SkinMenu {
+    selected: usize,
+}
+
+#[derive(Component)]
+pub struct SkinRoot;
+
+#[derive(Component)]
+pub struct SkinEntry(usize);
+
+pub fn setup(mut cmd: Commands, record: Res<SkinRecord>) {
+    let selected = Skin::ALL
+        .iter()
+        .position(|&s| s == record.selected)
+        .unwrap_or(0);
+
+    cmd.insert_resource(SkinMenu { selected });
+
+    cmd.spawn((
+        SkinRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "skins",
+            TextStyle {
+                font_size: 40.0,
+                color: Color::BLACK,
+                ..default()
+            },
+        ));
+
+        for index in 0..Skin::ALL.len() {
+            parent.spawn((
+                SkinEntry(index),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: UNSELECTED,
+                        ..default()
+                    },
+                ),
+            ));
+        }
+
+        parent.spawn(TextBundle::from_section(
+            "up/down to choose, enter to wear, escape to go back",
+            TextStyle {
+                font_size: 16.0,
+                color: UNSELECTED,
+                ..default()
+            },
+        ));
+    });
+}
+
+pub fn cleanup(mut cmd: Commands, roots: Query<Entity, With<SkinRoot>>) {
+    for entity in roots.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    cmd.remove_resource::<SkinMenu>();
+}
+
+pub fn navigate(
+    input: Res<ButtonInput<KeyCode>>,
+    mut menu: ResMut<SkinMenu>,
+    mut record: ResMut<SkinRecord>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(KeyCode::ArrowUp) {
+        menu.selected = menu.selected.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        menu.selected = (menu.selected + 1).min(Skin::ALL.len() - 1);
+    }
+
+    let skin = Skin::ALL[menu.selected];
+
+    if input.any_just_pressed([KeyCode::Enter, KeyCode::Space])
+        && record.is_unlocked(skin)
+        && record.selected != skin
+    {
+        record.selected = skin;
+        record.save();
+    }
+
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+pub fn refresh(
+    menu: Res<SkinMenu>,
+    record: Res<SkinRecord>,
+    mut entries: Query<(&SkinEntry, &mut Text)>,
+) {
+    for (entry, mut text) in entries.iter_mut() {
+        let skin = Skin::ALL[entry.0];
+
+        let value = if !record.is_unlocked(skin) {
+            format!("{} (locked: {})", skin.name(), skin.requirement())
+        } else if skin == record.selected {
+            format!("{} (wearing)", skin.name())
+        } else {
+            skin.name().to_owned()
+        };
+
+        let color = if entry.0 == menu.selected {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+// kept as readable ron in the config directory so they can be edited by
+// hand, while everything else is data
+const CONFIG_KEYS: [&str; 1] = ["settings"];
+
+#[cfg(target_arch = "wasm32")]
+const MAX_LOG_LINES: usize = 1000;
+
+// set by the test harness, so test rounds neither see nor touch the player's
+// own scores, settings and logs
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+fn disabled() -> bool {
+    DISABLED.load(Ordering::Relaxed)
+}
+
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    if disabled() {
+        return None;
+    }
+
+    let text = read(key)?;
+
+    ron::from_str(&text).ok()
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    if disabled() {
+        return;
+    }
+
+    let text = if CONFIG_KEYS.contains(&key) {
+        ron::ser::to_string_pretty(value, Default::default())
+    } else {
+        ron::to_string(value)
+    };
+
+    match text {
+        Ok(text) => write(key, &text),
+        Err(e) => bevy::log::warn!("failed to serialize {key}: {e}"),
+    }
+}
+
+pub fn remove(key: &str) {
+    if !disabled() {
+        delete(key);
+    }
+}
+
+// logs are only ever added to, one line at a time, and kept as they're
+// written rather than as ron
+pub fn append_log(key: &str, line: &str) {
+    if !disabled() {
+        append(key, line);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn path(key: &str) -> Option<std::path::PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "mfro", "snake")?;
+
+    let dir = if CONFIG_KEYS.contains(&key) {
+        dirs.config_dir()
+    } else {
+        dirs.data_dir()
+    };
+
+    Some(dir.join(format!("{key}.ron")))
+}
+
+// where everything was kept before config moved to its own directory
+#[cfg(not(target_arch = "wasm32"))]
+fn data_path(key: &str) -> Option<std::path::PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "mfro", "snake")?;
+
+    Some(dirs.data_dir().join(format!("{key}.ron")))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)?)
+        .ok()
+        .or_else(|| std::fs::read_to_string(data_path(key)?).ok())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, text: &str) {
+    let Some(path) = path(key) else {
+        return;
+    };
+
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    if let Err(e) = std::fs::write(&path, text) {
+        bevy::log::warn!("failed to save {}: {e}", path.display());
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn delete(key: &str) {
+    if let Some(path) = path(key) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn log_path(key: &str) -> Option<std::path::PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "mfro", "snake")?;
+
+    Some(dirs.data_dir().join(format!("{key}.jsonl")))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn append(key: &str, line: &str) {
+    use std::io::Write;
+
+    let Some(path) = log_path(key) else {
+        return;
+    };
+
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{line}"));
+
+    if let Err(e) = result {
+        bevy::log::warn!("failed to append to {}: {e}", path.display());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(&format!("snake.{key}")).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, text: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(&format!("snake.{key}"), text);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn delete(key: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(&format!("snake.{key}"));
+    }
+}
+
+// local storage only holds a few megabytes for the whole game, so the web
+// build keeps just the newest lines of a log
+#[cfg(target_arch = "wasm32")]
+fn append(key: &str, line: &str) {
+    let key = format!("{key}.log");
+    let text = read(&key).unwrap_or_default();
+
+    let lines: Vec<&str> = text.lines().chain([line]).collect();
+    let start = lines.len().saturating_sub(MAX_LOG_LINES);
+    let mut text = lines[start..].join("\n");
+    text.push('\n');
+
+    write(&key, &text);
+}
+
+// native logs are files the player can open themselves
+#[cfg(target_arch = "wasm32")]
+pub fn read_log(key: &str) -> Option<String> {
+    read(&format!("{key}.log"))
+}
@@ -0,0 +1,105 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    replay::Replay,
+    share,
+    verify::{self, FinalState, VerifyError},
+};
+
+// baked in at build time; builds without a key can't sign scores. anyone with
+// a signed build can pull the key back out of the binary, so a signature only
+// keeps out casual tampering: the server has to re-run every replay with
+// `check` before it trusts a score
+const KEY: Option<&str> = option_env!("SNAKE_LEADERBOARD_KEY");
+
+// a score for the leaderboard with the replay that earned it. the replay is
+// kept as the ron it was signed as, so the signature is checked against the
+// bytes that came in rather than a re-serialization of whatever they decode to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Submission {
+    replay: String,
+    pub score: u32,
+    signature: String,
+}
+
+impl Submission {
+    pub fn new(replay: &Replay, score: u32) -> Option<Self> {
+        let replay = ron::to_string(replay).ok()?;
+        let signature = mac(KEY?.as_bytes(), &replay, score).finalize();
+        let signature = URL_SAFE_NO_PAD.encode(signature.into_bytes());
+
+        Some(Self {
+            replay,
+            score,
+            signature,
+        })
+    }
+
+    // checked before anything looks inside the replay
+    pub fn verify(&self, key: &[u8]) -> bool {
+        let Ok(signature) = URL_SAFE_NO_PAD.decode(&self.signature) else {
+            return false;
+        };
+
+        mac(key, &self.replay, self.score)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+
+    pub fn replay(&self) -> Option<Replay> {
+        ron::from_str(&self.replay).ok()
+    }
+
+    // re-runs the replay and checks it comes to the score it claims
+    pub fn check(&self) -> Result<FinalState, VerifyError> {
+        let replay = self
+            .replay()
+            .ok_or_else(|| VerifyError::InvalidSetup("the replay can't be read".to_owned()))?;
+        let state = verify::verify_replay(&replay)?;
+
+        if state.score != self.score {
+            return Err(VerifyError::ScoreMismatch {
+                claimed: self.score,
+                actual: state.score,
+            });
+        }
+
+        Ok(state)
+    }
+
+    pub fn encode(&self) -> Option<String> {
+        let text = ron::to_string(self).ok()?;
+
+        Some(URL_SAFE_NO_PAD.encode(text))
+    }
+
+    pub fn decode(code: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(code).ok()?;
+
+        ron::from_str(std::str::from_utf8(&bytes).ok()?).ok()
+    }
+}
+
+fn mac(key: &[u8], replay: &str, score: u32) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac takes keys of any length");
+
+    mac.update(replay.as_bytes());
+    mac.update(&score.to_le_bytes());
+    mac
+}
+
+// there's no leaderboard client yet, so submissions go out the same way as
+// replays for now
+pub fn export(replay: &Replay, score: u32) -> Result<(), &'static str> {
+    let submission = Submission::new(replay, score).ok_or("this build can't sign scores")?;
+    let code = submission.encode().ok_or("failed to encode the score")?;
+
+    if share::export("score", code) {
+        Ok(())
+    } else {
+        Err("failed to export the score")
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::{
+    hud::Toasts,
+    replay::{self, Replay},
+    savegame::SavedGame,
+    AppState, Game, GameConfig, Offset, Spawner,
+};
+
+const NAMES: [(&str, Offset); 4] = [
+    ("up", Offset::new(0, -1)),
+    ("down", Offset::new(0, 1)),
+    ("left", Offset::new(-1, 0)),
+    ("right", Offset::new(1, 0)),
+];
+
+// a tool-assisted run, loaded with --tas and played like a replay against the
+// launch config and seed. the script has a turn per line
+//
+//     # tick direction [player]
+//     12 up
+//     30 left 1
+//
+// where the direction is up, down, left or right, or on boards with other
+// directions an index into them. while it plays, p pauses, . advances one
+// tick, f5 saves a state and f9 goes back to it
+#[derive(Resource)]
+pub struct Tas {
+    replay: Replay,
+    // a step was asked for, so pause again once it has ticked
+    stepping: bool,
+    savestate: Option<SavedGame>,
+}
+
+impl Tas {
+    pub fn load(path: &Path, config: &GameConfig) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+
+        let seed = config
+            .seed
+            .ok_or("a tas script needs a --seed to play against")?;
+
+        let mut replay = Replay::new(config, seed);
+        // the run goes on until it ends by itself
+        replay.ticks = u32::MAX;
+
+        let directions = config.topology.get().directions();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| format!("{}:{}: {message}", path.display(), number + 1);
+
+            let mut words = line.split_whitespace();
+
+            let tick: u32 = words
+                .next()
+                .and_then(|w| w.parse().ok())
+                .ok_or_else(|| error("expected a tick"))?;
+
+            let word = words.next().ok_or_else(|| error("expected a direction"))?;
+            let direction = match NAMES.iter().find(|&&(name, _)| name == word) {
+                Some(&(_, offset)) => directions.iter().position(|&d| d == offset),
+                None => word.parse().ok().filter(|&i: &usize| i < directions.len()),
+            }
+            .ok_or_else(|| error(&format!("{word:?} isn't a direction on this board")))?;
+
+            let player: usize = match words.next() {
+                Some(word) => word
+                    .parse()
+                    .ok()
+                    .filter(|&p| p < config.players)
+                    .ok_or_else(|| error(&format!("{word:?} isn't a player")))?,
+                None => 0,
+            };
+
+            replay.turns.push((tick, player as u8, direction as u8));
+        }
+
+        replay.turns.sort_by_key(|&(tick, _, _)| tick);
+
+        Ok(Self {
+            replay,
+            stepping: false,
+            savestate: None,
+        })
+    }
+}
+
+pub fn start(
+    mut cmd: Commands,
+    tas: Res<Tas>,
+    mut toast: Toasts,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    replay::watch(&mut cmd, tas.replay.clone(), &mut next_state);
+    toast.show("p pauses, . steps, f5 saves a state, f9 loads it");
+}
+
+pub fn control(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    spawner: Res<Spawner>,
+    config: Res<GameConfig>,
+    mut tas: ResMut<Tas>,
+    mut toast: Toasts,
+    mut game: ResMut<Game>,
+) {
+    if game.is_over() {
+        return;
+    }
+
+    if input.just_pressed(KeyCode::KeyP) {
+        game.paused = !game.paused;
+    }
+
+    if game.paused && input.just_pressed(KeyCode::Period) {
+        let duration = game.tick_timer.duration();
+        game.tick_timer.set_elapsed(duration);
+        game.countdown = None;
+        game.paused = false;
+        tas.stepping = true;
+    }
+
+    if input.just_pressed(KeyCode::F5) {
+        tas.savestate = Some(game.snapshot());
+        toast.show(format!("saved state at tick {}", game.replay.ticks));
+    }
+
+    if input.just_pressed(KeyCode::F9) {
+        if let Some(savestate) = tas.savestate.clone() {
+            game.restore(&mut cmd, &spawner, &config, savestate);
+            game.countdown = None;
+            game.paused = true;
+            toast.show(format!("loaded state at tick {}", game.replay.ticks));
+        }
+    }
+}
+
+pub fn pause_after_step(mut tas: ResMut<Tas>, mut game: ResMut<Game>) {
+    if tas.stepping {
+        tas.stepping = false;
+        game.paused = true;
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    difficulty::Difficulty, mode::GameMode, settings::Settings, Game, GameConfig, RoundOver,
+};
+
+// where reports go unless the settings file points somewhere else, baked in
+// at build time
+const ENDPOINT: Option<&str> = option_env!("SNAKE_TELEMETRY_URL");
+
+// how a round went and nothing else: no name, seed, replay or date that
+// could tie it back to a player
+#[derive(Serialize)]
+struct Report {
+    mode: GameMode,
+    difficulty: Difficulty,
+    score: u32,
+    length: usize,
+    seconds: f32,
+}
+
+// only ever sends anything once the player has turned it on in the settings
+pub fn send(
+    mut events: EventReader<RoundOver>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    game: Res<Game>,
+) {
+    let endpoint = settings.telemetry_endpoint.as_deref().or(ENDPOINT);
+
+    for event in events.read() {
+        let Some(endpoint) = endpoint.filter(|_| settings.telemetry) else {
+            continue;
+        };
+
+        let report = Report {
+            mode: config.mode,
+            difficulty: config.difficulty,
+            score: event.score,
+            length: event.length,
+            seconds: game.played,
+        };
+
+        match serde_json::to_string(&report) {
+            Ok(body) => post(endpoint, body),
+            Err(e) => warn!("failed to serialize report: {e}"),
+        }
+    }
+}
+
+// off the main thread, so a slow server never holds up a frame. failures are
+// only logged, there's nothing for the player to do about them
+#[cfg(not(target_arch = "wasm32"))]
+fn post(endpoint: &str, body: String) {
+    let endpoint = endpoint.to_owned();
+
+    std::thread::spawn(move || {
+        let result = ureq::post(&endpoint)
+            .set("Content-Type", "application/json")
+            .send_string(&body);
+
+        if let Err(e) = result {
+            warn!("failed to send report: {e}");
+        }
+    });
+}
+
+// a beacon is queued by the browser and still goes out if the tab is closed
+#[cfg(target_arch = "wasm32")]
+fn post(endpoint: &str, body: String) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+
+    if let Err(e) = window
+        .navigator()
+        .send_beacon_with_opt_str(endpoint, Some(&body))
+    {
+        warn!("failed to send report: {e:?}");
+    }
+}
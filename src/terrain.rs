@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use serde::{Deserialize, Serialize};
+
+use crate::{level::Level, mask, GameConfig, Offset, Position, Spawner};
+
+// terrain sits under everything else on the board
+const TERRAIN_Z: f32 = -1.0;
+const ARROW_Z: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Terrain {
+    // turns can't be made while the head is on ice
+    Ice,
+    // ticks take twice as long while the player's head is in mud
+    Mud,
+    // a head moving onto a conveyor is pushed one more cell its way
+    Conveyor(Offset),
+}
+
+impl Terrain {
+    // for level codes: a kind byte, and the direction for conveyors
+    pub fn encode(self, bytes: &mut Vec<u8>) {
+        match self {
+            Terrain::Ice => bytes.push(0),
+            Terrain::Mud => bytes.push(1),
+            Terrain::Conveyor(direction) => {
+                bytes.push(2);
+                bytes.push(direction.x as i8 as u8);
+                bytes.push(direction.y as i8 as u8);
+            }
+        }
+    }
+
+    pub fn decode(bytes: &mut impl Iterator<Item = u8>) -> Option<Self> {
+        match bytes.next()? {
+            0 => Some(Terrain::Ice),
+            1 => Some(Terrain::Mud),
+            2 => Some(Terrain::Conveyor(Offset::new(
+                bytes.next()? as i8 as isize,
+                bytes.next()? as i8 as isize,
+            ))),
+            _ => None,
+        }
+    }
+}
+
+pub fn spawn(
+    cmd: &mut Commands,
+    spawner: &Spawner,
+    config: &GameConfig,
+    position: Position,
+    terrain: Terrain,
+) -> Entity {
+    let material = match terrain {
+        Terrain::Ice => &spawner.ice_material,
+        Terrain::Mud => &spawner.mud_material,
+        Terrain::Conveyor(_) => &spawner.conveyor_material,
+    };
+
+    let mut transform = spawner.transform(position);
+    transform.translation.z = TERRAIN_Z;
+
+    let mut tile = cmd.spawn(MaterialMesh2dBundle {
+        mesh: spawner.mesh.clone(),
+        material: material.clone(),
+        transform,
+        ..default()
+    });
+
+    if let Terrain::Conveyor(direction) = terrain {
+        tile.with_children(|parent| {
+            parent.spawn(MaterialMesh2dBundle {
+                mesh: spawner.arrow_mesh.clone(),
+                material: spawner.ring_material.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, ARROW_Z)
+                    .with_rotation(Quat::from_rotation_z(angle(config, direction))),
+                ..default()
+            });
+        });
+    }
+
+    tile.id()
+}
+
+// the on-screen angle of a board direction, measured from a cell away from
+// the edges so every direction has a neighbor to measure against
+fn angle(config: &GameConfig, direction: Offset) -> f32 {
+    let topology = config.topology.get();
+    let from = Position::new(2, 2);
+    let (x, y) = topology.neighbor(from, direction);
+
+    let delta = topology.to_world(Position::new(x as usize, y as usize)) - topology.to_world(from);
+
+    delta.y.atan2(delta.x)
+}
+
+pub fn spawn_level(
+    cmd: &mut Commands,
+    spawner: &Spawner,
+    config: &GameConfig,
+) -> (HashMap<Position, Terrain>, Vec<Entity>) {
+    let terrain: HashMap<_, _> = config
+        .level
+        .iter()
+        .flat_map(|l: &Level| l.terrain.iter().copied())
+        .collect();
+
+    let mut tiles: Vec<_> = terrain
+        .iter()
+        .map(|(&position, &kind)| spawn(cmd, spawner, config, position, kind))
+        .collect();
+
+    tiles.extend(mask::spawn_void(cmd, spawner, config));
+
+    (terrain, tiles)
+}
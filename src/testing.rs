@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::RunSystemOnce, input::ButtonInput, prelude::*};
+
+use crate::{
+    level::Level, replay::Replay, savegame::SavedGame, storage, terrain::Terrain, verify, AppState,
+    Game, GameConfig, Offset, Position, SnakeDied, Spawner, PLAYER,
+};
+
+const FRAME: Duration = Duration::from_millis(5);
+const MAX_FRAMES_PER_TICK: usize = 10_000;
+
+// runs the real game plugin on `MinimalPlugins` with a manual clock so
+// integration tests can script key presses tick by tick
+pub struct Harness {
+    app: App,
+}
+
+impl Harness {
+    pub fn new(seed: u64) -> Self {
+        Self::with(GameConfig {
+            seed: Some(seed),
+            ..default()
+        })
+    }
+
+    // a round without the random walls, for tests that need a clear path
+    pub fn empty(seed: u64) -> Self {
+        Self::with(GameConfig {
+            seed: Some(seed),
+            wall_density: 0.0,
+            ..default()
+        })
+    }
+
+    fn with(config: GameConfig) -> Self {
+        storage::disable();
+
+        let mut app = verify::headless(config, FRAME);
+
+        app.world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+
+        app.update();
+
+        // skip the countdown and keep test rounds out of the high scores
+        let mut game = app.world.resource_mut::<Game>();
+        game.countdown = None;
+        game.ranked = false;
+
+        Self { app }
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.app
+            .world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+    }
+
+    // advance frames until the game has ticked once, or is over
+    pub fn tick(&mut self) {
+        let before = self.game().replay.ticks;
+
+        for _ in 0..MAX_FRAMES_PER_TICK {
+            self.app.update();
+            self.app
+                .world
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .reset_all();
+
+            if self.game().replay.ticks != before || self.game().is_over() {
+                return;
+            }
+        }
+
+        panic!("game did not tick within {MAX_FRAMES_PER_TICK} frames");
+    }
+
+    pub fn ticks(&mut self, count: usize) {
+        for _ in 0..count {
+            self.tick();
+        }
+    }
+
+    fn game(&self) -> &Game {
+        self.app.world.resource::<Game>()
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        let config = self.app.world.resource::<GameConfig>();
+
+        (config.width, config.height)
+    }
+
+    // the player's cells from tail to head
+    pub fn snake(&self) -> Vec<(usize, usize)> {
+        self.game().snakes[PLAYER]
+            .nodes
+            .iter()
+            .map(|n| (n.position.x, n.position.y))
+            .collect()
+    }
+
+    pub fn head(&self) -> (usize, usize) {
+        *self.snake().last().unwrap()
+    }
+
+    pub fn food(&self) -> Option<(usize, usize)> {
+        self.game()
+            .food
+            .as_ref()
+            .map(|f| (f.position.x, f.position.y))
+    }
+
+    pub fn walls(&self) -> Vec<(usize, usize)> {
+        self.game()
+            .walls
+            .iter()
+            .map(|w| (w.position.x, w.position.y))
+            .collect()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.game().score
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.game().dead
+    }
+
+    pub fn deaths(&self) -> usize {
+        self.app.world.resource::<Events<SnakeDied>>().len()
+    }
+
+    // how each of the recent deaths came about, as the game describes them
+    pub fn death_causes(&self) -> Vec<&'static str> {
+        let events = self.app.world.resource::<Events<SnakeDied>>();
+
+        events
+            .get_reader()
+            .read(events)
+            .map(|SnakeDied(death)| death.cause.describe())
+            .collect()
+    }
+
+    pub fn set_food(&mut self, (x, y): (usize, usize)) {
+        let mut game = self.app.world.resource_mut::<Game>();
+        let food = game.food.as_mut().unwrap();
+        food.position = Position::new(x, y);
+    }
+
+    pub fn add_wall(&mut self, (x, y): (usize, usize)) {
+        self.app.world.run_system_once_with(
+            Position::new(x, y),
+            |In(position): In<Position>,
+             mut cmd: Commands,
+             spawner: Res<Spawner>,
+             mut game: ResMut<Game>| {
+                let wall = spawner.new_wall(&mut cmd, position);
+                game.walls.push(wall);
+            },
+        );
+    }
+
+    // a conveyor without its tile, enough for the game to push snakes along
+    pub fn add_conveyor(&mut self, (x, y): (usize, usize), (dx, dy): (isize, isize)) {
+        let mut game = self.app.world.resource_mut::<Game>();
+        game.terrain
+            .insert(Position::new(x, y), Terrain::Conveyor(Offset::new(dx, dy)));
+    }
+
+    // the round as recorded so far
+    pub fn replay(&self) -> Replay {
+        self.game().replay.clone()
+    }
+
+    pub fn snapshot(&self) -> SavedGame {
+        self.game().snapshot()
+    }
+
+    // put the round back to a snapshot, skipping the resume countdown like `new`
+    // skips the starting one
+    pub fn restore(&mut self, snapshot: SavedGame) {
+        self.app.world.run_system_once_with(
+            snapshot,
+            |In(snapshot): In<SavedGame>,
+             mut cmd: Commands,
+             spawner: Res<Spawner>,
+             config: Res<GameConfig>,
+             mut game: ResMut<Game>| {
+                game.restore(&mut cmd, &spawner, &config, snapshot);
+            },
+        );
+
+        let mut game = self.app.world.resource_mut::<Game>();
+        game.countdown = None;
+        game.ranked = false;
+    }
+}
+
+// decodes a level code and encodes it again at the current version, so tests
+// can check that older codes carry over
+pub fn reencode_level(code: &str) -> Option<String> {
+    Level::decode(code).map(|level| level.encode())
+}
+
+// replays as they're written in saves and submissions, so tests can edit them
+// the way a tampered or broken file would
+pub fn write_replay(replay: &Replay) -> String {
+    ron::to_string(replay).unwrap()
+}
+
+pub fn parse_replay(text: &str) -> Option<Replay> {
+    ron::from_str(text).ok()
+}
@@ -0,0 +1,369 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cheats::{Cheats, GIANT_FOOD_SCALE},
+    fog, heatmap,
+    keys::KeyColor,
+    mask, plan, practice,
+    settings::Settings,
+    trail, Game, GameConfig, Spawner,
+};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Default,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Default,
+        Palette::Deuteranopia,
+        Palette::Protanopia,
+        Palette::Tritanopia,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Default => "default",
+            Palette::Deuteranopia => "deuteranopia",
+            Palette::Protanopia => "protanopia",
+            Palette::Tritanopia => "tritanopia",
+        }
+    }
+}
+
+// how the food moves so it stands out from everything else on the board,
+// which holds still between ticks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FoodPulse {
+    // how far the food grows and shrinks, as a fraction of a cell
+    pub scale: f32,
+    // pulses a second
+    pub rate: f32,
+    // turns a second
+    pub spin: f32,
+}
+
+impl FoodPulse {
+    pub const STILL: FoodPulse = FoodPulse {
+        scale: 0.0,
+        rate: 0.0,
+        spin: 0.0,
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub background: Color,
+    pub snake: Color,
+    pub partner: Color,
+    pub opponent: Color,
+    pub food: Color,
+    pub wall: Color,
+    pub ring: Color,
+    pub enemy: Color,
+    pub shrink: Color,
+    pub magnet: Color,
+    pub shield: Color,
+    pub rewind: Color,
+    pub ice: Color,
+    pub mud: Color,
+    pub conveyor: Color,
+    // one per key color, in the order of `KeyColor::ALL`. doors take a
+    // darker shade of their key
+    pub keys: [Color; 4],
+    pub food_pulse: FoodPulse,
+}
+
+impl Theme {
+    pub const CLASSIC: Theme = Theme {
+        background: Color::WHITE,
+        snake: Color::rgb(0.0, 0.0, 0.0),
+        partner: Color::rgb(0.1, 0.55, 0.25),
+        opponent: Color::rgb(0.2, 0.4, 0.8),
+        food: Color::rgb(0.0, 0.0, 0.0),
+        wall: Color::rgb(0.5, 0.5, 0.5),
+        ring: Color::rgb(0.25, 0.25, 0.25),
+        enemy: Color::rgb(0.85, 0.15, 0.15),
+        shrink: Color::rgb(0.55, 0.2, 0.75),
+        magnet: Color::rgb(0.95, 0.6, 0.1),
+        shield: Color::rgb(0.2, 0.75, 0.9),
+        rewind: Color::rgb(0.9, 0.3, 0.6),
+        ice: Color::rgb(0.8, 0.92, 1.0),
+        mud: Color::rgb(0.55, 0.42, 0.3),
+        conveyor: Color::rgb(0.85, 0.85, 0.7),
+        keys: [
+            Color::rgb(0.85, 0.2, 0.2),
+            Color::rgb(0.2, 0.4, 0.85),
+            Color::rgb(0.2, 0.65, 0.3),
+            Color::rgb(0.9, 0.75, 0.1),
+        ],
+        food_pulse: FoodPulse {
+            scale: 0.15,
+            rate: 1.5,
+            spin: 0.0,
+        },
+    };
+
+    pub const ZEN: Theme = Theme {
+        background: Color::rgb(0.91, 0.95, 0.93),
+        snake: Color::rgb(0.29, 0.56, 0.54),
+        partner: Color::rgb(0.45, 0.62, 0.35),
+        opponent: Color::rgb(0.55, 0.5, 0.72),
+        food: Color::rgb(0.93, 0.6, 0.47),
+        wall: Color::rgb(0.72, 0.8, 0.76),
+        ring: Color::rgb(0.6, 0.68, 0.64),
+        enemy: Color::rgb(0.8, 0.42, 0.45),
+        shrink: Color::rgb(0.68, 0.55, 0.82),
+        magnet: Color::rgb(0.9, 0.75, 0.5),
+        shield: Color::rgb(0.55, 0.78, 0.86),
+        rewind: Color::rgb(0.88, 0.6, 0.75),
+        ice: Color::rgb(0.85, 0.93, 0.97),
+        mud: Color::rgb(0.7, 0.62, 0.52),
+        conveyor: Color::rgb(0.85, 0.88, 0.78),
+        keys: [
+            Color::rgb(0.85, 0.5, 0.5),
+            Color::rgb(0.5, 0.62, 0.85),
+            Color::rgb(0.5, 0.75, 0.55),
+            Color::rgb(0.9, 0.82, 0.5),
+        ],
+        food_pulse: FoodPulse {
+            scale: 0.1,
+            rate: 0.5,
+            spin: 0.1,
+        },
+    };
+
+    // the monochrome lcd of an old phone: every piece is the same dark green
+    // except other snakes, which need a shade of their own to tell apart. the
+    // food holds still, as it did on the lcd
+    pub const NOKIA: Theme = Theme {
+        background: Color::rgb(0.61, 0.73, 0.38),
+        snake: Color::rgb(0.17, 0.24, 0.1),
+        partner: Color::rgb(0.3, 0.4, 0.18),
+        opponent: Color::rgb(0.4, 0.5, 0.25),
+        food: Color::rgb(0.17, 0.24, 0.1),
+        wall: Color::rgb(0.17, 0.24, 0.1),
+        ring: Color::rgb(0.17, 0.24, 0.1),
+        enemy: Color::rgb(0.17, 0.24, 0.1),
+        shrink: Color::rgb(0.3, 0.4, 0.18),
+        magnet: Color::rgb(0.3, 0.4, 0.18),
+        shield: Color::rgb(0.3, 0.4, 0.18),
+        rewind: Color::rgb(0.3, 0.4, 0.18),
+        ice: Color::rgb(0.55, 0.67, 0.34),
+        mud: Color::rgb(0.5, 0.6, 0.3),
+        conveyor: Color::rgb(0.55, 0.67, 0.34),
+        keys: [
+            Color::rgb(0.17, 0.24, 0.1),
+            Color::rgb(0.3, 0.4, 0.18),
+            Color::rgb(0.4, 0.5, 0.25),
+            Color::rgb(0.5, 0.6, 0.3),
+        ],
+        food_pulse: FoodPulse::STILL,
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        background: Color::WHITE,
+        snake: Color::rgb(0.0, 0.0, 0.0),
+        partner: Color::rgb(0.0, 0.4, 0.0),
+        opponent: Color::rgb(0.0, 0.0, 0.8),
+        food: Color::rgb(0.85, 0.0, 0.0),
+        wall: Color::rgb(0.35, 0.35, 0.35),
+        ring: Color::rgb(0.2, 0.2, 0.2),
+        enemy: Color::rgb(0.7, 0.0, 0.7),
+        shrink: Color::rgb(0.4, 0.0, 0.9),
+        magnet: Color::rgb(0.9, 0.5, 0.0),
+        shield: Color::rgb(0.0, 0.55, 0.75),
+        rewind: Color::rgb(0.85, 0.0, 0.45),
+        ice: Color::rgb(0.7, 0.85, 1.0),
+        mud: Color::rgb(0.45, 0.3, 0.15),
+        conveyor: Color::rgb(0.9, 0.85, 0.4),
+        keys: [
+            Color::rgb(0.85, 0.0, 0.0),
+            Color::rgb(0.0, 0.2, 0.9),
+            Color::rgb(0.0, 0.55, 0.0),
+            Color::rgb(0.95, 0.8, 0.0),
+        ],
+        food_pulse: FoodPulse {
+            scale: 0.2,
+            rate: 1.5,
+            spin: 0.0,
+        },
+    };
+}
+
+impl Theme {
+    // colors from the Okabe-Ito set, picked per deficiency so the player,
+    // partner, opponents, food, enemies and keys never share a confusable hue
+    pub fn with_palette(self, palette: Palette) -> Theme {
+        match palette {
+            Palette::Default => self,
+            Palette::Deuteranopia => Theme {
+                partner: Color::rgb(0.0, 0.45, 0.7),
+                opponent: Color::rgb(0.9, 0.62, 0.0),
+                food: Color::rgb(0.8, 0.47, 0.65),
+                enemy: Color::rgb(0.84, 0.37, 0.0),
+                keys: [
+                    Color::rgb(0.84, 0.37, 0.0),
+                    Color::rgb(0.0, 0.45, 0.7),
+                    Color::rgb(0.0, 0.62, 0.45),
+                    Color::rgb(0.94, 0.89, 0.26),
+                ],
+                ..self
+            },
+            // reds look dark and dull without l cones, so none are used
+            Palette::Protanopia => Theme {
+                partner: Color::rgb(0.0, 0.45, 0.7),
+                opponent: Color::rgb(0.94, 0.89, 0.26),
+                food: Color::rgb(0.0, 0.62, 0.45),
+                enemy: Color::rgb(0.9, 0.62, 0.0),
+                keys: [
+                    Color::rgb(0.8, 0.47, 0.65),
+                    Color::rgb(0.0, 0.45, 0.7),
+                    Color::rgb(0.0, 0.62, 0.45),
+                    Color::rgb(0.94, 0.89, 0.26),
+                ],
+                ..self
+            },
+            Palette::Tritanopia => Theme {
+                partner: Color::rgb(0.84, 0.37, 0.0),
+                opponent: Color::rgb(0.0, 0.62, 0.45),
+                food: Color::rgb(0.8, 0.47, 0.65),
+                enemy: Color::rgb(0.0, 0.45, 0.7),
+                keys: [
+                    Color::rgb(0.84, 0.37, 0.0),
+                    Color::rgb(0.0, 0.45, 0.7),
+                    Color::rgb(0.0, 0.62, 0.45),
+                    Color::rgb(0.94, 0.89, 0.26),
+                ],
+                ..self
+            },
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+pub fn current(config: &GameConfig, settings: &Settings) -> Theme {
+    let theme = if settings.high_contrast {
+        Theme::HIGH_CONTRAST
+    } else {
+        settings.theme.theme().unwrap_or(config.theme)
+    };
+
+    theme.with_palette(settings.palette)
+}
+
+pub fn apply(
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    spawner: Res<Spawner>,
+    mut clear_color: ResMut<ClearColor>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let theme = current(&config, &settings);
+
+    clear_color.0 = theme.background;
+
+    for (handle, color) in [
+        (&spawner.material, theme.snake),
+        (&spawner.partner_material, theme.partner),
+        (&spawner.opponent_material, theme.opponent),
+        (&spawner.food_material, theme.food),
+        (&spawner.wall_material, theme.wall),
+        (&spawner.ring_material, theme.ring),
+        (&spawner.enemy_material, theme.enemy),
+        (&spawner.shrink_material, theme.shrink),
+        (&spawner.magnet_material, theme.magnet),
+        (&spawner.shield_material, theme.shield),
+        (&spawner.rewind_material, theme.rewind),
+        (&spawner.ice_material, theme.ice),
+        (&spawner.mud_material, theme.mud),
+        (&spawner.conveyor_material, theme.conveyor),
+        (
+            &spawner.fog_material,
+            theme.background.with_a(fog::FOG_ALPHA),
+        ),
+        (&spawner.void_material, theme.wall.with_a(mask::VOID_ALPHA)),
+        (
+            &spawner.preview_material,
+            theme.food.with_a(practice::PREVIEW_ALPHA),
+        ),
+        (
+            &spawner.plan_material,
+            theme.opponent.with_a(plan::PLAN_ALPHA),
+        ),
+    ] {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = color;
+        }
+    }
+
+    for (index, color) in KeyColor::ALL.into_iter().enumerate() {
+        if let Some(material) = materials.get_mut(&spawner.key_materials[index]) {
+            material.color = color.color(&theme);
+        }
+        if let Some(material) = materials.get_mut(&spawner.door_materials[index]) {
+            material.color = color.door_color(&theme);
+        }
+    }
+
+    for (step, handle) in spawner.trail_materials.iter().enumerate() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = theme.snake.with_a(trail::alpha(step));
+        }
+    }
+
+    for (level, handle) in spawner.heat_materials.iter().enumerate() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = theme.snake.with_a(heatmap::alpha(level));
+        }
+    }
+}
+
+// the food pulses and turns the way the theme says, unless motion is reduced
+pub fn animate_food(
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    game: Res<Game>,
+    cheats: Res<Cheats>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(food) = game.food.as_ref() else {
+        return;
+    };
+
+    let Ok(mut transform) = transforms.get_mut(food.entity) else {
+        return;
+    };
+
+    let pulse = if settings.reduced_motion {
+        FoodPulse::STILL
+    } else {
+        current(&config, &settings).food_pulse
+    };
+
+    let seconds = time.elapsed_seconds();
+    let size = if cheats.giant_food() {
+        GIANT_FOOD_SCALE
+    } else {
+        1.0
+    };
+    let scale = size * (1.0 + pulse.scale * (seconds * pulse.rate * TAU).sin());
+    let rotation = Quat::from_rotation_z(seconds * pulse.spin * TAU);
+
+    transform.scale = Vec3::new(scale, scale, 1.0);
+    transform.rotation = rotation;
+}
@@ -0,0 +1,151 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use bevy::prelude::*;
+use wasm_bindgen::{prelude::*, JsCast};
+
+use crate::{settings::Settings, Game, GameConfig, Offset, PLAYER};
+
+// the latest reading, in degrees, as f32 bits
+static BETA: AtomicU32 = AtomicU32::new(0);
+static GAMMA: AtomicU32 = AtomicU32::new(0);
+static SEEN: AtomicBool = AtomicBool::new(false);
+// whether the next tap or key press should ask for the sensor, and whether
+// anything is listening for one yet
+static ASKING: AtomicBool = AtomicBool::new(false);
+static WAITING: AtomicBool = AtomicBool::new(false);
+
+pub fn listen() {
+    let window = web_sys::window().unwrap();
+
+    let callback = Closure::<dyn FnMut(web_sys::DeviceOrientationEvent)>::new(
+        |event: web_sys::DeviceOrientationEvent| {
+            let (Some(beta), Some(gamma)) = (event.beta(), event.gamma()) else {
+                return;
+            };
+
+            BETA.store((beta as f32).to_bits(), Ordering::Relaxed);
+            GAMMA.store((gamma as f32).to_bits(), Ordering::Relaxed);
+            SEEN.store(true, Ordering::Relaxed);
+        },
+    );
+
+    window
+        .add_event_listener_with_callback("deviceorientation", callback.as_ref().unchecked_ref())
+        .unwrap();
+
+    callback.forget();
+}
+
+// ios only sends orientation events once the page has asked for them, and it
+// may only ask from inside a tap or key press. bevy reads input outside of
+// those, so the next gesture anywhere on the page does the asking. elsewhere
+// there's no permission to ask for, and the events just come
+pub fn request_permission() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+
+    let Ok(class) = js_sys::Reflect::get(&window, &"DeviceOrientationEvent".into()) else {
+        return;
+    };
+
+    let Some(request) = js_sys::Reflect::get(&class, &"requestPermission".into())
+        .ok()
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+    else {
+        return;
+    };
+
+    ASKING.store(true, Ordering::Relaxed);
+
+    if WAITING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ask = Closure::<dyn FnMut()>::new(move || {
+        if !ASKING.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        let answered = Closure::<dyn FnMut(JsValue)>::new(|state: JsValue| {
+            if state.as_string().as_deref() != Some("granted") {
+                warn!("tilt steering wasn't allowed to read the device's orientation");
+            }
+        });
+
+        match request.call0(&class) {
+            Ok(promise) => {
+                let _ = js_sys::Promise::from(promise).then(&answered);
+            }
+            Err(e) => warn!("failed to ask for the device's orientation: {e:?}"),
+        }
+
+        answered.forget();
+    });
+
+    for event in ["click", "touchend", "keyup"] {
+        let _ = window.add_event_listener_with_callback(event, ask.as_ref().unchecked_ref());
+    }
+
+    ask.forget();
+}
+
+// front-to-back and left-to-right tilt, or `None` on devices that never
+// reported one
+pub fn reading() -> Option<(f32, f32)> {
+    if !SEEN.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    Some((
+        f32::from_bits(BETA.load(Ordering::Relaxed)),
+        f32::from_bits(GAMMA.load(Ordering::Relaxed)),
+    ))
+}
+
+// tilting past the threshold turns towards the side that dips, measured from
+// the calibrated level. it turns once per tilt, so the device has to come
+// back towards level or lean another way to turn again
+pub fn steer(
+    settings: Res<Settings>,
+    config: Res<GameConfig>,
+    mut game: ResMut<Game>,
+    mut held: Local<Option<Offset>>,
+) {
+    if !settings.tilt {
+        return;
+    }
+
+    let Some((beta, gamma)) = reading() else {
+        return;
+    };
+
+    let (level_beta, level_gamma) = settings.tilt_level;
+    let (dy, dx) = (beta - level_beta, gamma - level_gamma);
+
+    let offset = if dx.abs().max(dy.abs()) < settings.tilt_sensitivity.threshold() {
+        None
+    } else if dx.abs() > dy.abs() {
+        Some(Offset::new(dx.signum() as isize, 0))
+    } else {
+        Some(Offset::new(0, dy.signum() as isize))
+    };
+
+    if offset == *held {
+        return;
+    }
+
+    *held = offset;
+
+    let Some(offset) = offset else {
+        return;
+    };
+
+    if game.is_over() || game.paused {
+        return;
+    }
+
+    let player = if config.swap { game.active } else { PLAYER };
+    let offset = game.controls.transform(config.confusion, offset);
+    game.push_input(&config, player, offset);
+}
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::{Offset, Position, GRID_SCALE};
+
+const SQRT_3: f32 = 1.732_050_8;
+const HEX_RADIUS: f32 = GRID_SCALE / SQRT_3;
+
+const HEX_DIRECTIONS: [Offset; 6] = [
+    Offset::new(1, 0),
+    Offset::new(0, 1),
+    Offset::new(-1, 1),
+    Offset::new(-1, 0),
+    Offset::new(0, -1),
+    Offset::new(1, -1),
+];
+
+pub trait BoardTopology: Send + Sync {
+    fn directions(&self) -> &'static [Offset];
+
+    fn neighbor(&self, position: Position, facing: Offset) -> (isize, isize);
+
+    fn steer(&self, pressed: Offset, current: Offset) -> Option<Offset>;
+
+    fn to_world(&self, position: Position) -> Vec2;
+
+    fn board_size(&self, width: usize, height: usize) -> Vec2;
+
+    fn cell_mesh(&self) -> Mesh;
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    #[default]
+    Square,
+    Hex,
+}
+
+impl Topology {
+    pub fn get(self) -> &'static dyn BoardTopology {
+        match self {
+            Topology::Square => &Square,
+            Topology::Hex => &Hex,
+        }
+    }
+}
+
+pub struct Square;
+
+impl BoardTopology for Square {
+    fn directions(&self) -> &'static [Offset] {
+        &Offset::DIRECTIONS
+    }
+
+    fn neighbor(&self, position: Position, facing: Offset) -> (isize, isize) {
+        (
+            position.x as isize + facing.x,
+            position.y as isize + facing.y,
+        )
+    }
+
+    fn steer(&self, pressed: Offset, _current: Offset) -> Option<Offset> {
+        Some(pressed)
+    }
+
+    fn to_world(&self, position: Position) -> Vec2 {
+        Vec2::new(
+            (position.x as f32 + 0.5) * GRID_SCALE,
+            -(position.y as f32 + 0.5) * GRID_SCALE,
+        )
+    }
+
+    fn board_size(&self, width: usize, height: usize) -> Vec2 {
+        Vec2::new(width as f32, height as f32) * GRID_SCALE
+    }
+
+    fn cell_mesh(&self) -> Mesh {
+        Rectangle::new(GRID_SCALE - 1.0, GRID_SCALE - 1.0).into()
+    }
+}
+
+// pointy-top hexes in "odd-r" offset coordinates, with facings expressed as
+// axial deltas so that reversing a direction is still a plain negation
+pub struct Hex;
+
+impl BoardTopology for Hex {
+    fn directions(&self) -> &'static [Offset] {
+        &HEX_DIRECTIONS
+    }
+
+    fn neighbor(&self, position: Position, facing: Offset) -> (isize, isize) {
+        let (x, y) = (position.x as isize, position.y as isize);
+
+        let q = x - (y - (y & 1)) / 2 + facing.x;
+        let r = y + facing.y;
+
+        (q + (r - (r & 1)) / 2, r)
+    }
+
+    fn steer(&self, pressed: Offset, current: Offset) -> Option<Offset> {
+        let index = HEX_DIRECTIONS.iter().position(|&d| d == current)?;
+
+        let turn = match (pressed.x, pressed.y) {
+            (1, 0) => 1,
+            (-1, 0) => HEX_DIRECTIONS.len() - 1,
+            _ => return None,
+        };
+
+        Some(HEX_DIRECTIONS[(index + turn) % HEX_DIRECTIONS.len()])
+    }
+
+    fn to_world(&self, position: Position) -> Vec2 {
+        let shift = (position.y & 1) as f32 * 0.5;
+
+        Vec2::new(
+            (position.x as f32 + 0.5 + shift) * GRID_SCALE,
+            -(position.y as f32 * 1.5 + 1.0) * HEX_RADIUS,
+        )
+    }
+
+    fn board_size(&self, width: usize, height: usize) -> Vec2 {
+        Vec2::new(
+            (width as f32 + 0.5) * GRID_SCALE,
+            ((height as f32 - 1.0) * 1.5 + 2.0) * HEX_RADIUS,
+        )
+    }
+
+    fn cell_mesh(&self) -> Mesh {
+        RegularPolygon::new(HEX_RADIUS - 0.5, 6).into()
+    }
+}
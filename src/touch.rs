@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use crate::{AppState, Game, GameConfig, Offset, PLAYER};
+
+const BUTTON_SIZE: f32 = 56.0;
+const MARGIN: f32 = 16.0;
+const BUTTON_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.15);
+const LABEL_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+
+#[derive(Component, Debug, Clone, Copy)]
+pub enum TouchButton {
+    Steer(Offset),
+    Pause,
+}
+
+#[derive(Component)]
+pub struct TouchControls;
+
+// phones and tablets have no keyboard, so the web build draws a d-pad and a
+// pause button over the board while playing
+pub fn is_touch_device() -> bool {
+    web_sys::window().is_some_and(|w| w.navigator().max_touch_points() > 0)
+}
+
+pub fn setup(mut cmd: Commands) {
+    if !is_touch_device() {
+        return;
+    }
+
+    let button = |cmd: &mut ChildBuilder, button: TouchButton, label: &str, left: f32, top: f32| {
+        cmd.spawn((
+            button,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(left * BUTTON_SIZE),
+                    top: Val::Px(top * BUTTON_SIZE),
+                    width: Val::Px(BUTTON_SIZE),
+                    height: Val::Px(BUTTON_SIZE),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+        ))
+        .with_children(|cmd| {
+            cmd.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: BUTTON_SIZE / 2.0,
+                    color: LABEL_COLOR,
+                    ..default()
+                },
+            ));
+        });
+    };
+
+    cmd.spawn((
+        TouchControls,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(MARGIN),
+                bottom: Val::Px(MARGIN),
+                width: Val::Px(BUTTON_SIZE * 3.0),
+                height: Val::Px(BUTTON_SIZE * 3.0),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ))
+    .with_children(|cmd| {
+        button(cmd, TouchButton::Steer(Offset::new(0, -1)), "^", 1.0, 0.0);
+        button(cmd, TouchButton::Steer(Offset::new(-1, 0)), "<", 0.0, 1.0);
+        button(cmd, TouchButton::Steer(Offset::new(1, 0)), ">", 2.0, 1.0);
+        button(cmd, TouchButton::Steer(Offset::new(0, 1)), "v", 1.0, 2.0);
+    });
+
+    cmd.spawn((
+        TouchControls,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(MARGIN),
+                bottom: Val::Px(MARGIN),
+                width: Val::Px(BUTTON_SIZE),
+                height: Val::Px(BUTTON_SIZE),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ))
+    .with_children(|cmd| {
+        button(cmd, TouchButton::Pause, "||", 0.0, 0.0);
+    });
+}
+
+pub fn show(
+    state: Res<State<AppState>>,
+    mut controls: Query<&mut Visibility, With<TouchControls>>,
+) {
+    let visibility = if *state.get() == AppState::Playing {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for mut v in controls.iter_mut() {
+        v.set_if_neq(visibility);
+    }
+}
+
+// each new touch is hit-tested on its own, so a thumb resting on the d-pad
+// doesn't swallow a tap with the other hand
+pub fn press(
+    touches: Res<Touches>,
+    buttons: Query<(&TouchButton, &Node, &GlobalTransform)>,
+    config: Res<GameConfig>,
+    mut game: ResMut<Game>,
+) {
+    for touch in touches.iter_just_pressed() {
+        let Some(&button) = buttons
+            .iter()
+            .find(|(_, node, transform)| node.logical_rect(transform).contains(touch.position()))
+            .map(|(button, _, _)| button)
+        else {
+            continue;
+        };
+
+        if game.is_over() {
+            continue;
+        }
+
+        match button {
+            TouchButton::Pause => game.toggle_pause(),
+            TouchButton::Steer(_) if game.paused => {}
+            TouchButton::Steer(offset) => {
+                let player = if config.swap { game.active } else { PLAYER };
+                let offset = game.controls.transform(config.confusion, offset);
+                game.push_input(&config, player, offset);
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+
+use crate::{Position, Spawner};
+
+// a vacated cell steps down through these, one every few frames
+pub const TRAIL_STEPS: usize = 4;
+const STEP_SECONDS: f32 = 0.05;
+const START_ALPHA: f32 = 0.5;
+
+// the snake's color at each step of fading out
+pub fn alpha(step: usize) -> f32 {
+    START_ALPHA * (1.0 - step as f32 / TRAIL_STEPS as f32)
+}
+
+#[derive(Component)]
+pub struct Fading {
+    step: usize,
+    timer: Timer,
+}
+
+// left on a cell a snake just moved off. it sits under everything else, so
+// anything moving in on the next tick covers it
+pub fn spawn(cmd: &mut Commands, spawner: &Spawner, position: Position) {
+    let mut transform = spawner.transform(position);
+    transform.translation.z -= 0.1;
+
+    cmd.spawn((
+        Fading {
+            step: 0,
+            timer: Timer::from_seconds(STEP_SECONDS, TimerMode::Repeating),
+        },
+        MaterialMesh2dBundle {
+            mesh: spawner.mesh.clone(),
+            material: spawner.trail_materials[0].clone(),
+            transform,
+            ..default()
+        },
+    ));
+}
+
+pub fn fade(
+    mut cmd: Commands,
+    time: Res<Time>,
+    spawner: Res<Spawner>,
+    mut trails: Query<(Entity, &mut Fading, &mut Handle<ColorMaterial>)>,
+) {
+    for (entity, mut fading, mut material) in trails.iter_mut() {
+        if !fading.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        fading.step += 1;
+
+        match spawner.trail_materials.get(fading.step) {
+            Some(next) => *material = next.clone(),
+            None => cmd.entity(entity).despawn(),
+        }
+    }
+}
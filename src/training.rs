@@ -0,0 +1,407 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    ai::{AiLevel, Weights, POLICY_KEY},
+    env::SnakeEnv,
+    hud::Toasts,
+    storage, AppState, GameConfig,
+};
+
+const POPULATION: usize = 24;
+const ELITES: usize = 4;
+const TOURNAMENT: usize = 3;
+const MUTATION_RATE: f64 = 0.3;
+const MUTATION_SIZE: f32 = 0.5;
+
+// every policy in a generation plays the same seeds so they're compared on
+// equal boards
+const GAMES: usize = 3;
+const BOARD_WIDTH: usize = 20;
+const BOARD_HEIGHT: usize = 16;
+const MAX_TICKS: u32 = 1500;
+// games end early when the snake goes this long without eating, so policies
+// can't score by circling forever
+const STARVE_TICKS: u32 = 300;
+
+const CHART_BARS: usize = 40;
+const CHART_WIDTH: f32 = 400.0;
+const CHART_HEIGHT: f32 = 150.0;
+
+const SELECTED: Color = Color::BLACK;
+const UNSELECTED: Color = Color::GRAY;
+
+#[derive(Default)]
+struct Progress {
+    generation: u32,
+    best: f32,
+    mean: f32,
+    // best fitness of each generation so far
+    history: Vec<f32>,
+    champion: Option<(Weights, f32)>,
+}
+
+// evolution runs on its own thread so the dashboard stays responsive. leaving
+// the dashboard stops the games in flight and waits for the thread to finish,
+// so coming back never leaves two running
+#[derive(Resource)]
+pub struct Training {
+    progress: Arc<Mutex<Progress>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Component)]
+pub struct TrainingRoot;
+
+#[derive(Component)]
+pub struct TrainingText;
+
+#[derive(Component)]
+pub struct TrainingBar(usize);
+
+pub fn setup(mut cmd: Commands) {
+    let progress = Arc::<Mutex<Progress>>::default();
+    let stop = Arc::<AtomicBool>::default();
+
+    let thread = {
+        let progress = progress.clone();
+        let stop = stop.clone();
+        thread::spawn(move || evolve(&progress, &stop))
+    };
+
+    cmd.insert_resource(Training {
+        progress,
+        stop,
+        thread: Some(thread),
+    });
+
+    cmd.spawn((
+        TrainingRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "training",
+            TextStyle {
+                font_size: 40.0,
+                color: SELECTED,
+                ..default()
+            },
+        ));
+
+        parent.spawn((
+            TrainingText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.0,
+                    color: SELECTED,
+                    ..default()
+                },
+            ),
+        ));
+
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(CHART_WIDTH),
+                    height: Val::Px(CHART_HEIGHT),
+                    align_items: AlignItems::FlexEnd,
+                    column_gap: Val::Px(1.0),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                border_color: BorderColor(UNSELECTED),
+                ..default()
+            })
+            .with_children(|chart| {
+                for index in 0..CHART_BARS {
+                    chart.spawn((
+                        TrainingBar(index),
+                        NodeBundle {
+                            style: Style {
+                                flex_grow: 1.0,
+                                height: Val::Percent(0.0),
+                                ..default()
+                            },
+                            background_color: BackgroundColor(UNSELECTED),
+                            ..default()
+                        },
+                    ));
+                }
+            });
+
+        parent.spawn(TextBundle::from_section(
+            "e to export the best policy (play it with --ai evolved), escape to go back",
+            TextStyle {
+                font_size: 16.0,
+                color: UNSELECTED,
+                ..default()
+            },
+        ));
+    });
+}
+
+pub fn cleanup(
+    mut cmd: Commands,
+    mut training: ResMut<Training>,
+    roots: Query<Entity, With<TrainingRoot>>,
+) {
+    training.stop.store(true, Ordering::Relaxed);
+
+    if let Some(thread) = training.thread.take() {
+        if thread.join().is_err() {
+            warn!("training stopped with a panic");
+        }
+    }
+
+    for entity in roots.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    cmd.remove_resource::<Training>();
+}
+
+pub fn navigate(
+    input: Res<ButtonInput<KeyCode>>,
+    training: Res<Training>,
+    mut toast: Toasts,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(KeyCode::KeyE) {
+        let champion = training.progress.lock().unwrap().champion;
+
+        match champion {
+            Some((weights, _)) => {
+                storage::save(POLICY_KEY, &weights);
+                toast.show("policy exported");
+            }
+            None => toast.show("no generation has finished yet"),
+        }
+    }
+
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+pub fn refresh(
+    training: Res<Training>,
+    mut texts: Query<&mut Text, With<TrainingText>>,
+    mut bars: Query<(&TrainingBar, &mut Style, &mut BackgroundColor)>,
+) {
+    let progress = training.progress.lock().unwrap();
+
+    let value = match progress.champion {
+        Some((_, fitness)) => format!(
+            "generation {}\nbest {:.2}, mean {:.2}\nall-time best {:.2}",
+            progress.generation, progress.best, progress.mean, fitness
+        ),
+        None => format!(
+            "generation {}\nplaying the first games...",
+            progress.generation
+        ),
+    };
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+
+    // the latest generations, scaled to the best of them
+    let history = &progress.history[progress.history.len().saturating_sub(CHART_BARS)..];
+    let top = history.iter().copied().fold(f32::EPSILON, f32::max);
+
+    for (bar, mut style, mut color) in bars.iter_mut() {
+        let fitness = history.get(bar.0).copied().unwrap_or(0.0);
+        let height = Val::Percent(fitness / top * 100.0);
+
+        if style.height != height {
+            style.height = height;
+        }
+
+        let value = if bar.0 + 1 == history.len() {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        if color.0 != value {
+            color.0 = value;
+        }
+    }
+}
+
+fn evolve(progress: &Mutex<Progress>, stop: &AtomicBool) {
+    let mut rng = rand::thread_rng();
+
+    // seeded around the hand-tuned weights, keeping them as one candidate
+    let mut population: Vec<_> = (0..POPULATION)
+        .map(|i| match i {
+            0 => Weights::default(),
+            _ => mutate(Weights::default(), &mut rng),
+        })
+        .collect();
+
+    while !stop.load(Ordering::Relaxed) {
+        let seeds: Vec<u64> = (0..GAMES).map(|_| rng.gen()).collect();
+        let fitness = evaluate(&population, &seeds, stop);
+
+        // the generation was cut short, so its scores mean nothing
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let mut ranked: Vec<_> = population.into_iter().zip(fitness).collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        {
+            let mut progress = progress.lock().unwrap();
+            let best = ranked[0];
+
+            progress.generation += 1;
+            progress.best = best.1;
+            progress.mean = ranked.iter().map(|r| r.1).sum::<f32>() / ranked.len() as f32;
+            progress.history.push(best.1);
+
+            if progress.champion.is_none_or(|(_, f)| best.1 > f) {
+                progress.champion = Some(best);
+            }
+        }
+
+        population = ranked.iter().take(ELITES).map(|r| r.0).collect();
+
+        while population.len() < POPULATION {
+            let a = tournament(&ranked, &mut rng);
+            let b = tournament(&ranked, &mut rng);
+
+            population.push(mutate(crossover(a, b, &mut rng), &mut rng));
+        }
+    }
+}
+
+// plays every policy across all cores
+fn evaluate(population: &[Weights], seeds: &[u64], stop: &AtomicBool) -> Vec<f32> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = population.len().div_ceil(threads);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = population
+            .chunks(chunk)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|&w| fitness(w, seeds, stop))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    })
+}
+
+// food eaten per game, with the share of the tick budget survived as a
+// tiebreaker. gives up as soon as training is stopped
+fn fitness(weights: Weights, seeds: &[u64], stop: &AtomicBool) -> f32 {
+    let config = GameConfig {
+        width: BOARD_WIDTH,
+        height: BOARD_HEIGHT,
+        evolved: weights,
+        ..default()
+    };
+
+    let mut env = SnakeEnv::with_config(config);
+    let policy = AiLevel::Evolved.get();
+
+    let total: f32 = seeds
+        .iter()
+        .map(|&seed| {
+            env.reset(seed);
+
+            let mut eaten = 0.0;
+            let mut hungry = 0;
+
+            for tick in 1..=MAX_TICKS {
+                let (_, reward, done) = env.step(env.policy_action(policy));
+
+                if reward > 0.0 {
+                    eaten += 1.0;
+                    hungry = 0;
+                } else {
+                    hungry += 1;
+                }
+
+                if done || hungry >= STARVE_TICKS || stop.load(Ordering::Relaxed) {
+                    return eaten + tick as f32 / MAX_TICKS as f32;
+                }
+            }
+
+            eaten + 1.0
+        })
+        .sum();
+
+    total / seeds.len() as f32
+}
+
+fn tournament(ranked: &[(Weights, f32)], rng: &mut impl Rng) -> Weights {
+    (0..TOURNAMENT)
+        .map(|_| ranked[rng.gen_range(0..ranked.len())])
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap()
+        .0
+}
+
+fn crossover(a: Weights, b: Weights, rng: &mut impl Rng) -> Weights {
+    let mut pick = |a: f32, b: f32| if rng.gen_bool(0.5) { a } else { b };
+
+    Weights {
+        food: pick(a.food, b.food),
+        space: pick(a.space, b.space),
+        straight: pick(a.straight, b.straight),
+        crowding: pick(a.crowding, b.crowding),
+    }
+}
+
+fn mutate(weights: Weights, rng: &mut impl Rng) -> Weights {
+    let mut nudge = |w: f32| {
+        if rng.gen_bool(MUTATION_RATE) {
+            w + rng.gen_range(-MUTATION_SIZE..MUTATION_SIZE)
+        } else {
+            w
+        }
+    };
+
+    Weights {
+        food: nudge(weights.food),
+        space: nudge(weights.space),
+        straight: nudge(weights.straight),
+        crowding: nudge(weights.crowding),
+    }
+}
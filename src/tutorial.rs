@@ -0,0 +1,337 @@
+use bevy::prelude::*;
+
+use crate::{
+    bindings::key_name, hud::Toasts, level::Level, savegame::SavedGame, settings::Settings,
+    AppState, Controller, Game, GameConfig, Offset, Position, Spawner, PLAYER,
+};
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 10;
+// slower than any difficulty so there's time to read along
+const TICK_INTERVAL: f32 = 0.3;
+const SPAWN: Position = Position { x: 6, y: 6 };
+const FOOD: Position = Position { x: 6, y: 3 };
+// every turn the tutorial asks for is the same, so it only has one key to
+// point at
+const TURN: Offset = Offset::new(0, -1);
+// how long the snake has to stay clear of its body before the tutorial is
+// done
+const CLEAR_TICKS: u32 = 3;
+
+// the snake doubled back across its own path, tail first, with its head two
+// cells short of running into itself
+const COIL: [(usize, usize); 14] = [
+    (8, 2),
+    (8, 3),
+    (8, 4),
+    (8, 5),
+    (8, 6),
+    (8, 7),
+    (7, 7),
+    (6, 7),
+    (5, 7),
+    (4, 7),
+    (4, 6),
+    (4, 5),
+    (5, 5),
+    (6, 5),
+];
+
+const SELECTED: Color = Color::BLACK;
+const UNSELECTED: Color = Color::GRAY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Turn,
+    Food,
+    Body,
+    Done,
+}
+
+#[derive(Resource)]
+pub struct Tutorial {
+    step: Step,
+    // the snake is held still until this turn is pressed
+    waiting: bool,
+    // the step and board to go back to when the snake dies
+    checkpoint: Option<(Step, SavedGame)>,
+    // the tick the snake was let go on in the body step
+    since: u32,
+    // the setup from before the tutorial, put back when it's left
+    config: GameConfig,
+}
+
+#[derive(Component)]
+pub struct TutorialRoot;
+
+#[derive(Component)]
+pub struct TutorialText;
+
+#[derive(Component)]
+pub struct TutorialKey(Offset);
+
+// the first time the game starts it opens on the tutorial, unless a link
+// already picked somewhere else to go
+pub fn open(settings: Res<Settings>, mut next_state: ResMut<NextState<AppState>>) {
+    if !settings.tutorial_done && next_state.0.is_none() {
+        next_state.set(AppState::Tutorial);
+    }
+}
+
+pub fn setup(mut cmd: Commands, settings: Res<Settings>, mut config: ResMut<GameConfig>) {
+    cmd.insert_resource(Tutorial {
+        step: Step::Turn,
+        waiting: true,
+        checkpoint: None,
+        since: 0,
+        config: config.clone(),
+    });
+
+    let mut level = Level::new(WIDTH, HEIGHT);
+    level.spawn = SPAWN;
+    level.facing = Offset::new(1, 0);
+    level.food = Some(FOOD);
+
+    *config = GameConfig {
+        tick_interval: TICK_INTERVAL,
+        speed_ramp: 0.0,
+        time_limit: None,
+        seed: Some(0),
+        wrap: false,
+        players: 1,
+        opponents: 0,
+        lives: 1,
+        ..default()
+    };
+    level.apply(&mut config);
+
+    cmd.spawn((
+        TutorialRoot,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                bottom: Val::Px(40.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            TutorialText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.0,
+                    color: SELECTED,
+                    ..default()
+                },
+            )
+            .with_text_justify(JustifyText::Center),
+        ));
+
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(16.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                for (key, offset) in settings.bindings.controls()[0] {
+                    row.spawn((
+                        TutorialKey(offset),
+                        TextBundle::from_section(
+                            key_name(key),
+                            TextStyle {
+                                font_size: 24.0,
+                                color: UNSELECTED,
+                                ..default()
+                            },
+                        ),
+                    ));
+                }
+            });
+
+        parent.spawn(TextBundle::from_section(
+            "escape to skip the tutorial",
+            TextStyle {
+                font_size: 16.0,
+                color: UNSELECTED,
+                ..default()
+            },
+        ));
+    });
+}
+
+// finished or skipped, the tutorial doesn't open on its own again
+pub fn cleanup(
+    mut cmd: Commands,
+    tutorial: Res<Tutorial>,
+    mut config: ResMut<GameConfig>,
+    mut settings: ResMut<Settings>,
+    roots: Query<Entity, With<TutorialRoot>>,
+) {
+    for entity in roots.iter() {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    *config = tutorial.config.clone();
+
+    if !settings.tutorial_done {
+        settings.tutorial_done = true;
+        settings.save();
+    }
+
+    cmd.remove_resource::<Tutorial>();
+}
+
+// only player one's keys steer, and while the snake is held only the turn
+// it's waiting for lets it go
+pub fn steer(
+    input: Res<ButtonInput<KeyCode>>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    mut tutorial: ResMut<Tutorial>,
+    mut game: ResMut<Game>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(KeyCode::Escape)
+        || (tutorial.step == Step::Done && input.just_pressed(KeyCode::Enter))
+    {
+        next_state.set(AppState::Menu);
+        return;
+    }
+
+    if tutorial.step == Step::Done {
+        return;
+    }
+
+    for (key, offset) in settings.bindings.controls()[0] {
+        if !input.just_pressed(key) || (tutorial.waiting && offset != TURN) {
+            continue;
+        }
+
+        tutorial.waiting = false;
+        game.push_input(&config, PLAYER, offset);
+    }
+}
+
+// the tick only runs once the snake has been let go
+// bevy checks this even outside the tutorial, where there's no `Tutorial`
+pub fn moving(tutorial: Option<Res<Tutorial>>) -> bool {
+    tutorial.is_some_and(|t| !t.waiting && t.step != Step::Done)
+}
+
+pub fn advance(
+    mut cmd: Commands,
+    spawner: Res<Spawner>,
+    config: Res<GameConfig>,
+    mut toast: Toasts,
+    mut tutorial: ResMut<Tutorial>,
+    mut game: ResMut<Game>,
+) {
+    if game.is_added() {
+        game.countdown = None;
+        tutorial.checkpoint = Some((Step::Turn, game.snapshot()));
+    }
+
+    if game.dead {
+        if let Some((step, snapshot)) = tutorial.checkpoint.clone() {
+            game.restore(&mut cmd, &spawner, &config, snapshot);
+            game.countdown = None;
+            tutorial.step = step;
+            tutorial.waiting = true;
+        }
+
+        toast.show("ouch, try that again");
+        return;
+    }
+
+    match tutorial.step {
+        Step::Turn if !tutorial.waiting => tutorial.step = Step::Food,
+        Step::Food if game.food_eaten > 0 => {
+            coil(&mut cmd, &spawner, &mut game);
+
+            tutorial.step = Step::Body;
+            tutorial.waiting = true;
+            tutorial.since = game.replay.ticks;
+            tutorial.checkpoint = Some((Step::Body, game.snapshot()));
+        }
+        Step::Body if game.replay.ticks >= tutorial.since + CLEAR_TICKS => {
+            tutorial.step = Step::Done;
+        }
+        _ => {}
+    }
+}
+
+// swaps the player's snake for one about to run into itself, with the food
+// cleared away so there's nothing else to look at
+fn coil(cmd: &mut Commands, spawner: &Spawner, game: &mut Game) {
+    if let Some(food) = game.food.take() {
+        cmd.entity(food.entity).despawn();
+    }
+
+    let snake = &mut game.snakes[PLAYER];
+
+    for node in snake.nodes.drain(..) {
+        cmd.entity(node.entity).despawn();
+    }
+
+    snake.nodes = COIL
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, y))| spawner.new_node(cmd, Position::new(x, y), Controller::Player(0), i))
+        .collect();
+    snake.facing = Offset::new(1, 0);
+    snake.input_queue.clear();
+    snake.last_tail = None;
+}
+
+pub fn refresh(
+    settings: Res<Settings>,
+    tutorial: Res<Tutorial>,
+    mut texts: Query<&mut Text, With<TutorialText>>,
+    mut keys: Query<(&TutorialKey, &mut Text), Without<TutorialText>>,
+) {
+    let turn = settings.bindings.controls()[0]
+        .iter()
+        .find(|&&(_, offset)| offset == TURN)
+        .map(|&(key, _)| key_name(key))
+        .unwrap_or_default();
+
+    let value = match tutorial.step {
+        Step::Turn => format!(
+            "the snake moves on its own, you only steer it\npress {turn} to turn towards the food"
+        ),
+        Step::Food => "eat the food to grow, every piece is worth points".to_owned(),
+        Step::Body if tutorial.waiting => format!(
+            "your own body is as solid as the edge of the board\npress {turn} to turn away before you run into it"
+        ),
+        Step::Body => "keep clear of it...".to_owned(),
+        Step::Done => "that's all there is to it\nenter to go to the menu".to_owned(),
+    };
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+
+    for (key, mut text) in keys.iter_mut() {
+        let color = if !tutorial.waiting || key.0 == TURN {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
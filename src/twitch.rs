@@ -0,0 +1,124 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use bevy::prelude::*;
+
+use crate::{Game, GameConfig, Offset};
+
+const SERVER: &str = "irc.chat.twitch.tv:6667";
+// twitch lets anyone read chat anonymously under a justinfan nick
+const NICK: &str = "justinfan31337";
+
+const DIRECTIONS: [(&str, Offset); 4] = [
+    ("up", Offset::new(0, -1)),
+    ("down", Offset::new(0, 1)),
+    ("right", Offset::new(1, 0)),
+    ("left", Offset::new(-1, 0)),
+];
+
+// chat plays snake: a channel's chat votes up, down, left or right, and once
+// a tick the direction with the most votes steers the player. each chatter's
+// latest vote is the one that counts
+#[derive(Resource)]
+pub struct Chat {
+    votes: Arc<Mutex<HashMap<String, usize>>>,
+    // the tick the votes were last counted on
+    tick: u32,
+}
+
+impl Chat {
+    pub fn connect(channel: &str) -> Result<Self, String> {
+        let channel = channel.trim_start_matches('#').to_lowercase();
+
+        let mut stream =
+            TcpStream::connect(SERVER).map_err(|e| format!("failed to reach twitch chat: {e}"))?;
+
+        write!(stream, "NICK {NICK}\r\nJOIN #{channel}\r\n")
+            .map_err(|e| format!("failed to join #{channel}: {e}"))?;
+
+        let votes = Arc::<Mutex<HashMap<String, usize>>>::default();
+        let tally = votes.clone();
+
+        thread::spawn(move || {
+            if let Err(e) = listen(stream, &tally) {
+                warn!("twitch chat disconnected: {e}");
+            }
+        });
+
+        Ok(Self { votes, tick: 0 })
+    }
+
+    // a channel's worth of players isn't one player's score
+    pub fn start_round(&mut self, game: &mut Game) {
+        game.ranked = false;
+        self.tick = game.replay.ticks;
+        self.votes.lock().unwrap().clear();
+    }
+}
+
+fn listen(mut stream: TcpStream, votes: &Mutex<HashMap<String, usize>>) -> std::io::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+
+    for line in reader.lines() {
+        let line = line?;
+
+        if let Some(server) = line.strip_prefix("PING ") {
+            write!(stream, "PONG {server}\r\n")?;
+            continue;
+        }
+
+        // :nick!nick@nick.tmi.twitch.tv PRIVMSG #channel :message
+        let Some((prefix, rest)) = line.strip_prefix(':').and_then(|l| l.split_once(' ')) else {
+            continue;
+        };
+        let Some((_, message)) = rest
+            .strip_prefix("PRIVMSG ")
+            .and_then(|r| r.split_once(" :"))
+        else {
+            continue;
+        };
+
+        let nick = prefix.split('!').next().unwrap_or(prefix);
+        let message = message.trim().to_lowercase();
+
+        if let Some(direction) = DIRECTIONS.iter().position(|&(name, _)| name == message) {
+            votes.lock().unwrap().insert(nick.to_owned(), direction);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn vote(config: Res<GameConfig>, mut game: ResMut<Game>, mut chat: ResMut<Chat>) {
+    if game.is_over() || game.paused || chat.tick == game.replay.ticks {
+        return;
+    }
+
+    chat.tick = game.replay.ticks;
+
+    let mut counts = [0; DIRECTIONS.len()];
+    for (_, direction) in chat.votes.lock().unwrap().drain() {
+        counts[direction] += 1;
+    }
+
+    // ties go to whichever direction comes first
+    let Some((direction, _)) = counts
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .max_by_key(|&(i, &count)| (count, std::cmp::Reverse(i)))
+    else {
+        return;
+    };
+
+    let player = game.active;
+    let offset = game
+        .controls
+        .transform(config.confusion, DIRECTIONS[direction].1);
+    game.push_input(&config, player, offset);
+}
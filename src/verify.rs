@@ -0,0 +1,155 @@
+use std::{fmt, time::Duration};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    check_start_room,
+    replay::{Playback, Replay},
+    AppState, Game, GameConfig, SnakePlugin, MIN_LIVE_SIZE,
+};
+
+// the longest replay worth playing out, about an hour at the fastest speed,
+// and the most rewinds one can take, each of which plays ticks over again
+const MAX_TICKS: u32 = 360_000;
+const MAX_REWINDS: usize = 16;
+
+// what a replay comes to when it's played out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FinalState {
+    pub score: u32,
+    pub length: usize,
+    pub ticks: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    // a turn for a player or direction the replay's board doesn't have
+    InvalidTurn { tick: u32 },
+    // a board the game can't be played on
+    InvalidSetup(String),
+    // the game was still going when it should have been long over
+    Unfinished { ticks: u32 },
+    ScoreMismatch { claimed: u32, actual: u32 },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidTurn { tick } => write!(f, "invalid turn on tick {tick}"),
+            Self::InvalidSetup(reason) => write!(f, "invalid setup: {reason}"),
+            Self::Unfinished { ticks } => write!(f, "replay still running after {ticks} ticks"),
+            Self::ScoreMismatch { claimed, actual } => {
+                write!(
+                    f,
+                    "claimed a score of {claimed} but the replay scores {actual}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+// the game plugin on `MinimalPlugins` without a window or renderer, with time
+// only moving by `frame` each update
+pub(crate) fn headless(config: GameConfig, frame: Duration) -> App {
+    let mut app = App::new();
+
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(frame))
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<ButtonInput<MouseButton>>()
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<ColorMaterial>>()
+        .init_resource::<Assets<Image>>()
+        .insert_resource(ClearColor(Color::WHITE))
+        .insert_resource(config)
+        .add_plugins(SnakePlugin::default());
+
+    app.update();
+
+    app
+}
+
+// plays a replay through the real game systems as fast as they'll go, for a
+// leaderboard backend to check a submitted score against. time stands still
+// and every update runs exactly one tick, so nothing depends on frame timing
+pub fn verify_replay(replay: &Replay) -> Result<FinalState, VerifyError> {
+    let config = replay.config();
+    let directions = config.topology.get().directions().len();
+
+    if config.width < MIN_LIVE_SIZE || config.height < MIN_LIVE_SIZE {
+        return Err(VerifyError::InvalidSetup(format!(
+            "the board is {}x{}, the minimum is {MIN_LIVE_SIZE}x{MIN_LIVE_SIZE}",
+            config.width, config.height
+        )));
+    }
+
+    check_start_room(&config).map_err(VerifyError::InvalidSetup)?;
+
+    if replay.ticks > MAX_TICKS {
+        return Err(VerifyError::InvalidSetup(format!(
+            "{} ticks is more than the {MAX_TICKS} a replay may run for",
+            replay.ticks
+        )));
+    }
+
+    if replay.rewinds.len() > MAX_REWINDS {
+        return Err(VerifyError::InvalidSetup(format!(
+            "{} rewinds is more than the {MAX_REWINDS} a replay may take",
+            replay.rewinds.len()
+        )));
+    }
+
+    if let Some(level) = config.level.as_ref() {
+        level
+            .validate()
+            .map_err(|e| VerifyError::InvalidSetup(format!("the level {e}")))?;
+    }
+
+    if let Some(mask) = config.mask.as_ref() {
+        if !mask.fits(config.players + config.opponents) {
+            return Err(VerifyError::InvalidSetup(
+                "the mask has no room for every snake".to_owned(),
+            ));
+        }
+    }
+
+    if let Some(&(tick, _, _)) = replay.turns.iter().find(|&&(_, player, direction)| {
+        player as usize >= config.players || direction as usize >= directions
+    }) {
+        return Err(VerifyError::InvalidTurn { tick });
+    }
+
+    let mut app = headless(config, Duration::ZERO);
+
+    app.insert_resource(Playback(replay.clone()));
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Watching);
+
+    // going back to a rewind checkpoint plays some ticks over again
+    let limit = (replay.ticks as usize + 1) * (replay.rewinds.len() + 1);
+
+    for _ in 0..=limit {
+        app.update();
+
+        let mut game = app.world.resource_mut::<Game>();
+
+        if game.is_over() {
+            return Ok(FinalState {
+                score: game.score,
+                length: game.player().nodes.len(),
+                ticks: game.replay.ticks,
+            });
+        }
+
+        game.countdown = None;
+        let duration = game.tick_timer.duration();
+        game.tick_timer.set_elapsed(duration);
+    }
+
+    Err(VerifyError::Unfinished {
+        ticks: app.world.resource::<Game>().replay.ticks,
+    })
+}
@@ -0,0 +1,37 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use wasm_bindgen::{prelude::*, JsCast};
+
+static HIDDEN: AtomicBool = AtomicBool::new(false);
+// hidden tabs get no frames, so a tab hidden and shown again in between two of
+// them is only ever seen through this
+static WAS_HIDDEN: AtomicBool = AtomicBool::new(false);
+
+pub fn listen() {
+    let document = web_sys::window().unwrap().document().unwrap();
+
+    let callback = Closure::<dyn FnMut()>::new(|| {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let hidden = document.hidden();
+        HIDDEN.store(hidden, Ordering::Relaxed);
+
+        if hidden {
+            WAS_HIDDEN.store(true, Ordering::Relaxed);
+        }
+    });
+
+    document
+        .add_event_listener_with_callback("visibilitychange", callback.as_ref().unchecked_ref())
+        .unwrap();
+
+    callback.forget();
+}
+
+pub fn is_hidden() -> bool {
+    HIDDEN.load(Ordering::Relaxed)
+}
+
+// whether the tab was hidden at all since the last call
+pub fn take_hidden() -> bool {
+    WAS_HIDDEN.swap(false, Ordering::Relaxed)
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::KeyCode;
+use mfro_snake::testing::Harness;
+
+#[test]
+fn moves_forward_every_tick() {
+    let mut harness = Harness::new(1);
+    let (x, y) = harness.head();
+
+    harness.tick();
+    assert_eq!(harness.head(), (x + 1, y));
+
+    harness.tick();
+    assert_eq!(harness.head(), (x + 2, y));
+}
+
+#[test]
+fn turns_on_key_press() {
+    let mut harness = Harness::new(1);
+    let (x, y) = harness.head();
+
+    harness.press(KeyCode::ArrowDown);
+    harness.tick();
+
+    assert_eq!(harness.head(), (x, y + 1));
+}
+
+#[test]
+fn ignores_reversing() {
+    let mut harness = Harness::new(1);
+    let (x, y) = harness.head();
+
+    harness.press(KeyCode::ArrowLeft);
+    harness.tick();
+
+    assert_eq!(harness.head(), (x + 1, y));
+    assert!(!harness.is_dead());
+}
+
+#[test]
+fn grows_and_respawns_food_when_eating() {
+    let mut harness = Harness::new(1);
+    let (x, y) = harness.head();
+    let length = harness.snake().len();
+
+    harness.set_food((x + 1, y));
+    harness.tick();
+
+    assert_eq!(harness.snake().len(), length + 1);
+    assert!(harness.score() > 0);
+
+    let food = harness.food().unwrap();
+    let (width, height) = harness.size();
+
+    assert!(food.0 < width && food.1 < height);
+    assert!(!harness.snake().contains(&food));
+    assert!(!harness.walls().contains(&food));
+}
+
+#[test]
+fn dies_on_the_board_edge() {
+    let mut harness = Harness::empty(1);
+    let (width, _) = harness.size();
+    let (x, _) = harness.head();
+
+    harness.ticks(width - x);
+
+    assert!(harness.is_dead());
+    assert_eq!(harness.deaths(), 1);
+    assert_eq!(harness.death_causes(), ["ran off the edge"]);
+}
+
+#[test]
+fn conveyors_push_the_snake_without_turning_it() {
+    let mut harness = Harness::empty(1);
+    let (x, y) = harness.head();
+
+    harness.add_conveyor((x + 1, y), (0, 1));
+    harness.tick();
+    assert_eq!(harness.head(), (x + 1, y + 1));
+
+    harness.tick();
+    assert_eq!(harness.head(), (x + 2, y + 1));
+}
+
+#[test]
+fn dies_on_a_wall() {
+    let mut harness = Harness::empty(1);
+    let (x, y) = harness.head();
+
+    harness.add_wall((x + 2, y));
+    harness.ticks(2);
+
+    assert!(harness.is_dead());
+    assert_eq!(harness.death_causes(), ["hit a wall"]);
+}
+
+#[test]
+fn dies_on_its_own_body() {
+    let mut harness = Harness::new(1);
+
+    for key in [KeyCode::ArrowDown, KeyCode::ArrowLeft, KeyCode::ArrowUp] {
+        harness.press(key);
+        harness.tick();
+    }
+
+    assert!(harness.is_dead());
+    assert_eq!(harness.death_causes(), ["ran into itself"]);
+}
+
+#[test]
+fn restores_a_snapshot() {
+    let mut harness = Harness::new(1);
+    harness.tick();
+
+    let snapshot = harness.snapshot();
+    let (snake, food, score) = (harness.snake(), harness.food(), harness.score());
+
+    harness.press(KeyCode::ArrowDown);
+    harness.ticks(2);
+    let ahead = harness.snake();
+
+    harness.restore(snapshot.clone());
+    assert_eq!(harness.snake(), snake);
+    assert_eq!(harness.food(), food);
+    assert_eq!(harness.score(), score);
+
+    // and plays on from there the same way again
+    harness.press(KeyCode::ArrowDown);
+    harness.ticks(2);
+    assert_eq!(harness.snake(), ahead);
+
+    harness.restore(snapshot);
+    assert_eq!(harness.snake(), snake);
+}
@@ -0,0 +1,40 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use mfro_snake::testing::reencode_level;
+
+// a 4x3 board with the spawn at 2,1 facing right, no food, a wall in the
+// bottom right corner, no enemies or terrain, one key at 0,0 and no doors
+const HEADER: [u8; 6] = [4, 3, 2, 1, 1, 0];
+const BODY: [u8; 10] = [0, 11, 1, 0, 0, 1, 0, 0, 0, 0];
+
+fn code(version: u8, header: [u8; 6], objectives: &[u8]) -> String {
+    let bytes: Vec<u8> = [version]
+        .into_iter()
+        .chain(header)
+        .chain(BODY)
+        .chain(objectives.iter().copied())
+        .collect();
+
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[test]
+fn upgrades_a_version_4_code() {
+    let upgraded = reencode_level(&code(4, HEADER, &[])).unwrap();
+
+    // the same level, with no objectives
+    assert_eq!(upgraded, code(5, HEADER, &[0, 0, 0, 0]));
+    assert_eq!(reencode_level(&upgraded), Some(upgraded));
+}
+
+#[test]
+fn keeps_objectives_through_a_round_trip() {
+    // grow to 5, eat 3, within 12.5 seconds, without touching a wall
+    let original = code(5, HEADER, &[5, 3, 125, 1]);
+
+    assert_eq!(reencode_level(&original), Some(original));
+}
+
+#[test]
+fn rejects_a_spawn_outside_the_board() {
+    assert_eq!(reencode_level(&code(4, [4, 3, 9, 1, 1, 0], &[])), None);
+}
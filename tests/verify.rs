@@ -0,0 +1,163 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bevy::prelude::KeyCode;
+use mfro_snake::{
+    submission::Submission,
+    testing::{parse_replay, write_replay, Harness},
+    verify_replay, Replay, VerifyError,
+};
+
+// a short round that turns once and runs into the bottom edge
+fn played() -> (Replay, u32) {
+    let mut harness = Harness::new(1);
+
+    harness.press(KeyCode::ArrowDown);
+
+    while !harness.is_dead() {
+        harness.tick();
+    }
+
+    (harness.replay(), harness.score())
+}
+
+// a submission as it would come in from a client, without a signature since
+// test builds have no key
+fn submission(replay: &Replay, score: u32) -> Submission {
+    let text = format!(
+        "(replay:{:?},score:{score},signature:\"\")",
+        write_replay(replay)
+    );
+
+    Submission::decode(&URL_SAFE_NO_PAD.encode(text)).unwrap()
+}
+
+#[test]
+fn a_recorded_round_verifies() {
+    let (replay, score) = played();
+    let state = verify_replay(&replay).unwrap();
+
+    assert_eq!(state.score, score);
+    assert_eq!(state.ticks, replay.ticks);
+    assert!(submission(&replay, score).check().is_ok());
+}
+
+#[test]
+fn rejects_a_claimed_score_the_replay_does_not_reach() {
+    let (replay, score) = played();
+
+    assert_eq!(
+        submission(&replay, score + 10).check(),
+        Err(VerifyError::ScoreMismatch {
+            claimed: score + 10,
+            actual: score,
+        })
+    );
+}
+
+#[test]
+fn rejects_a_turn_for_a_missing_player() {
+    let (mut replay, _) = played();
+    replay.turns.push((1, 7, 0));
+
+    assert_eq!(
+        verify_replay(&replay),
+        Err(VerifyError::InvalidTurn { tick: 1 })
+    );
+}
+
+#[test]
+fn rejects_a_level_spawning_off_the_board() {
+    let (replay, _) = played();
+    let text = write_replay(&replay).replace(
+        "level:None",
+        "level:Some((width:10,height:10,walls:[],spawn:(x:50,y:50),facing:(x:1,y:0),food:None))",
+    );
+    let replay = parse_replay(&text).unwrap();
+
+    assert!(matches!(
+        verify_replay(&replay),
+        Err(VerifyError::InvalidSetup(_))
+    ));
+}
+
+#[test]
+fn rejects_a_mask_without_room_for_every_snake() {
+    let (replay, _) = played();
+    let mut rows = vec!["\"##########\""];
+    rows.resize(8, "\"..........\"");
+
+    // two players, but only one row a snake can start on
+    let text = write_replay(&replay)
+        .replace("mode:Classic", "mode:Coop")
+        .replace("mask:None", &format!("mask:Some([{}])", rows.join(",")));
+    let replay = parse_replay(&text).unwrap();
+
+    assert!(matches!(
+        verify_replay(&replay),
+        Err(VerifyError::InvalidSetup(_))
+    ));
+}
+
+#[test]
+fn refuses_a_mask_too_small_to_play_on() {
+    let (replay, _) = played();
+    let text = write_replay(&replay).replace("mask:None", "mask:Some([\"#\"])");
+
+    assert!(parse_replay(&text).is_none());
+}
+
+#[test]
+fn rejects_a_board_smaller_than_the_minimum() {
+    let (mut replay, _) = played();
+    replay.size = Some((0, 0));
+
+    assert!(matches!(
+        verify_replay(&replay),
+        Err(VerifyError::InvalidSetup(_))
+    ));
+}
+
+#[test]
+fn rejects_a_board_without_room_for_every_start() {
+    let (replay, _) = played();
+    let text = write_replay(&replay).replace("mode:Classic", "mode:Coop");
+    let mut replay = parse_replay(&text).unwrap();
+    replay.size = Some((30, 9));
+
+    assert!(matches!(
+        verify_replay(&replay),
+        Err(VerifyError::InvalidSetup(_))
+    ));
+}
+
+#[test]
+fn rejects_more_ticks_than_a_replay_may_run_for() {
+    let (mut replay, _) = played();
+    replay.ticks = u32::MAX;
+
+    assert!(matches!(
+        verify_replay(&replay),
+        Err(VerifyError::InvalidSetup(_))
+    ));
+}
+
+#[test]
+fn rejects_more_rewinds_than_a_replay_may_take() {
+    let (mut replay, _) = played();
+    replay.rewinds = vec![1; 1000];
+
+    assert!(matches!(
+        verify_replay(&replay),
+        Err(VerifyError::InvalidSetup(_))
+    ));
+}
+
+#[test]
+fn rejects_a_submission_whose_replay_does_not_parse() {
+    let text = "(replay:\"(ticks:\",score:0,signature:\"\")";
+    let submission = Submission::decode(&URL_SAFE_NO_PAD.encode(text)).unwrap();
+
+    assert!(matches!(
+        submission.check(),
+        Err(VerifyError::InvalidSetup(_))
+    ));
+}